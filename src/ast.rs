@@ -1,11 +1,18 @@
+use crate::diagnostic::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Schema {
     pub entities: Vec<Entity>,
+    pub enums: Vec<Enum>,
     pub relationships: Vec<Relationship>,
     pub views: Vec<View>,
     /// Grid-based layout arrangement: rows of entity names
     /// Each row represents a level, columns represent horizontal order
-    pub arrangement: Option<Vec<Vec<String>>>,
+    pub arrangement: Option<Vec<Vec<Name>>>,
+    /// File-wide hints such as `@hint.direction`
+    pub hints: Vec<Hint>,
+    /// `import "other.erd"` items, resolved by the loader
+    pub imports: Vec<Import>,
 }
 
 impl Schema {
@@ -16,6 +23,18 @@ impl Schema {
     pub fn view_names(&self) -> Vec<&str> {
         self.views.iter().map(|v| v.name.as_str()).collect()
     }
+
+    pub fn find_enum(&self, name: &str) -> Option<&Enum> {
+        self.enums.iter().find(|e| e.name == name)
+    }
+
+    /// Value of the file-wide hint `key`, if it was given as a word or string.
+    pub fn hint_str(&self, key: &str) -> Option<&str> {
+        self.hints.iter().rev().find(|h| h.key == key).and_then(|h| match &h.value {
+            HintValue::Ident(s) | HintValue::Str(s) => Some(s.as_str()),
+            HintValue::Int(_) => None,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,13 +43,111 @@ pub struct Entity {
     pub columns: Vec<Column>,
     pub constraints: Vec<Constraint>,
     pub hints: Vec<Hint>,
+    /// `/// text` lines above the entity and the string after its name
+    pub doc: Option<String>,
+    /// `extends Parent`, when the entity is a subtype
+    pub extends: Option<Extends>,
+    pub span: Span,
+}
+
+/// `extends Party [disjoint | overlapping] [total | partial]` after an
+/// entity's name. The markers describe the supertype's set of subtypes, so
+/// subtypes of one supertype should agree on them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extends {
+    pub parent: Name,
+    pub disjointness: Option<Disjointness>,
+    pub completeness: Option<Completeness>,
+}
+
+/// Whether a supertype's row may belong to more than one subtype.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disjointness {
+    Disjoint,
+    Overlapping,
+}
+
+impl Disjointness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disjoint => "disjoint",
+            Self::Overlapping => "overlapping",
+        }
+    }
+}
+
+/// Whether every supertype row belongs to some subtype.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completeness {
+    Total,
+    Partial,
+}
+
+impl Completeness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Total => "total",
+            Self::Partial => "partial",
+        }
+    }
+}
+
+/// `enum Status { pending, paid }`: a type that columns can name, holding
+/// one of a fixed set of values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    pub name: String,
+    /// In declaration order
+    pub values: Vec<String>,
+    /// `/// text` lines above the enum
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Column {
     pub name: String,
-    pub typ: String,
+    pub typ: ColumnType,
     pub modifiers: Vec<ColumnModifier>,
+    /// `/// text` lines above the column
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+/// A column type with its parameters, as in `varchar(255)`, `decimal(10, 2)`
+/// or `text[]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnType {
+    pub name: String,
+    /// Length, precision and scale, or whatever else was written in the
+    /// parentheses, in order.
+    pub args: Vec<String>,
+    /// Number of `[]` suffixes.
+    pub array_dims: usize,
+}
+
+impl ColumnType {
+    /// A plain type without parameters.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            args: Vec::new(),
+            array_dims: 0,
+        }
+    }
+}
+
+impl std::fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.args.is_empty() {
+            write!(f, "({})", self.args.join(", "))?;
+        }
+        for _ in 0..self.array_dims {
+            write!(f, "[]")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -39,6 +156,8 @@ pub enum ColumnModifier {
     NotNull,
     Unique,
     Default(String),
+    /// `check "quantity > 0"`: the condition, as written
+    Check(String),
     Fk { target: String, column: String },
 }
 
@@ -52,9 +171,11 @@ pub enum Constraint {
         on_delete: Option<String>,
         on_update: Option<String>,
     },
+    /// `index(a, b) [unique, name = ix]`
     Index {
         columns: Vec<String>,
         name: Option<String>,
+        unique: bool,
     },
 }
 
@@ -62,6 +183,7 @@ pub enum Constraint {
 pub struct Hint {
     pub key: String,
     pub value: HintValue,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -71,26 +193,143 @@ pub enum HintValue {
     Ident(String),
 }
 
+/// `import "billing.erd"`: the path as written, relative to the importing file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub path: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Relationship {
     pub left: String,
+    /// `User.id` in `User.id 1 -- * Order.user_id`: the column the edge
+    /// attaches to
+    pub left_column: Option<String>,
     pub left_cardinality: Cardinality,
     pub right: String,
+    pub right_column: Option<String>,
     pub right_cardinality: Cardinality,
+    /// `via OrderTag` in `Order * -- * Tag via OrderTag`: the associative
+    /// entity that implements a many-to-many relationship
+    pub via: Option<String>,
     pub label: Option<String>,
-    pub role: Option<String>,
+    /// `as manager, reports`: the role the left entity plays, then the
+    /// right's; `_` on either side leaves that end unnamed
+    pub left_role: Option<String>,
+    pub right_role: Option<String>,
+    /// `identifying` or `non_identifying`; when unset, it is identifying if
+    /// the foreign key behind it is part of the child's primary key
+    pub identifying: Option<bool>,
+    /// `[dashed, color = red, width = 2]` at the end of the line
+    pub attributes: Vec<Attribute>,
+    /// `/// text` lines above the relationship
+    pub doc: Option<String>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Cardinality {
-    One,        // 1
-    ZeroOrOne,  // 0..1
-    Many,       // *
-    OneOrMore,  // 1..*
+/// One entry of a relationship's `[...]` list: a bare flag such as `dashed`,
+/// or `name = value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    pub value: Option<HintValue>,
+    pub span: Span,
+}
+
+/// How many rows may sit at one end of a relationship: `min..max`, where a
+/// `max` of `None` is `*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cardinality {
+    pub min: u32,
+    pub max: Option<u32>,
+}
+
+impl Cardinality {
+    pub const ONE: Self = Self::range(1, Some(1)); // 1
+    pub const ZERO_OR_ONE: Self = Self::range(0, Some(1)); // 0..1
+    pub const MANY: Self = Self::range(0, None); // *
+    pub const ONE_OR_MORE: Self = Self::range(1, None); // 1..*
+
+    pub const fn range(min: u32, max: Option<u32>) -> Self {
+        Self { min, max }
+    }
+
+    /// True when the end may hold more than one row.
+    pub fn is_many(&self) -> bool {
+        self.max.is_none_or(|max| max > 1)
+    }
+
+    /// True when the end may hold no row at all.
+    pub fn is_optional(&self) -> bool {
+        self.min == 0
+    }
+}
+
+/// The shortest form that parses back to the same range: `1`, `0..1`, `*`,
+/// `1..*`, `3`, `2..5`.
+impl std::fmt::Display for Cardinality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (0, None) => write!(f, "*"),
+            (min, None) => write!(f, "{}..*", min),
+            (min, Some(max)) if min == max => write!(f, "{}", min),
+            (min, Some(max)) => write!(f, "{}..{}", min, max),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct View {
     pub name: String,
-    pub includes: Vec<String>,
+    /// `view b extends a`: the view whose entities this one starts from
+    pub extends: Option<Name>,
+    /// `include` and `exclude` lines, applied in order
+    pub rules: Vec<ViewRule>,
+    /// An `@hint.arrangement` of the view's own, used instead of the file's
+    pub arrangement: Option<Vec<Vec<Name>>>,
+    /// Hints such as `@hint.detail` that apply when the view is rendered,
+    /// over the file's
+    pub hints: Vec<Hint>,
+    pub span: Span,
+}
+
+/// One `include` or `exclude` line of a view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewRule {
+    pub exclude: bool,
+    pub selectors: Vec<Selector>,
+}
+
+/// What an `include` or `exclude` line picks out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    /// `Order`, or a pattern where `*` stands for any run of characters,
+    /// such as `Order*` or `*_audit`
+    Pattern(Name),
+    /// `neighbours(Order, 2)`: the entities the pattern matches and those
+    /// within `depth` links of them; `depth` defaults to 1
+    Neighbours { pattern: Name, depth: usize },
+    /// `group "billing"`: the entities with that `@hint.group`
+    Group(Name),
+}
+
+/// A name that refers to something defined elsewhere, such as an entity
+/// listed in a view or an arrangement, with where it was written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Name {
+    pub name: String,
+    pub span: Span,
+}
+
+impl Name {
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.name == *other
+    }
 }
@@ -0,0 +1,229 @@
+//! Source locations and the caret-style reports printed for them.
+
+use std::fmt::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+/// A byte range in the source, with the line and column where it starts.
+///
+/// The default span is all zeros and stands for "no location", as for
+/// schemas converted from SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    /// 1-based line of `start`.
+    pub line: usize,
+    /// 1-based column of `start`, counted in characters.
+    pub column: usize,
+    /// Which file the span is in, as an index into the files a loader read.
+    /// 0 is the file being rendered.
+    pub file: usize,
+}
+
+impl Span {
+    /// The span from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.start),
+            ..self
+        }
+    }
+
+    /// True for the "no location" span.
+    pub fn is_unknown(&self) -> bool {
+        self.line == 0
+    }
+}
+
+/// How serious a [`Diagnostic`] is. Errors stop a render; warnings do not.
+/// Notes only add context to another diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A problem found in a schema that parsed, such as a reference to an entity
+/// that is never defined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    /// Other places that explain the problem, such as an earlier definition.
+    pub notes: Vec<(String, Span)>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Attach a note pointing at `span`.
+    pub fn with_note(mut self, message: impl Into<String>, span: Span) -> Self {
+        self.notes.push((message.into(), span));
+        self
+    }
+
+    /// Report the diagnostic and its notes against `source`, as [`render`]
+    /// does.
+    pub fn render(&self, file: &str, source: &str) -> String {
+        self.render_in(|_| (file, source))
+    }
+
+    /// Report the diagnostic and its notes, each against the file its span is
+    /// in. `file_of` maps a span's `file` to that file's name and source.
+    pub fn render_in<'a>(&self, file_of: impl Fn(usize) -> (&'a str, &'a str)) -> String {
+        let (file, source) = file_of(self.span.file);
+        let mut out = render(file, source, self.severity, self.span, &self.message);
+        for (message, span) in &self.notes {
+            let (file, source) = file_of(span.file);
+            out.push_str(&render(file, source, Severity::Note, *span, message));
+        }
+        out
+    }
+}
+
+/// Render a message as `file:line:col`, followed by the source line it points
+/// at and a caret underline:
+///
+/// ```text
+/// error: Unexpected `(`, expected end of line
+///  --> schema.erd:3:17
+///   |
+/// 3 |     name varchar(255)
+///   |                 ^
+/// ```
+pub fn render(file: &str, source: &str, severity: Severity, span: Span, message: &str) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}: {}", severity, message).unwrap();
+
+    if span.is_unknown() {
+        writeln!(out, " --> {}", file).unwrap();
+        return out;
+    }
+
+    let gutter = span.line.to_string().len();
+    let pad = " ".repeat(gutter);
+    writeln!(out, "{}--> {}:{}:{}", pad, file, span.line, span.column).unwrap();
+
+    let Some(line) = source.lines().nth(span.line - 1) else {
+        return out;
+    };
+    let line_start = line.as_ptr() as usize - source.as_ptr() as usize;
+
+    // Measure in display width so the caret lines up under wide characters.
+    let before = &line[..(span.start - line_start).min(line.len())];
+    let underlined = &line[before.len()..(span.end.saturating_sub(line_start)).min(line.len())];
+    let offset = UnicodeWidthStr::width(before);
+    let length = UnicodeWidthStr::width(underlined).max(1);
+
+    writeln!(out, "{} |", pad).unwrap();
+    writeln!(out, "{} | {}", span.line, line).unwrap();
+    writeln!(out, "{} | {}{}", pad, " ".repeat(offset), "^".repeat(length)).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_points_at_column() {
+        let source = "entity User {\n    name varchar(255)\n}\n";
+        let span = Span {
+            start: 30,
+            end: 31,
+            line: 2,
+            column: 17,
+            file: 0,
+        };
+        let report = render("schema.erd", source, Severity::Error, span, "Unexpected token");
+
+        assert_eq!(
+            report,
+            "error: Unexpected token\n \
+             --> schema.erd:2:17\n  \
+             |\n\
+             2 |     name varchar(255)\n  \
+             |                 ^\n"
+        );
+    }
+
+    #[test]
+    fn test_render_aligns_under_wide_characters() {
+        let source = "entity ユーザー ?";
+        let span = Span {
+            start: 20,
+            end: 21,
+            line: 1,
+            column: 13,
+            file: 0,
+        };
+        let report = render("schema.erd", source, Severity::Error, span, "Unexpected character: ?");
+        let caret_line = report.lines().last().unwrap();
+
+        // "entity " (7) + "ユーザー" (8) + " " (1)
+        assert_eq!(caret_line, format!("  | {}^", " ".repeat(16)));
+    }
+
+    #[test]
+    fn test_render_without_location() {
+        let report = render("schema.sql", "", Severity::Error, Span::default(), "no tables");
+        assert_eq!(report, "error: no tables\n --> schema.sql\n");
+    }
+
+    #[test]
+    fn test_render_warning() {
+        let warning = Diagnostic::warning("listed twice", Span::default());
+        assert!(!warning.is_error());
+        assert!(warning.render("schema.erd", "").starts_with("warning: listed twice\n"));
+    }
+
+    #[test]
+    fn test_render_notes_in_their_own_file() {
+        let second = Span {
+            start: 7,
+            end: 11,
+            line: 1,
+            column: 8,
+            file: 0,
+        };
+        let first = Span { file: 1, ..second };
+        let error = Diagnostic::error("defined twice", second).with_note("first defined here", first);
+        let files = [("main.erd", "entity User {}"), ("users.erd", "entity User {}")];
+        let report = error.render_in(|file| files[file]);
+
+        assert!(report.starts_with("error: defined twice\n --> main.erd:1:8\n"));
+        assert!(report.contains("note: first defined here\n --> users.erd:1:8\n"));
+    }
+}
@@ -1,4 +1,5 @@
-use crate::ast::{Cardinality, ColumnModifier, Constraint, Schema};
+use crate::ast::{Cardinality, ColumnModifier, Completeness, Constraint, Entity, HintValue, Relationship, Schema};
+use crate::view::ResolvedView;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DetailLevel {
@@ -6,15 +7,154 @@ pub enum DetailLevel {
     Pk,
     PkFk,
     All,
+    /// All columns, each with its constraints, default and referenced column
+    Full,
+    /// As `Full`, with the entity's indexes and foreign key actions listed
+    /// under its columns
+    Physical,
 }
 
 impl DetailLevel {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "tables" => Some(Self::Tables),
             "pk" => Some(Self::Pk),
             "pk_fk" => Some(Self::PkFk),
             "all" => Some(Self::All),
+            "full" => Some(Self::Full),
+            "physical" => Some(Self::Physical),
+            _ => None,
+        }
+    }
+}
+
+/// Where relationship edges attach to their entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchoring {
+    /// Anywhere along the border facing the other entity
+    #[default]
+    Border,
+    /// Beside the row of the column that implements the relationship
+    Columns,
+}
+
+impl Anchoring {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "border" => Some(Self::Border),
+            "columns" => Some(Self::Columns),
+            _ => None,
+        }
+    }
+}
+
+/// Which declarations draw edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeSource {
+    /// Only `rel` entries
+    #[default]
+    Rel,
+    /// `rel` entries, and every foreign key they leave out
+    Fk,
+}
+
+impl EdgeSource {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "rel" => Some(Self::Rel),
+            "fk" => Some(Self::Fk),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a subtype also lists the columns it inherits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InheritedColumns {
+    /// Only the columns declared on the subtype itself
+    #[default]
+    Hide,
+    /// Its supertypes' columns first, set apart from its own
+    Show,
+}
+
+impl InheritedColumns {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "hide" => Some(Self::Hide),
+            "show" => Some(Self::Show),
+            _ => None,
+        }
+    }
+}
+
+/// How `A * -- * B via AB` relationships are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JoinEntities {
+    /// The join entity between the two, one to many from each
+    #[default]
+    Show,
+    /// One many-to-many edge named after the join entity, which is left out
+    Collapse,
+}
+
+impl JoinEntities {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "show" => Some(Self::Show),
+            "collapse" => Some(Self::Collapse),
+            _ => None,
+        }
+    }
+}
+
+/// Whether enums are drawn as boxes of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnumBoxes {
+    /// Enum-typed columns only show the enum's name
+    #[default]
+    Hide,
+    /// Each enum in use gets a box listing its values, linked to its columns
+    Show,
+}
+
+impl EnumBoxes {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "hide" => Some(Self::Hide),
+            "show" => Some(Self::Show),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a view shows the entities just outside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ghosts {
+    /// Relationships to entities the view leaves out are dropped
+    #[default]
+    Hide,
+    /// Each left-out entity related to a shown one is drawn as a ghost, and
+    /// the relationships crossing the view's boundary run to it
+    Show,
+    /// Every left-out entity is a ghost, with all its relationships: the
+    /// rest of the schema is greyed out rather than hidden
+    All,
+}
+
+impl Ghosts {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "hide" => Some(Self::Hide),
+            "show" => Some(Self::Show),
+            "all" => Some(Self::All),
             _ => None,
         }
     }
@@ -24,6 +164,8 @@ impl DetailLevel {
 pub struct GraphIR {
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
+    /// Groups that have at least one node, in order of first appearance
+    pub groups: Vec<Group>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +176,116 @@ pub struct Node {
     pub level: Option<i64>,
     pub order: Option<i64>,  // Horizontal order within a level (from arrangement)
     pub group: Option<String>,
+    /// The entity's documentation, shown as a tooltip and in its note
+    pub doc: Option<String>,
+    pub kind: NodeKind,
+    /// Identified through a relationship: the child of an identifying one
+    pub weak: bool,
+    /// Columns are drawn as aligned cells with their constraints, default
+    /// and referenced column, as at `DetailLevel::Full`
+    pub detailed: bool,
+    /// Lines of the compartment under the columns: indexes, then foreign
+    /// keys with referential actions. Filled at `DetailLevel::Physical`.
+    pub indexes: Vec<String>,
+    pub style: Style,
+}
+
+/// Colours and lines set on one entity or relationship. Whatever is left
+/// unset is drawn as the stylesheet says.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    /// The entity's body, from `@hint.color`
+    pub fill: Option<String>,
+    /// The entity's header, from `@hint.header`
+    pub header: Option<String>,
+    /// The entity's border, from `@hint.stroke`, or the relationship's line,
+    /// from `color`
+    pub stroke: Option<String>,
+    /// The line's width, from `width`
+    pub width: Option<f64>,
+    /// From `dashed`
+    pub dashed: bool,
+}
+
+impl Style {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn of_entity(entity: &Entity) -> Self {
+        let color = |key: &str| {
+            entity
+                .hints
+                .iter()
+                .rev()
+                .find(|h| h.key == key)
+                .and_then(|h| color_value(&h.value))
+        };
+        Style {
+            fill: color("hint.color"),
+            header: color("hint.header"),
+            stroke: color("hint.stroke"),
+            ..Style::default()
+        }
+    }
+
+    fn of_relationship(rel: &Relationship) -> Self {
+        let mut style = Style::default();
+        for attribute in &rel.attributes {
+            match (attribute.name.as_str(), &attribute.value) {
+                ("dashed", None) => style.dashed = true,
+                ("color", Some(value)) => style.stroke = color_value(value),
+                ("width", Some(value)) => style.width = width_value(value),
+                _ => {}
+            }
+        }
+        style
+    }
+}
+
+/// Whether `s` can be written into the stylesheet as a colour: a name,
+/// `#rgb` or a function such as `rgb(...)`. Anything that could end the
+/// declaration is refused.
+pub fn is_color(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "#(),.% -".contains(c))
+}
+
+/// A colour hint or attribute, which may be quoted or not.
+pub fn color_value(value: &HintValue) -> Option<String> {
+    match value {
+        HintValue::Ident(s) | HintValue::Str(s) if is_color(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// A positive line width, written as a number or, with a fraction, quoted.
+pub fn width_value(value: &HintValue) -> Option<f64> {
+    let width = match value {
+        HintValue::Int(n) => *n as f64,
+        HintValue::Str(s) => s.parse().ok()?,
+        HintValue::Ident(_) => return None,
+    };
+    (width > 0.0 && width.is_finite()).then_some(width)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeKind {
+    #[default]
+    Entity,
+    /// An enum's box; its columns are the enum's values, without types
+    Enum,
+    /// An entity outside the view, drawn greyed out with its name only
+    Ghost,
+}
+
+/// A cluster of nodes sharing `@hint.group`, drawn as one labelled region.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub name: String,
+    /// Fill colour from `@hint.group.NAME.color`; a palette colour when unset
+    pub color: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +294,49 @@ pub struct ColumnIR {
     pub typ: String,
     pub is_pk: bool,
     pub is_fk: bool,
+    pub doc: Option<String>,
+    /// Declared on a supertype, not on the entity itself
+    pub inherited: bool,
+    /// `not null`; a primary key column is never null whether it says so or
+    /// not
+    pub not_null: bool,
+    pub unique: bool,
+    pub default: Option<String>,
+    pub check: Option<String>,
+    /// `Entity.column` of the foreign key the column belongs to
+    pub references: Option<String>,
+}
+
+impl ColumnIR {
+    /// Short markers for the constraints: `NN` for `not null` and `?` for a
+    /// column that may be null (a key column is neither), `UQ` for unique
+    /// and `CK` for a check.
+    pub fn badges(&self) -> Vec<&'static str> {
+        let mut badges = Vec::new();
+        if !self.is_pk {
+            badges.push(if self.not_null { "NN" } else { "?" });
+        }
+        if self.unique {
+            badges.push("UQ");
+        }
+        if self.check.is_some() {
+            badges.push("CK");
+        }
+        badges
+    }
+
+    /// The row's cells at `DetailLevel::Full`: key marker and name, type,
+    /// badges, default and referenced column. Cells with nothing to show are
+    /// empty.
+    pub fn cells(&self) -> Vec<String> {
+        vec![
+            format!("{}{}", if self.is_pk { "◆ " } else { "  " }, self.name),
+            self.typ.clone(),
+            self.badges().join(" "),
+            self.default.as_ref().map(|d| format!("= {}", d)).unwrap_or_default(),
+            self.references.as_ref().map(|r| format!("→ {}", r)).unwrap_or_default(),
+        ]
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -51,78 +346,69 @@ pub struct Edge {
     pub from_cardinality: Cardinality,
     pub to_cardinality: Cardinality,
     pub label: Option<String>,
-    pub role: Option<String>,
+    /// Role names written near the `from` and `to` ends
+    pub from_role: Option<String>,
+    pub to_role: Option<String>,
+    /// Column whose row the `from` end attaches beside
+    pub from_column: Option<String>,
+    pub to_column: Option<String>,
+    pub doc: Option<String>,
+    pub kind: EdgeKind,
+    /// Whether the child's primary key includes the parent's key; unknown
+    /// when neither the relationship nor a foreign key says
+    pub identifying: Option<bool>,
+    /// The join entity, on each half of an `A * -- * B via AB` drawn through
+    /// it
+    pub via: Option<String>,
+    pub style: Style,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeKind {
+    #[default]
+    Relationship,
+    /// From an enum's box to a column of its type, drawn dashed and without
+    /// cardinalities
+    EnumLink,
+    /// From a supertype to one of its subtypes, with the completeness the
+    /// subtypes give it
+    Generalization(Option<Completeness>),
 }
 
 impl GraphIR {
-    pub fn from_schema(schema: &Schema, view: Option<&str>, detail: DetailLevel) -> Self {
+    /// Build the graph of the entities `view` shows, or of every entity.
+    pub fn from_schema(schema: &Schema, view: Option<&ResolvedView>, detail: DetailLevel) -> Self {
         use std::collections::HashMap;
 
-        let included_entities: Vec<&str> = match view.and_then(|name| schema.find_view(name)) {
-            Some(view) => view.includes.iter().map(|s| s.as_str()).collect(),
-            None => schema.entities.iter().map(|e| e.name.as_str()).collect(),
+        let shown = |name: &str| {
+            schema.entities.iter().any(|e| e.name == name) && view.is_none_or(|v| v.contains(name))
         };
 
-        // Build arrangement lookup: entity name -> (level, order)
-        let arrangement_lookup: HashMap<&str, (i64, i64)> = schema
-            .arrangement
-            .as_ref()
+        // Build arrangement lookup: entity name -> (level, order). The view's
+        // own arrangement comes first; rows and places left empty by
+        // entities it does not show close up.
+        let arrangement = view.and_then(|v| v.arrangement.as_ref()).or(schema.arrangement.as_ref());
+        let arrangement_lookup: HashMap<&str, (i64, i64)> = arrangement
             .map(|arr| {
                 arr.iter()
+                    .map(|row| row.iter().map(|name| name.as_str()).filter(|name| shown(name)).collect::<Vec<_>>())
+                    .filter(|row| !row.is_empty())
                     .enumerate()
                     .flat_map(|(level, row)| {
-                        row.iter()
+                        row.into_iter()
                             .enumerate()
-                            .map(move |(order, name)| (name.as_str(), (level as i64, order as i64)))
+                            .map(move |(order, name)| (name, (level as i64, order as i64)))
                     })
                     .collect()
             })
             .unwrap_or_default();
 
-        let nodes: Vec<Node> = schema
+        let mut nodes: Vec<Node> = schema
             .entities
             .iter()
-            .filter(|e| included_entities.contains(&e.name.as_str()))
+            .filter(|e| shown(&e.name))
             .map(|e| {
-                // A composite key is declared next to the columns, not on them.
-                let composite_pk: Vec<&str> = e
-                    .constraints
-                    .iter()
-                    .filter_map(|c| match c {
-                        Constraint::PrimaryKey(columns) => Some(columns),
-                        _ => None,
-                    })
-                    .flatten()
-                    .map(|name| name.as_str())
-                    .collect();
-
-                let columns: Vec<ColumnIR> = e
-                    .columns
-                    .iter()
-                    .filter_map(|c| {
-                        let is_pk = c.modifiers.iter().any(|m| matches!(m, ColumnModifier::Pk))
-                            || composite_pk.contains(&c.name.as_str());
-                        let is_fk = c.modifiers.iter().any(|m| matches!(m, ColumnModifier::Fk { .. }));
-
-                        let include = match detail {
-                            DetailLevel::Tables => false,
-                            DetailLevel::Pk => is_pk,
-                            DetailLevel::PkFk => is_pk || is_fk,
-                            DetailLevel::All => true,
-                        };
-
-                        if include {
-                            Some(ColumnIR {
-                                name: c.name.clone(),
-                                typ: c.typ.clone(),
-                                is_pk,
-                                is_fk,
-                            })
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+                let columns = entity_columns(e, detail);
 
                 // Arrangement takes priority over @hint.level
                 let (level, order) = if let Some(&(arr_level, arr_order)) =
@@ -132,10 +418,10 @@ impl GraphIR {
                 } else {
                     // Fall back to @hint.level, no order specified
                     let hint_level = e.hints.iter().find_map(|h| {
-                        if h.key == "hint.level" {
-                            if let crate::ast::HintValue::Int(n) = h.value {
-                                return Some(n);
-                            }
+                        if h.key == "hint.level"
+                            && let crate::ast::HintValue::Int(n) = h.value
+                        {
+                            return Some(n);
                         }
                         None
                     });
@@ -160,34 +446,692 @@ impl GraphIR {
                     level,
                     order,
                     group,
+                    doc: e.doc.clone(),
+                    kind: NodeKind::Entity,
+                    weak: false,
+                    detailed: matches!(detail, DetailLevel::Full | DetailLevel::Physical),
+                    indexes: if detail == DetailLevel::Physical { entity_indexes(e) } else { Vec::new() },
+                    style: Style::of_entity(e),
                 }
             })
             .collect();
 
         let node_ids: Vec<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
 
-        let edges: Vec<Edge> = schema
+        let mut edges: Vec<Edge> = schema
             .relationships
             .iter()
             .filter(|r| node_ids.contains(&r.left.as_str()) && node_ids.contains(&r.right.as_str()))
-            .map(|r| Edge {
+            .flat_map(|r| {
+                let edge = Edge {
+                    from: r.left.clone(),
+                    to: r.right.clone(),
+                    from_cardinality: r.left_cardinality,
+                    to_cardinality: r.right_cardinality,
+                    label: r.label.clone(),
+                    from_role: r.left_role.clone(),
+                    to_role: r.right_role.clone(),
+                    from_column: r.left_column.clone(),
+                    to_column: r.right_column.clone(),
+                    doc: r.doc.clone(),
+                    kind: EdgeKind::Relationship,
+                    identifying: r.identifying,
+                    via: None,
+                    style: Style::of_relationship(r),
+                };
+                match &r.via {
+                    // `A x -- y B via AB` goes through the join entity as
+                    // `A 1 -- y AB` and `B 1 -- x AB`.
+                    Some(join) if node_ids.contains(&join.as_str()) => vec![
+                        Edge {
+                            to: join.clone(),
+                            from_cardinality: Cardinality::ONE,
+                            to_role: None,
+                            to_column: None,
+                            identifying: None,
+                            via: Some(join.clone()),
+                            ..edge.clone()
+                        },
+                        Edge {
+                            from: r.right.clone(),
+                            to: join.clone(),
+                            from_cardinality: Cardinality::ONE,
+                            to_cardinality: r.left_cardinality,
+                            label: None,
+                            from_role: r.right_role.clone(),
+                            to_role: None,
+                            from_column: r.right_column.clone(),
+                            to_column: None,
+                            identifying: None,
+                            via: Some(join.clone()),
+                            ..edge
+                        },
+                    ],
+                    // Without its join entity, it is one edge named after it.
+                    Some(join) => vec![Edge {
+                        label: r.label.clone().or_else(|| Some(join.clone())),
+                        ..edge
+                    }],
+                    None => vec![edge],
+                }
+            })
+            .collect();
+
+        // A relationship not marked either way is identifying when the key
+        // behind it is part of its holder's primary key. The child of an
+        // identifying relationship is weak: the key holder, or else the many
+        // end.
+        let keys = foreign_keys(schema);
+        let claimed = claim_keys(&edges, &keys);
+        let mut weak: Vec<String> = Vec::new();
+        for (edge, key) in edges.iter_mut().zip(claimed) {
+            let key = key.map(|k| &keys[k]);
+            if edge.identifying.is_none() {
+                edge.identifying = key.map(|k| k.identifying);
+            }
+            if edge.identifying == Some(true) {
+                weak.push(match key {
+                    Some(key) => key.holder.to_string(),
+                    None if edge.from_cardinality.is_many() && !edge.to_cardinality.is_many() => edge.from.clone(),
+                    None => edge.to.clone(),
+                });
+            }
+        }
+
+        // Subtypes hang below their supertype. The markers describe all of a
+        // supertype's subtypes, so they are written once, on its first edge.
+        let mut labelled: Vec<&str> = Vec::new();
+        for e in &schema.entities {
+            let Some(extends) = &e.extends else {
+                continue;
+            };
+            let parent = extends.parent.as_str();
+            if !node_ids.contains(&parent) || !node_ids.contains(&e.name.as_str()) {
+                continue;
+            }
+            let siblings = || {
+                schema
+                    .entities
+                    .iter()
+                    .filter_map(|s| s.extends.as_ref())
+                    .filter(|x| x.parent == parent)
+            };
+            let disjointness = siblings().find_map(|x| x.disjointness);
+            let completeness = siblings().find_map(|x| x.completeness);
+            let markers: Vec<&str> = disjointness
+                .map(|d| d.as_str())
+                .into_iter()
+                .chain(completeness.map(|c| c.as_str()))
+                .collect();
+            let label = (!markers.is_empty() && !labelled.contains(&parent))
+                .then(|| format!("{{{}}}", markers.join(", ")));
+            labelled.push(parent);
+
+            edges.push(Edge {
+                from: parent.to_string(),
+                to: e.name.clone(),
+                from_cardinality: Cardinality::ONE,
+                to_cardinality: Cardinality::ZERO_OR_ONE,
+                label,
+                from_role: None,
+                to_role: None,
+                from_column: None,
+                to_column: None,
+                doc: None,
+                kind: EdgeKind::Generalization(completeness),
+                identifying: None,
+                via: None,
+                style: Style::default(),
+            });
+        }
+
+        for node in &mut nodes {
+            node.weak = weak.contains(&node.id);
+        }
+
+        let mut groups: Vec<Group> = Vec::new();
+        for name in nodes.iter().filter_map(|n| n.group.as_deref()) {
+            if !groups.iter().any(|g| g.name == name) {
+                groups.push(Group {
+                    name: name.to_string(),
+                    color: schema
+                        .hints
+                        .iter()
+                        .rev()
+                        .find(|h| h.key == format!("hint.group.{}.color", name))
+                        .and_then(|h| color_value(&h.value)),
+                });
+            }
+        }
+
+        GraphIR { nodes, edges, groups }
+    }
+
+    /// Add a ghost for every entity left out of the graph that a relationship
+    /// or `extends` links to a node in it, and draw the edges between the
+    /// two. With `EdgeSource::Fk`, foreign keys link them too.
+    ///
+    /// Edges are taken from the graph of the whole schema, so a relationship
+    /// through a join entity that was left out runs through its ghost. Two
+    /// ghosts are linked to each other only with `Ghosts::All`, which makes
+    /// every entity left out a ghost. Call this straight after `from_schema`,
+    /// before the other passes.
+    pub fn add_ghosts(&mut self, schema: &Schema, ghosts: Ghosts, source: EdgeSource) {
+        if ghosts == Ghosts::Hide {
+            return;
+        }
+        let all = ghosts == Ghosts::All;
+        let mut whole = GraphIR::from_schema(schema, None, DetailLevel::Tables);
+        if source == EdgeSource::Fk {
+            whole.derive_fk_edges(schema);
+        }
+
+        let shown = |id: &str| self.nodes.iter().any(|n| n.id == id);
+        let mut ghosts: Vec<String> = Vec::new();
+        if all {
+            ghosts.extend(whole.nodes.iter().filter(|n| !shown(&n.id)).map(|n| n.id.clone()));
+        }
+        for edge in &whole.edges {
+            for (end, other) in [(&edge.from, &edge.to), (&edge.to, &edge.from)] {
+                if !shown(end) && shown(other) && !ghosts.contains(end) {
+                    ghosts.push(end.clone());
+                }
+            }
+        }
+        if ghosts.is_empty() {
+            return;
+        }
+
+        let drawn = |id: &String| shown(id) || ghosts.contains(id);
+        self.edges = whole
+            .edges
+            .into_iter()
+            .filter(|e| drawn(&e.from) && drawn(&e.to) && (all || shown(&e.from) || shown(&e.to)))
+            .collect();
+        for node in whole.nodes {
+            if ghosts.contains(&node.id) {
+                self.nodes.push(Node {
+                    columns: Vec::new(),
+                    level: None,
+                    order: None,
+                    group: None,
+                    kind: NodeKind::Ghost,
+                    weak: false,
+                    detailed: false,
+                    indexes: Vec::new(),
+                    style: Style::default(),
+                    ..node
+                });
+            } else if let Some(shown) = self.nodes.iter_mut().find(|n| n.id == node.id) {
+                shown.weak = node.weak;
+            }
+        }
+    }
+
+    /// Draw each `A * -- * B via AB` as one many-to-many edge, labelled with
+    /// the join entity unless it has a label of its own, and leave the join
+    /// entity out. A join entity that takes part in anything else is kept,
+    /// and so are the edges through it.
+    pub fn collapse_joins(&mut self, schema: &Schema) {
+        for r in &schema.relationships {
+            let Some(join) = &r.via else {
+                continue;
+            };
+            let through = |e: &Edge| e.via.as_ref() == Some(join);
+            let Some(at) = self.edges.iter().position(through) else {
+                continue;
+            };
+            let touches = |e: &Edge| e.from == *join || e.to == *join;
+            if self.edges.iter().any(|e| touches(e) && !through(e)) {
+                continue;
+            }
+
+            let edge = Edge {
                 from: r.left.clone(),
                 to: r.right.clone(),
                 from_cardinality: r.left_cardinality,
                 to_cardinality: r.right_cardinality,
-                label: r.label.clone(),
-                role: r.role.clone(),
+                label: r.label.clone().or_else(|| Some(join.clone())),
+                from_role: r.left_role.clone(),
+                to_role: r.right_role.clone(),
+                from_column: r.left_column.clone(),
+                to_column: r.right_column.clone(),
+                doc: r.doc.clone(),
+                kind: EdgeKind::Relationship,
+                identifying: r.identifying,
+                via: None,
+                style: Style::of_relationship(r),
+            };
+            self.edges.retain(|e| !through(e));
+            self.edges.insert(at, edge);
+            self.nodes.retain(|n| n.id != *join);
+        }
+
+        let nodes = &self.nodes;
+        self.groups
+            .retain(|g| nodes.iter().any(|n| n.group.as_deref() == Some(g.name.as_str())));
+    }
+
+    /// Draw an edge for every foreign key no relationship already stands for.
+    ///
+    /// The edge runs from the referenced entity to the one holding the key.
+    /// Its ends follow the key column: the referenced end is `0..1` when the
+    /// key is nullable and `1` otherwise, and the holding end is `1` when the
+    /// key is unique and `*` otherwise. Each `rel` entry between the two
+    /// entities stands for one of their keys, the one its columns name or
+    /// else the first left, so a relationship written out is not drawn twice.
+    pub fn derive_fk_edges(&mut self, schema: &Schema) {
+        let keys = foreign_keys(schema);
+        let claimed = claim_keys(&self.edges, &keys);
+        let has_node = |id: &str| self.nodes.iter().any(|n| n.id == id);
+        let is_ghost = |id: &str| self.nodes.iter().any(|n| n.id == id && n.kind == NodeKind::Ghost);
+
+        let derived: Vec<Edge> = keys
+            .iter()
+            .enumerate()
+            .filter(|(i, key)| !claimed.contains(&Some(*i)) && has_node(key.holder) && has_node(key.target))
+            .filter(|(_, key)| !(is_ghost(key.holder) && is_ghost(key.target)))
+            .map(|(_, key)| Edge {
+                from: key.target.to_string(),
+                to: key.holder.to_string(),
+                from_cardinality: if key.nullable { Cardinality::ZERO_OR_ONE } else { Cardinality::ONE },
+                to_cardinality: if key.unique { Cardinality::ONE } else { Cardinality::MANY },
+                label: None,
+                from_role: None,
+                to_role: None,
+                from_column: None,
+                to_column: None,
+                doc: None,
+                kind: EdgeKind::Relationship,
+                identifying: Some(key.identifying),
+                via: None,
+                style: Style::default(),
             })
             .collect();
+        for node in &mut self.nodes {
+            node.weak |= derived.iter().any(|e| e.identifying == Some(true) && e.to == node.id);
+        }
+        self.edges.extend(derived);
+    }
+
+    /// Anchor edges written without columns on the foreign key behind them:
+    /// the `fk ->` column on one end and the column it references on the
+    /// other. Each foreign key anchors one edge; edges no foreign key
+    /// explains are left alone.
+    pub fn anchor_fk_columns(&mut self, schema: &Schema) {
+        let keys = foreign_keys(schema);
+        let claimed = claim_keys(&self.edges, &keys);
+
+        for (edge, key) in self.edges.iter_mut().zip(claimed) {
+            // A generalization keeps to the border, where its symbol goes.
+            if edge.from_column.is_some() || edge.to_column.is_some() || edge.kind != EdgeKind::Relationship {
+                continue;
+            }
+            let Some(key) = key.map(|i| &keys[i]) else {
+                continue;
+            };
+            let ([column], [target_column]) = (key.columns.as_slice(), key.target_columns.as_slice()) else {
+                continue;
+            };
+            let (column, target_column) = (Some(column.to_string()), Some(target_column.to_string()));
+            if edge.to == key.holder && edge.from == key.target {
+                (edge.to_column, edge.from_column) = (column, target_column);
+            } else {
+                (edge.from_column, edge.to_column) = (column, target_column);
+            }
+        }
+    }
+
+    /// List each subtype's inherited columns above its own, those of the
+    /// topmost supertype first. A column the subtype declares again is not
+    /// repeated.
+    pub fn inherit_columns(&mut self, schema: &Schema, detail: DetailLevel) {
+        for node in self.nodes.iter_mut().filter(|n| n.kind == NodeKind::Entity) {
+            let Some(entity) = schema.entities.iter().find(|e| e.name == node.id) else {
+                continue;
+            };
+
+            // Supertypes nearest first, stopping at a cycle
+            let mut ancestors: Vec<&Entity> = Vec::new();
+            let mut parent = entity.extends.as_ref();
+            while let Some(extends) = parent {
+                let Some(p) = schema.entities.iter().find(|e| e.name == extends.parent.name) else {
+                    break;
+                };
+                if p.name == entity.name || ancestors.iter().any(|a| a.name == p.name) {
+                    break;
+                }
+                ancestors.push(p);
+                parent = p.extends.as_ref();
+            }
+
+            let mut inherited: Vec<ColumnIR> = Vec::new();
+            for ancestor in ancestors.iter().rev() {
+                for mut column in entity_columns(ancestor, detail) {
+                    let declared = |c: &ColumnIR| c.name == column.name;
+                    if node.columns.iter().any(declared) || inherited.iter().any(declared) {
+                        continue;
+                    }
+                    column.inherited = true;
+                    inherited.push(column);
+                }
+            }
+            node.columns.splice(0..0, inherited);
+        }
+    }
+
+    /// Add a box for every enum a shown column has as its type, listing the
+    /// enum's values, and a link from it to each of those columns.
+    ///
+    /// Links run from the enum to the entity, one to many, so the enum is
+    /// layered above the entities using it. An enum whose name is taken by a
+    /// node is left out.
+    pub fn add_enums(&mut self, schema: &Schema) {
+        for e in &schema.enums {
+            if self.nodes.iter().any(|n| n.id == e.name) {
+                continue;
+            }
+
+            let mut links = Vec::new();
+            for node in self.nodes.iter().filter(|n| n.kind == NodeKind::Entity) {
+                let Some(entity) = schema.entities.iter().find(|en| en.name == node.id) else {
+                    continue;
+                };
+                for column in &node.columns {
+                    let is_user = entity
+                        .columns
+                        .iter()
+                        .any(|c| c.name == column.name && c.typ.name == e.name);
+                    if is_user {
+                        links.push(Edge {
+                            from: e.name.clone(),
+                            to: node.id.clone(),
+                            from_cardinality: Cardinality::ONE,
+                            to_cardinality: Cardinality::MANY,
+                            label: None,
+                            from_role: None,
+                            to_role: None,
+                            from_column: None,
+                            to_column: Some(column.name.clone()),
+                            doc: None,
+                            kind: EdgeKind::EnumLink,
+                            identifying: None,
+                            via: None,
+                            style: Style::default(),
+                        });
+                    }
+                }
+            }
+            if links.is_empty() {
+                continue;
+            }
+
+            self.nodes.push(Node {
+                id: e.name.clone(),
+                label: format!("«enum» {}", e.name),
+                columns: e
+                    .values
+                    .iter()
+                    .map(|value| ColumnIR {
+                        name: value.clone(),
+                        typ: String::new(),
+                        is_pk: false,
+                        is_fk: false,
+                        doc: None,
+                        inherited: false,
+                        not_null: false,
+                        unique: false,
+                        default: None,
+                        check: None,
+                        references: None,
+                    })
+                    .collect(),
+                level: None,
+                order: None,
+                group: None,
+                doc: e.doc.clone(),
+                kind: NodeKind::Enum,
+                weak: false,
+                detailed: false,
+                indexes: Vec::new(),
+                style: Style::default(),
+            });
+            self.edges.extend(links);
+        }
+    }
+}
+
+/// One line per index of `entity`, then one per foreign key with an
+/// `on delete` or `on update` action, written as in SQL.
+fn entity_indexes(entity: &Entity) -> Vec<String> {
+    let mut indexes = Vec::new();
+    let mut actions = Vec::new();
+    for constraint in &entity.constraints {
+        match constraint {
+            Constraint::Index { columns, name, unique } => {
+                let mut line = if *unique { "UNIQUE INDEX".to_string() } else { "INDEX".to_string() };
+                if let Some(name) = name {
+                    line.push_str(&format!(" {}", name));
+                }
+                line.push_str(&format!(" ({})", columns.join(", ")));
+                indexes.push(line);
+            }
+            Constraint::ForeignKey {
+                columns,
+                target,
+                target_columns,
+                on_delete,
+                on_update,
+            } if on_delete.is_some() || on_update.is_some() => {
+                let mut line = format!("FK ({}) → {}({})", columns.join(", "), target, target_columns.join(", "));
+                for (event, action) in [("DELETE", on_delete), ("UPDATE", on_update)] {
+                    if let Some(action) = action {
+                        line.push_str(&format!(" ON {} {}", event, action.to_uppercase()));
+                    }
+                }
+                actions.push(line);
+            }
+            _ => {}
+        }
+    }
+    indexes.extend(actions);
+    indexes
+}
+
+/// The columns of `entity` that `detail` shows.
+fn entity_columns(entity: &Entity, detail: DetailLevel) -> Vec<ColumnIR> {
+    // A composite key is declared next to the columns, not on them.
+    let composite_pk: Vec<&str> = entity
+        .constraints
+        .iter()
+        .filter_map(|c| match c {
+            Constraint::PrimaryKey(columns) => Some(columns),
+            _ => None,
+        })
+        .flatten()
+        .map(|name| name.as_str())
+        .collect();
+
+    // The column each key column refers to, from `fk ->` or a
+    // `foreign_key(...)` constraint
+    let mut references: Vec<(&str, String)> = Vec::new();
+    for c in &entity.columns {
+        for modifier in &c.modifiers {
+            if let ColumnModifier::Fk { target, column } = modifier {
+                references.push((&c.name, format!("{}.{}", target, column)));
+            }
+        }
+    }
+    for constraint in &entity.constraints {
+        if let Constraint::ForeignKey {
+            columns,
+            target,
+            target_columns,
+            ..
+        } = constraint
+        {
+            for (column, target_column) in columns.iter().zip(target_columns) {
+                references.push((column, format!("{}.{}", target, target_column)));
+            }
+        }
+    }
+
+    entity
+        .columns
+        .iter()
+        .filter_map(|c| {
+            let is_pk = c.modifiers.iter().any(|m| matches!(m, ColumnModifier::Pk))
+                || composite_pk.contains(&c.name.as_str());
+            let reference = references.iter().find(|(column, _)| *column == c.name);
+            let is_fk = reference.is_some();
+
+            let include = match detail {
+                DetailLevel::Tables => false,
+                DetailLevel::Pk => is_pk,
+                DetailLevel::PkFk => is_pk || is_fk,
+                DetailLevel::All | DetailLevel::Full | DetailLevel::Physical => true,
+            };
+
+            if include {
+                Some(ColumnIR {
+                    name: c.name.clone(),
+                    typ: c.typ.to_string(),
+                    is_pk,
+                    is_fk,
+                    doc: c.doc.clone(),
+                    inherited: false,
+                    not_null: is_pk || c.modifiers.contains(&ColumnModifier::NotNull),
+                    unique: c.modifiers.contains(&ColumnModifier::Unique),
+                    default: c.modifiers.iter().find_map(|m| match m {
+                        ColumnModifier::Default(value) => Some(value.clone()),
+                        _ => None,
+                    }),
+                    check: c.modifiers.iter().find_map(|m| match m {
+                        ColumnModifier::Check(condition) => Some(condition.clone()),
+                        _ => None,
+                    }),
+                    references: reference.map(|(_, target)| target.clone()),
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A foreign key, from an `fk ->` modifier or a `foreign_key(...)` constraint.
+struct ForeignKey<'a> {
+    holder: &'a str,
+    columns: Vec<&'a str>,
+    target: &'a str,
+    target_columns: Vec<&'a str>,
+    nullable: bool,
+    unique: bool,
+    /// Every column is part of the holder's primary key
+    identifying: bool,
+}
+
+/// Every foreign key in `schema`, in declaration order.
+fn foreign_keys(schema: &Schema) -> Vec<ForeignKey<'_>> {
+    let mut keys = Vec::new();
+    for entity in &schema.entities {
+        let primary_key: Vec<&str> = entity
+            .columns
+            .iter()
+            .filter(|c| c.modifiers.contains(&ColumnModifier::Pk))
+            .map(|c| c.name.as_str())
+            .chain(entity.constraints.iter().flat_map(|c| match c {
+                Constraint::PrimaryKey(columns) => columns.iter().map(|c| c.as_str()).collect(),
+                _ => Vec::new(),
+            }))
+            .collect();
+        let has = |name: &str, modifier: &ColumnModifier| {
+            entity
+                .columns
+                .iter()
+                .any(|c| c.name == name && c.modifiers.contains(modifier))
+        };
+        // A key is nullable if any of its columns is, and unique if it is the
+        // whole primary key or a single unique column.
+        let nullable = |columns: &[&str]| {
+            columns
+                .iter()
+                .any(|c| !has(c, &ColumnModifier::NotNull) && !primary_key.contains(c))
+        };
+        let unique = |columns: &[&str]| {
+            columns == primary_key.as_slice() || matches!(columns, [c] if has(c, &ColumnModifier::Unique))
+        };
+        let identifying = |columns: &[&str]| columns.iter().all(|c| primary_key.contains(c));
 
-        GraphIR { nodes, edges }
+        for column in &entity.columns {
+            for modifier in &column.modifiers {
+                if let ColumnModifier::Fk { target, column: target_column } = modifier {
+                    let columns = vec![column.name.as_str()];
+                    keys.push(ForeignKey {
+                        holder: &entity.name,
+                        nullable: nullable(&columns),
+                        unique: unique(&columns),
+                        identifying: identifying(&columns),
+                        columns,
+                        target,
+                        target_columns: vec![target_column.as_str()],
+                    });
+                }
+            }
+        }
+        for constraint in &entity.constraints {
+            if let Constraint::ForeignKey { columns, target, target_columns, .. } = constraint {
+                let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+                keys.push(ForeignKey {
+                    holder: &entity.name,
+                    nullable: nullable(&columns),
+                    unique: unique(&columns),
+                    identifying: identifying(&columns),
+                    columns,
+                    target,
+                    target_columns: target_columns.iter().map(|c| c.as_str()).collect(),
+                });
+            }
+        }
+    }
+    keys
+}
+
+/// The key each edge stands for, if any.
+///
+/// An edge naming a key column takes that key. The others take the first key
+/// left between their two entities, held preferably by the `to` end, where
+/// the many side usually is.
+fn claim_keys(edges: &[Edge], keys: &[ForeignKey]) -> Vec<Option<usize>> {
+    let names = |edge: &Edge, key: &ForeignKey| {
+        let column = |c: &Option<String>| c.as_deref().is_some_and(|c| key.columns == [c]);
+        (edge.to == key.holder && edge.from == key.target && column(&edge.to_column))
+            || (edge.from == key.holder && edge.to == key.target && column(&edge.from_column))
+    };
+
+    let mut claimed: Vec<Option<usize>> = edges
+        .iter()
+        .map(|edge| keys.iter().position(|key| names(edge, key)))
+        .collect();
+    for (i, edge) in edges.iter().enumerate() {
+        if claimed[i].is_some() || edge.from_column.is_some() || edge.to_column.is_some() {
+            continue;
+        }
+        let free = |holder: &str, target: &str| {
+            (0..keys.len()).find(|&k| {
+                keys[k].holder == holder && keys[k].target == target && !claimed.contains(&Some(k))
+            })
+        };
+        claimed[i] = free(&edge.to, &edge.from).or_else(|| free(&edge.from, &edge.to));
     }
+    claimed
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::Parser;
+    use crate::view::resolve_named;
 
     #[test]
     fn test_ir_all_detail() {
@@ -235,6 +1179,68 @@ mod tests {
         assert_eq!(ir.nodes[0].columns.len(), 0);
     }
 
+    #[test]
+    fn test_ir_full_detail() {
+        let input = r#"
+            entity User { id int pk }
+            entity Order {
+                id int pk
+                user_id int not null fk -> User.id
+                code string unique default "X"
+                qty int check "qty > 0"
+                ref_id int
+                foreign_key(ref_id) references User(id)
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::Full);
+
+        assert!(ir.nodes[1].detailed);
+        let badges: Vec<Vec<&str>> = ir.nodes[1].columns.iter().map(|c| c.badges()).collect();
+        assert_eq!(badges, vec![vec![], vec!["NN"], vec!["?", "UQ"], vec!["?", "CK"], vec!["?"]]);
+        let cells: Vec<Vec<String>> = ir.nodes[1].columns.iter().map(|c| c.cells()).collect();
+        assert_eq!(cells[1], vec!["  user_id", "int", "NN", "", "→ User.id"]);
+        assert_eq!(cells[2][3], "= \"X\"");
+        assert_eq!(ir.nodes[1].columns[4].references.as_deref(), Some("User.id"));
+        assert!(ir.nodes[1].columns[4].is_fk);
+
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        assert!(!ir.nodes[1].detailed);
+    }
+
+    #[test]
+    fn test_ir_physical_detail() {
+        let input = r#"
+            entity User { id int pk }
+            entity Order {
+                id int pk
+                user_id int not null
+                code string
+                foreign_key(user_id) references User(id) on delete cascade on update no action
+                foreign_key(id) references User(id)
+                index(code) [unique, name = ix_order_code]
+                index(user_id, code)
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::Physical);
+
+        assert!(ir.nodes[1].detailed);
+        // Indexes come first; a foreign key without actions is left out.
+        assert_eq!(
+            ir.nodes[1].indexes,
+            vec![
+                "UNIQUE INDEX ix_order_code (code)",
+                "INDEX (user_id, code)",
+                "FK (user_id) → User(id) ON DELETE CASCADE ON UPDATE NO ACTION",
+            ]
+        );
+        assert!(ir.nodes[0].indexes.is_empty());
+
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::Full);
+        assert!(ir.nodes[1].indexes.is_empty());
+    }
+
     #[test]
     fn test_ir_with_view() {
         let input = r#"
@@ -247,7 +1253,7 @@ mod tests {
             }
         "#;
         let schema = Parser::new(input).unwrap().parse().unwrap();
-        let ir = GraphIR::from_schema(&schema, Some("core"), DetailLevel::All);
+        let ir = GraphIR::from_schema(&schema, resolve_named(&schema, "core").as_ref(), DetailLevel::All);
 
         assert_eq!(ir.nodes.len(), 2);
     }
@@ -288,6 +1294,90 @@ mod tests {
         assert_eq!(f.order, Some(2));
     }
 
+    #[test]
+    fn test_ir_view_arrangement() {
+        let input = r#"
+            @hint.arrangement = {
+                A B C;
+                D E F;
+                G
+            }
+            entity A { id int pk }
+            entity B { id int pk }
+            entity C { id int pk }
+            entity D { id int pk }
+            entity E { id int pk }
+            entity F { id int pk }
+            entity G { id int pk }
+            view holes { include C, G, F }
+            view own {
+                @hint.arrangement = { F; A C }
+                include A, C, F
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let places = |view: &str| -> Vec<(String, Option<i64>, Option<i64>)> {
+            let ir = GraphIR::from_schema(&schema, resolve_named(&schema, view).as_ref(), DetailLevel::All);
+            ir.nodes.iter().map(|n| (n.id.clone(), n.level, n.order)).collect()
+        };
+        let place = |id: &str, level: i64, order: i64| (id.to_string(), Some(level), Some(order));
+
+        // What the view leaves out of the file's arrangement closes up.
+        assert_eq!(places("holes"), vec![place("C", 0, 0), place("F", 1, 0), place("G", 2, 0)]);
+        assert_eq!(places("own"), vec![place("A", 1, 0), place("C", 1, 1), place("F", 0, 0)]);
+    }
+
+    #[test]
+    fn test_ir_ghosts() {
+        let input = r#"
+            entity User { id int pk }
+            entity Address { id int pk }
+            entity Order { id int pk user_id int fk -> User.id }
+            entity Tag { id int pk }
+            entity OrderTag { order_id int pk tag_id int pk }
+            entity Audit { id int pk order_id int fk -> Order.id }
+            rel {
+                User 1 -- * Address
+                User 1 -- * Order
+                Order * -- * Tag via OrderTag
+            }
+            view orders { include Order, Tag }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let view = resolve_named(&schema, "orders");
+        let ghosts = |ghosts: Ghosts, source: EdgeSource| {
+            let mut ir = GraphIR::from_schema(&schema, view.as_ref(), DetailLevel::All);
+            ir.add_ghosts(&schema, ghosts, source);
+            let ghosts: Vec<String> = ir
+                .nodes
+                .iter()
+                .filter(|n| n.kind == NodeKind::Ghost)
+                .map(|n| n.id.clone())
+                .collect();
+            let edges: Vec<(String, String)> = ir.edges.iter().map(|e| (e.from.clone(), e.to.clone())).collect();
+            (ghosts, edges, ir)
+        };
+
+        // The join entity left out comes back as a ghost, and the edge
+        // between two ghosts is not drawn.
+        let (names, edges, ir) = ghosts(Ghosts::Show, EdgeSource::Rel);
+        assert_eq!(names, vec!["User", "OrderTag"]);
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        assert_eq!(edges, vec![pair("User", "Order"), pair("Order", "OrderTag"), pair("Tag", "OrderTag")]);
+        assert!(ir.nodes.iter().filter(|n| n.kind == NodeKind::Ghost).all(|n| n.columns.is_empty()));
+
+        // Foreign keys lead to ghosts too, when they are drawn.
+        let (names, edges, _) = ghosts(Ghosts::Show, EdgeSource::Fk);
+        assert_eq!(names, vec!["User", "OrderTag", "Audit"]);
+        assert!(edges.contains(&pair("Order", "Audit")));
+
+        // Or the whole rest of the schema, linked as it is.
+        let (names, edges, _) = ghosts(Ghosts::All, EdgeSource::Rel);
+        assert_eq!(names, vec!["User", "Address", "OrderTag", "Audit"]);
+        assert_eq!(edges[0], pair("User", "Address"));
+        assert_eq!(edges.len(), 4);
+    }
+
     #[test]
     fn test_ir_arrangement_overrides_hint() {
         let input = r#"
@@ -309,4 +1399,290 @@ mod tests {
         assert_eq!(a.level, Some(0));
         assert_eq!(a.order, Some(0));
     }
+
+    #[test]
+    fn test_ir_groups() {
+        let input = r##"
+            @hint.group.billing.color = "#fde2e2"
+            entity Invoice {
+                @hint.group = billing
+                id int pk
+            }
+            entity User {
+                @hint.group = "core"
+                id int pk
+            }
+            entity Payment {
+                @hint.group = billing
+                id int pk
+            }
+            entity Log { id int pk }
+        "##;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+
+        let names: Vec<&str> = ir.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["billing", "core"]);
+        assert_eq!(ir.groups[0].color.as_deref(), Some("#fde2e2"));
+        assert_eq!(ir.groups[1].color, None);
+    }
+
+    #[test]
+    fn test_anchor_fk_columns() {
+        let input = r#"
+            entity User { id int pk }
+            entity Post {
+                id int pk
+                author_id int fk -> User.id
+                editor_id int fk -> User.id
+            }
+            entity Tag { id int pk }
+            rel {
+                User.id 1 -- * Post.editor_id
+                User 1 -- * Post
+                Post * -- * Tag
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        assert_eq!(ir.edges[1].to_column, None);
+
+        ir.anchor_fk_columns(&schema);
+        let columns: Vec<(Option<&str>, Option<&str>)> = ir
+            .edges
+            .iter()
+            .map(|e| (e.from_column.as_deref(), e.to_column.as_deref()))
+            .collect();
+        // editor_id is taken by the explicit edge, so the other gets author_id.
+        assert_eq!(
+            columns,
+            vec![(Some("id"), Some("editor_id")), (Some("id"), Some("author_id")), (None, None)]
+        );
+    }
+
+    #[test]
+    fn test_derive_fk_edges() {
+        let input = r#"
+            entity User { id int pk }
+            entity Profile {
+                user_id int pk fk -> User.id
+            }
+            entity Post {
+                id int pk
+                author_id int not null fk -> User.id
+                editor_id int fk -> User.id
+                slug string unique fk -> Slug.value
+            }
+            entity Slug { value string pk }
+            entity Tagging {
+                post_id int
+                tag_id int
+                foreign_key(post_id) references Post(id)
+            }
+            rel {
+                User 1 -- * Post : "writes"
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        ir.derive_fk_edges(&schema);
+
+        let edges: Vec<String> = ir
+            .edges
+            .iter()
+            .map(|e| format!("{} {} -- {} {}", e.from, e.from_cardinality, e.to_cardinality, e.to))
+            .collect();
+        // The `rel` entry stands for author_id, the first key between the two.
+        assert_eq!(
+            edges,
+            vec![
+                "User 1 -- * Post",
+                "User 1 -- 1 Profile",
+                "User 0..1 -- * Post",
+                "Slug 0..1 -- 1 Post",
+                "Post 0..1 -- * Tagging",
+            ]
+        );
+        assert_eq!(ir.edges[0].label.as_deref(), Some("writes"));
+
+        // Keys to entities outside the view draw nothing.
+        let input = format!("{}\nview posts {{ include Post, Tagging }}\n", input);
+        let schema = Parser::new(&input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, resolve_named(&schema, "posts").as_ref(), DetailLevel::All);
+        ir.derive_fk_edges(&schema);
+        assert_eq!(ir.edges.len(), 1);
+        assert_eq!(ir.edges[0].to, "Tagging");
+    }
+
+    #[test]
+    fn test_add_enums() {
+        let input = r#"
+            enum Status { pending, paid }
+            enum Unused { a }
+            entity Order {
+                id int pk
+                status Status not null
+                previous Status
+            }
+            entity Refund {
+                id int pk
+                status Status
+            }
+            view orders { include Order }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        ir.add_enums(&schema);
+
+        let status = ir.nodes.iter().find(|n| n.id == "Status").unwrap();
+        assert_eq!(status.kind, NodeKind::Enum);
+        assert_eq!(status.label, "«enum» Status");
+        let values: Vec<&str> = status.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(values, vec!["pending", "paid"]);
+        assert!(!ir.nodes.iter().any(|n| n.id == "Unused"));
+
+        let links: Vec<String> = ir
+            .edges
+            .iter()
+            .map(|e| format!("{} -> {}.{}", e.from, e.to, e.to_column.as_deref().unwrap()))
+            .collect();
+        assert_eq!(links, vec!["Status -> Order.status", "Status -> Order.previous", "Status -> Refund.status"]);
+        assert!(ir.edges.iter().all(|e| e.kind == EdgeKind::EnumLink));
+
+        // Only columns shown link to their enum.
+        let mut ir = GraphIR::from_schema(&schema, resolve_named(&schema, "orders").as_ref(), DetailLevel::Pk);
+        ir.add_enums(&schema);
+        assert_eq!(ir.nodes.len(), 1);
+        assert!(ir.edges.is_empty());
+    }
+
+    #[test]
+    fn test_generalization() {
+        let input = r#"
+            entity Party {
+                id int pk
+                created_at timestamp
+            }
+            entity Person extends Party disjoint {
+                id int pk
+                birth_date date
+            }
+            entity Organization extends Party total { name string }
+            entity Employee extends Person { salary int }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+
+        let edges: Vec<(&str, &str, Option<&str>)> = ir
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.label.as_deref()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("Party", "Person", Some("{disjoint, total}")),
+                ("Party", "Organization", None),
+                ("Person", "Employee", None),
+            ]
+        );
+        assert_eq!(ir.edges[1].kind, EdgeKind::Generalization(Some(Completeness::Total)));
+        assert_eq!(ir.edges[2].kind, EdgeKind::Generalization(None));
+
+        ir.inherit_columns(&schema, DetailLevel::All);
+        let employee = ir.nodes.iter().find(|n| n.id == "Employee").unwrap();
+        let columns: Vec<(&str, bool)> = employee.columns.iter().map(|c| (c.name.as_str(), c.inherited)).collect();
+        assert_eq!(
+            columns,
+            vec![("id", true), ("created_at", true), ("birth_date", true), ("salary", false)]
+        );
+        // Person redeclares id, which is not repeated.
+        let person = ir.nodes.iter().find(|n| n.id == "Person").unwrap();
+        assert_eq!(person.columns.len(), 3);
+    }
+
+    #[test]
+    fn test_identifying() {
+        let input = r#"
+            entity Order { id int pk }
+            entity Product { id int pk }
+            entity Customer { id int pk }
+            entity OrderItem {
+                order_id int fk -> Order.id
+                product_id int fk -> Product.id
+                customer_id int fk -> Customer.id
+                primary_key(order_id, product_id)
+            }
+            entity Note { id int pk }
+            rel {
+                Order 1 -- * OrderItem
+                Customer 1 -- * OrderItem
+                Order 1 -- * Note identifying
+                Product 1 -- * OrderItem non_identifying
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+
+        let identifying: Vec<Option<bool>> = ir.edges.iter().map(|e| e.identifying).collect();
+        assert_eq!(identifying, vec![Some(true), Some(false), Some(true), Some(false)]);
+        let weak: Vec<&str> = ir.nodes.iter().filter(|n| n.weak).map(|n| n.id.as_str()).collect();
+        assert_eq!(weak, vec!["OrderItem", "Note"]);
+
+        // A relationship with no key behind it stays unknown.
+        let schema = Parser::new("entity A {}\nentity B {}\nrel { A 1 -- * B }\n").unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        assert_eq!(ir.edges[0].identifying, None);
+        assert!(!ir.nodes[1].weak);
+    }
+
+    #[test]
+    fn test_join_entities() {
+        let input = r#"
+            entity Order { id int pk }
+            entity Tag { id int pk }
+            entity OrderTag {
+                order_id int fk -> Order.id
+                tag_id int fk -> Tag.id
+                primary_key(order_id, tag_id)
+            }
+            rel { Order * -- 1..* Tag via OrderTag as tagged, labels }
+            view orders { include Order, Tag }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ends = |ir: &GraphIR| -> Vec<String> {
+            ir.edges
+                .iter()
+                .map(|e| format!("{} {} -- {} {}", e.from, e.from_cardinality, e.to_cardinality, e.to))
+                .collect()
+        };
+
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        assert_eq!(ends(&ir), vec!["Order 1 -- 1..* OrderTag", "Tag 1 -- * OrderTag"]);
+        assert!(ir.edges.iter().all(|e| e.via.as_deref() == Some("OrderTag")));
+        assert!(ir.nodes[2].weak);
+        // Each half names the role of the entity it starts from.
+        let roles: Vec<_> = ir.edges.iter().map(|e| (e.from_role.as_deref(), e.to_role.as_deref())).collect();
+        assert_eq!(roles, vec![(Some("tagged"), None), (Some("labels"), None)]);
+
+        let mut collapsed = ir.clone();
+        collapsed.collapse_joins(&schema);
+        assert_eq!(ends(&collapsed), vec!["Order * -- 1..* Tag"]);
+        assert_eq!(collapsed.edges[0].label.as_deref(), Some("OrderTag"));
+        assert_eq!(collapsed.edges[0].to_role.as_deref(), Some("labels"));
+        assert_eq!(collapsed.nodes.len(), 2);
+
+        // A view without the join entity draws the relationship directly.
+        let ir = GraphIR::from_schema(&schema, resolve_named(&schema, "orders").as_ref(), DetailLevel::All);
+        assert_eq!(ends(&ir), vec!["Order * -- 1..* Tag"]);
+
+        // A join entity with relationships of its own stays.
+        let input = format!("{input}\nentity Note {{ id int pk }}\nrel {{ OrderTag 1 -- * Note }}\n");
+        let schema = Parser::new(&input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        ir.collapse_joins(&schema);
+        assert_eq!(ir.edges.len(), 3);
+        assert_eq!(ir.nodes.len(), 4);
+    }
 }
+
@@ -6,6 +6,7 @@ use crate::ir::GraphIR;
 use crate::measure::TextMetrics;
 use std::collections::HashMap;
 
+use super::anchors::ColumnAnchors;
 use super::routing::SELF_REF_LOOP_OFFSET;
 use super::types::CorridorAnalysis;
 
@@ -49,6 +50,34 @@ pub fn calculate_self_ref_reserve<'a>(
         .collect()
 }
 
+/// Horizontal space column-pinned edge ends may need to the right of each
+/// node: one stub per end, stacked `lane_spacing` apart. Only top-down and
+/// bottom-up layouts leave from the sides.
+pub fn calculate_row_exit_reserve<'a>(
+    ir: &'a GraphIR,
+    column_anchors: &ColumnAnchors,
+    entity_margin: f64,
+    lane_spacing: f64,
+) -> HashMap<&'a str, f64> {
+    if !column_anchors.side_exits() {
+        return HashMap::new();
+    }
+    let mut ends: HashMap<&str, usize> = HashMap::new();
+    for (idx, edge) in ir.edges.iter().enumerate() {
+        if edge.from == edge.to {
+            continue;
+        }
+        for (id, from) in [(edge.from.as_str(), true), (edge.to.as_str(), false)] {
+            if column_anchors.is_pinned(idx, from) {
+                *ends.entry(id).or_insert(0) += 1;
+            }
+        }
+    }
+    ends.into_iter()
+        .map(|(id, count)| (id, entity_margin + (count - 1) as f64 * lane_spacing))
+        .collect()
+}
+
 /// Build node level lookup: node_id -> level.
 pub fn build_node_level_lookup(ir: &GraphIR) -> HashMap<&str, i64> {
     ir.nodes
@@ -142,6 +171,27 @@ pub fn calculate_dynamic_channel_gaps(
     dynamic_gaps
 }
 
+/// Widen channels for sideways layouts, where the runs between an entity and
+/// its lane are horizontal and are where relationship labels end up. Each
+/// channel gets room for its widest label on either side of the lanes.
+pub fn widen_channels_for_labels(
+    dynamic_gaps: &mut HashMap<i64, f64>,
+    ir: &GraphIR,
+    channel_edges: &HashMap<i64, Vec<usize>>,
+    metrics: &TextMetrics,
+) {
+    for (level, gap) in dynamic_gaps.iter_mut() {
+        let widest = channel_edges
+            .get(level)
+            .into_iter()
+            .flatten()
+            .filter_map(|&idx| ir.edges[idx].label.as_deref())
+            .map(|label| metrics.text_width(label))
+            .fold(0.0, f64::max);
+        *gap = gap.max(widest * 2.0);
+    }
+}
+
 /// Build node order lookup: node_id -> order within level.
 pub fn build_node_order<'a>(
     levels: &HashMap<i64, Vec<&'a crate::ir::Node>>,
@@ -1,12 +1,92 @@
 //! Edge anchor calculation for nodes.
 
 use crate::ir::{GraphIR, Node};
+use crate::measure::TextMetrics;
 use std::collections::HashMap;
 
 use super::corridor::find_gap_center_x;
-use super::types::LayoutNode;
+use super::types::{Direction, LayoutNode};
+
+/// Edge ends attached beside the row of a column rather than distributed
+/// along a border.
+///
+/// Positions are in the top-down frame every phase works in. Top-down and
+/// bottom-up layouts leave the entity from its left or right side, level with
+/// the row. Sideways layouts already leave from those sides, which are the
+/// top and bottom borders before the transpose, so there the end just sits at
+/// the row.
+#[derive(Debug, Default)]
+pub struct ColumnAnchors {
+    /// (edge index, `from` end) -> distance from the node's top to the row
+    rows: HashMap<(usize, bool), f64>,
+    /// Half a row: how far a pinned end may move and still sit beside it
+    row_slack: f64,
+    sideways: bool,
+    /// Bottom-up: the node is mirrored, so rows count from its bottom
+    flipped: bool,
+}
+
+impl ColumnAnchors {
+    pub fn new(ir: &GraphIR, metrics: &TextMetrics, direction: Direction) -> Self {
+        let row = |id: &str, column: &Option<String>| {
+            let column = column.as_deref()?;
+            let node = ir.nodes.iter().find(|n| n.id == id)?;
+            let index = node.columns.iter().position(|c| c.name == column)?;
+            Some(metrics.row_center(index))
+        };
+
+        let mut rows = HashMap::new();
+        for (idx, edge) in ir.edges.iter().enumerate() {
+            if let Some(y) = row(&edge.from, &edge.from_column) {
+                rows.insert((idx, true), y);
+            }
+            if let Some(y) = row(&edge.to, &edge.to_column) {
+                rows.insert((idx, false), y);
+            }
+        }
+
+        Self {
+            rows,
+            row_slack: metrics.line_height / 2.0,
+            sideways: direction.is_horizontal(),
+            flipped: direction == Direction::BottomUp,
+        }
+    }
+
+    pub fn is_pinned(&self, edge_index: usize, from: bool) -> bool {
+        self.rows.contains_key(&(edge_index, from))
+    }
+
+    /// How far straightening may slide an end along its border.
+    pub fn reach(&self, edge_index: usize, from: bool) -> f64 {
+        if self.is_pinned(edge_index, from) {
+            self.row_slack
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// True when pinned ends leave from a side border.
+    pub fn side_exits(&self) -> bool {
+        !self.sideways
+    }
+
+    /// Where a pinned end meets `node`: a Y on a side border, or an X on the
+    /// top or bottom border when sideways.
+    pub fn position(&self, node: &LayoutNode, edge_index: usize, from: bool) -> Option<f64> {
+        let offset = *self.rows.get(&(edge_index, from))?;
+        Some(if self.sideways {
+            node.x + offset
+        } else if self.flipped {
+            node.y + node.height - offset
+        } else {
+            node.y + offset
+        })
+    }
+}
 
 /// Calculate edge anchor positions on nodes.
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_edge_anchors<'a>(
     ir: &'a GraphIR,
     node_positions: &HashMap<&str, &LayoutNode>,
@@ -14,6 +94,7 @@ pub fn calculate_edge_anchors<'a>(
     edge_gap_index: &HashMap<usize, usize>,
     layout_nodes: &[LayoutNode],
     levels: &HashMap<i64, Vec<&'a Node>>,
+    column_anchors: &ColumnAnchors,
     entity_margin: f64,
     anchor_spacing: f64,
 ) -> HashMap<(&'a str, bool), Vec<(usize, f64)>> {
@@ -47,10 +128,13 @@ pub fn calculate_edge_anchors<'a>(
             to_node.x + to_node.width / 2.0
         };
 
-        node_exits
-            .entry((edge.from.as_str(), going_down))
-            .or_default()
-            .push((idx, sort_key_x));
+        // Pinned ends keep their row and take no slot on the border.
+        if !column_anchors.is_pinned(idx, true) {
+            node_exits
+                .entry((edge.from.as_str(), going_down))
+                .or_default()
+                .push((idx, sort_key_x));
+        }
 
         let entry_sort_key_x = if is_multi_level {
             if let Some(&gap_idx) = edge_gap_index.get(&idx) {
@@ -62,10 +146,12 @@ pub fn calculate_edge_anchors<'a>(
             from_node.x + from_node.width / 2.0
         };
 
-        node_exits
-            .entry((edge.to.as_str(), !going_down))
-            .or_default()
-            .push((idx, entry_sort_key_x));
+        if !column_anchors.is_pinned(idx, false) {
+            node_exits
+                .entry((edge.to.as_str(), !going_down))
+                .or_default()
+                .push((idx, entry_sort_key_x));
+        }
     }
 
     // Sort and optimize anchor order
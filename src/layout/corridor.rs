@@ -115,10 +115,10 @@ pub fn find_gap_center_x(
         return (40.0 + first_left) / 2.0;
     }
 
-    if gap_index >= boundaries.len() {
-        if let Some(&(_, last_right)) = boundaries.last() {
-            return last_right + entity_margin + 50.0;
-        }
+    if gap_index >= boundaries.len()
+        && let Some(&(_, last_right)) = boundaries.last()
+    {
+        return last_right + entity_margin + 50.0;
     }
 
     if gap_index > 0 && gap_index < boundaries.len() {
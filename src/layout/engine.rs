@@ -2,16 +2,22 @@
 
 use crate::ir::GraphIR;
 use crate::measure::TextMetrics;
+use std::collections::HashMap;
 
 use super::analysis::{
     analyze_channel_edges, analyze_corridors, build_node_level_lookup, build_node_order,
-    calculate_dynamic_channel_gaps, calculate_self_ref_reserve, count_edges_per_node,
+    calculate_dynamic_channel_gaps, calculate_row_exit_reserve, calculate_self_ref_reserve,
+    count_edges_per_node,
+    widen_channels_for_labels,
 };
-use super::anchors::calculate_edge_anchors;
+use super::anchors::{calculate_edge_anchors, ColumnAnchors};
 use super::lanes::{assign_channel_lanes, calculate_multi_level_corridor_x};
+use super::layering::assign_levels;
+use super::notes::{measure_notes, place_notes};
+use super::ordering::minimise_crossings;
 use super::placement::{build_node_positions, calculate_node_sizes, group_nodes_by_level, place_nodes};
 use super::straighten::straighten_edges;
-use super::types::Layout;
+use super::types::{Direction, Layout, LayoutGroup, LayoutNode, Notes};
 use super::waypoints::route_edges;
 
 /// Layout engine configuration and computation.
@@ -26,6 +32,10 @@ pub struct LayoutEngine {
     pub(crate) entity_margin: f64,
     /// Longest detour absorbed by the straightening pass
     pub(crate) jog_tolerance: f64,
+    pub(crate) direction: Direction,
+    /// Space between a group's border and its members
+    pub(crate) group_padding: f64,
+    pub(crate) notes: Notes,
 }
 
 impl Default for LayoutEngine {
@@ -42,13 +52,42 @@ impl Default for LayoutEngine {
             corner_radius: 32.0,
             entity_margin: 30.0,
             jog_tolerance: 20.0,
+            direction: Direction::default(),
+            // Room for the group name above the first row of members.
+            group_padding: 24.0,
+            notes: Notes::default(),
         }
     }
 }
 
 impl LayoutEngine {
+    pub fn with_direction(direction: Direction) -> Self {
+        Self {
+            direction,
+            ..Self::default()
+        }
+    }
+
+    /// The same engine, drawing a note beside every documented entity when
+    /// `notes` is `Show`.
+    pub fn with_notes(self, notes: Notes) -> Self {
+        Self { notes, ..self }
+    }
+
     /// Compute layout for the given graph.
+    ///
+    /// Every phase works top-down. For a sideways direction the nodes are
+    /// measured with width and height swapped, and the finished layout is
+    /// transposed; bottom-up and right-left are then mirrored.
     pub fn layout(&self, ir: &GraphIR) -> Layout {
+        let transposed = self.direction.is_horizontal();
+
+        // Phase 0: Levels for nodes without a hint, then an order within each
+        // level that avoids crossings
+        let mut ranked = assign_levels(ir, &self.metrics, self.node_gap_x, transposed);
+        minimise_crossings(&mut ranked);
+        let ir = &ranked;
+
         // Phase 1: Edge analysis
         let node_level = build_node_level_lookup(ir);
         let edge_count_per_node = count_edges_per_node(ir, &node_level);
@@ -63,13 +102,16 @@ impl LayoutEngine {
             analyze_corridors(ir, &node_level, &node_order, self.lane_spacing);
 
         // Phase 4: Dynamic channel gaps
-        let dynamic_channel_gap = calculate_dynamic_channel_gaps(
+        let mut dynamic_channel_gap = calculate_dynamic_channel_gaps(
             &level_keys,
             &channel_edge_count,
             self.entity_margin,
             self.lane_spacing,
             self.channel_gap,
         );
+        if transposed {
+            widen_channels_for_labels(&mut dynamic_channel_gap, ir, &channel_edges_list, &self.metrics);
+        }
 
         // Phase 5: Node sizing and placement
         let node_sizes = calculate_node_sizes(
@@ -77,17 +119,54 @@ impl LayoutEngine {
             &edge_count_per_node,
             &self.metrics,
             self.anchor_spacing,
+            transposed,
         );
 
         let self_ref_reserve = calculate_self_ref_reserve(ir, &self.metrics, self.lane_spacing);
 
+        // Ends anchored on a column sit beside its row, and in a top-down
+        // layout leave sideways through stubs that need room next to the node.
+        let column_anchors = ColumnAnchors::new(ir, &self.metrics, self.direction);
+        let mut side_reserve = self_ref_reserve.clone();
+        for (id, stubs) in
+            calculate_row_exit_reserve(ir, &column_anchors, self.entity_margin, self.lane_spacing)
+        {
+            *side_reserve.entry(id).or_insert(0.0) += stubs;
+        }
+
+        // Notes come after all of that, and may make their level taller.
+        let notes = match self.notes {
+            Notes::Show => measure_notes(ir, &self.metrics, transposed),
+            Notes::Hide => HashMap::new(),
+        };
+        let mut note_reserve = side_reserve.clone();
+        for (&id, note) in &notes {
+            *note_reserve.entry(id).or_insert(0.0) += self.lane_spacing + note.width;
+        }
+        let note_heights: HashMap<&str, f64> =
+            notes.iter().map(|(&id, note)| (id, note.height)).collect();
+
+        // A band must at least fit its group's name, which runs along the
+        // rows unless the layout is turned sideways.
+        let group_bands: Vec<(&str, f64)> = ir
+            .groups
+            .iter()
+            .map(|g| {
+                let label = if transposed { 0.0 } else { self.metrics.text_width(&g.name) };
+                (g.name.as_str(), (label - self.group_padding).max(0.0))
+            })
+            .collect();
+
         let node_placement = place_nodes(
             &levels,
             &level_keys,
             &node_sizes,
             &corridor_analysis.gap_extra_width,
-            &self_ref_reserve,
+            &note_reserve,
+            &note_heights,
             &dynamic_channel_gap,
+            &group_bands,
+            self.group_padding,
             self.node_gap_x,
             self.node_gap_y,
             self.channel_gap,
@@ -103,6 +182,7 @@ impl LayoutEngine {
             &corridor_analysis.edge_gap_index,
             &node_placement.layout_nodes,
             &levels,
+            &column_anchors,
             self.entity_margin,
             self.anchor_spacing,
         );
@@ -143,6 +223,8 @@ impl LayoutEngine {
             &node_placement,
             &levels,
             &multi_level_corridor_x,
+            &column_anchors,
+            &self_ref_reserve,
             self.anchor_spacing,
             self.lane_spacing,
             self.channel_gap,
@@ -154,6 +236,7 @@ impl LayoutEngine {
         straighten_edges(
             &mut layout_edges,
             &node_positions,
+            &column_anchors,
             self.jog_tolerance,
             // Anchors are distributed one `anchor_spacing` apart; sliding one
             // must not crowd its neighbour (their cardinality labels need the
@@ -161,13 +244,348 @@ impl LayoutEngine {
             self.anchor_spacing,
         );
 
-        Layout {
+        let groups = group_regions(
+            ir,
+            &node_placement.layout_nodes,
+            &note_reserve,
+            &note_heights,
+            self.group_padding,
+        );
+        let notes = place_notes(
+            &node_placement.layout_nodes,
+            &notes,
+            &side_reserve,
+            self.lane_spacing,
+        );
+
+        let mut layout = Layout {
             nodes: node_placement.layout_nodes,
             edges: layout_edges,
+            groups,
+            notes,
             width: node_placement.max_width,
             height: node_placement.total_height,
             channel_gap: self.channel_gap,
             corner_radius: self.corner_radius,
+        };
+        orient(&mut layout, self.direction);
+        layout
+    }
+}
+
+/// The box around each group's members, their self-reference loops, row
+/// stubs and notes included, with `padding` on every side.
+fn group_regions(
+    ir: &GraphIR,
+    nodes: &[LayoutNode],
+    side_reserve: &HashMap<&str, f64>,
+    note_heights: &HashMap<&str, f64>,
+    padding: f64,
+) -> Vec<LayoutGroup> {
+    ir.groups
+        .iter()
+        .filter_map(|group| {
+            let members = nodes.iter().filter(|n| {
+                ir.nodes
+                    .iter()
+                    .any(|m| m.id == n.id && m.group.as_deref() == Some(group.name.as_str()))
+            });
+            let (mut l, mut t, mut r, mut b) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+            for node in members {
+                let reserve = *side_reserve.get(node.id.as_str()).unwrap_or(&0.0);
+                let note = *note_heights.get(node.id.as_str()).unwrap_or(&0.0);
+                l = l.min(node.x);
+                t = t.min(node.y);
+                r = r.max(node.x + node.width + reserve);
+                b = b.max(node.y + node.height.max(note));
+            }
+            (l <= r).then(|| LayoutGroup {
+                name: group.name.clone(),
+                x: l - padding,
+                y: t - padding,
+                width: r - l + padding * 2.0,
+                height: b - t + padding * 2.0,
+            })
+        })
+        .collect()
+}
+
+/// Turn a top-down layout into one running in `direction`.
+fn orient(layout: &mut Layout, direction: Direction) {
+    if direction.is_horizontal() {
+        for node in &mut layout.nodes {
+            std::mem::swap(&mut node.x, &mut node.y);
+            std::mem::swap(&mut node.width, &mut node.height);
+        }
+        for group in &mut layout.groups {
+            std::mem::swap(&mut group.x, &mut group.y);
+            std::mem::swap(&mut group.width, &mut group.height);
+        }
+        for note in &mut layout.notes {
+            std::mem::swap(&mut note.x, &mut note.y);
+            std::mem::swap(&mut note.width, &mut note.height);
+        }
+        for edge in &mut layout.edges {
+            for point in &mut edge.waypoints {
+                *point = (point.1, point.0);
+            }
+        }
+        std::mem::swap(&mut layout.width, &mut layout.height);
+    }
+
+    match direction {
+        Direction::BottomUp => {
+            for node in &mut layout.nodes {
+                node.y = layout.height - node.y - node.height;
+            }
+            for group in &mut layout.groups {
+                group.y = layout.height - group.y - group.height;
+            }
+            for note in &mut layout.notes {
+                note.y = layout.height - note.y - note.height;
+            }
+            for point in layout.edges.iter_mut().flat_map(|e| e.waypoints.iter_mut()) {
+                point.1 = layout.height - point.1;
+            }
+        }
+        Direction::RightLeft => {
+            for node in &mut layout.nodes {
+                node.x = layout.width - node.x - node.width;
+            }
+            for group in &mut layout.groups {
+                group.x = layout.width - group.x - group.width;
+            }
+            for note in &mut layout.notes {
+                note.x = layout.width - note.x - note.width;
+            }
+            for point in layout.edges.iter_mut().flat_map(|e| e.waypoints.iter_mut()) {
+                point.0 = layout.width - point.0;
+            }
+        }
+        Direction::TopDown | Direction::LeftRight => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::DetailLevel;
+    use crate::parser::Parser;
+
+    fn layout(direction: Direction) -> Layout {
+        let input = r#"
+            entity User {
+                id int pk
+                email varchar(255)
+            }
+            entity Order { id int pk }
+            rel { User 1 -- * Order }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        LayoutEngine::with_direction(direction).layout(&ir)
+    }
+
+    #[test]
+    fn test_directions() {
+        let top_down = layout(Direction::TopDown);
+        for direction in [
+            Direction::TopDown,
+            Direction::BottomUp,
+            Direction::LeftRight,
+            Direction::RightLeft,
+        ] {
+            let layout = layout(direction);
+            let user = layout.nodes.iter().find(|n| n.id == "User").unwrap();
+            let order = layout.nodes.iter().find(|n| n.id == "Order").unwrap();
+            let parent_first = match direction {
+                Direction::TopDown => user.y + user.height < order.y,
+                Direction::BottomUp => order.y + order.height < user.y,
+                Direction::LeftRight => user.x + user.width < order.x,
+                Direction::RightLeft => order.x + order.width < user.x,
+            };
+            assert!(parent_first, "{:?}", direction);
+
+            // Entities keep their own size whichever way the levels run.
+            let same = top_down.nodes.iter().find(|n| n.id == "User").unwrap();
+            assert_eq!((user.width, user.height), (same.width, same.height));
+
+            // The edge runs from one border to the other.
+            let path = &layout.edges[0].waypoints;
+            let (start, end) = (path[0], path[path.len() - 1]);
+            for ((x, y), node) in [(start, user), (end, order)] {
+                let on_x = x == node.x || x == node.x + node.width;
+                let on_y = y == node.y || y == node.y + node.height;
+                assert!(on_x || on_y, "{:?}: {:?} not on {}", direction, (x, y), node.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_anchors() {
+        let input = r#"
+            entity User {
+                id int pk
+                email varchar(255)
+            }
+            entity Order {
+                id int pk
+                user_id int
+            }
+            rel { User.id 1 -- * Order.user_id }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        let metrics = TextMetrics::default();
+
+        for direction in [
+            Direction::TopDown,
+            Direction::LeftRight,
+            Direction::BottomUp,
+            Direction::RightLeft,
+        ] {
+            let layout = LayoutEngine::with_direction(direction).layout(&ir);
+            let node = |id: &str| layout.nodes.iter().find(|n| n.id == id).unwrap();
+            let points = &layout.edges[0].waypoints;
+
+            // Each end meets a side border, level with its column's row.
+            for (point, id, row) in [(points[0], "User", 0), (points[points.len() - 1], "Order", 1)] {
+                let n = node(id);
+                let on_side = point.0 == n.x || point.0 == n.x + n.width;
+                assert!(on_side, "{:?}: {:?} not on a side of {}", direction, point, id);
+                let row_y = n.y + metrics.row_center(row);
+                assert!(
+                    (point.1 - row_y).abs() <= metrics.line_height / 2.0,
+                    "{:?}: {:?} not beside row {} of {}",
+                    direction,
+                    point,
+                    row,
+                    id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_group_regions() {
+        let input = r#"
+            entity A {
+                @hint.group = one
+                id int pk
+            }
+            entity B {
+                @hint.group = one
+                id int pk
+            }
+            entity C {
+                @hint.group = two
+                id int pk
+            }
+            entity D { id int pk }
+            rel {
+                A 1 -- * B
+                A 1 -- * C
+                C 1 -- * D
+                D 1 -- * B
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+
+        for direction in [Direction::TopDown, Direction::LeftRight, Direction::BottomUp] {
+            let layout = LayoutEngine::with_direction(direction).layout(&ir);
+            assert_eq!(layout.groups.len(), 2);
+
+            let inside = |node: &LayoutNode, g: &LayoutGroup| {
+                node.x > g.x
+                    && node.y > g.y
+                    && node.x + node.width < g.x + g.width
+                    && node.y + node.height < g.y + g.height
+            };
+            let apart = |node: &LayoutNode, g: &LayoutGroup| {
+                node.x > g.x + g.width
+                    || node.y > g.y + g.height
+                    || node.x + node.width < g.x
+                    || node.y + node.height < g.y
+            };
+            for group in &layout.groups {
+                for node in &layout.nodes {
+                    let member = ir
+                        .nodes
+                        .iter()
+                        .any(|n| n.id == node.id && n.group.as_deref() == Some(group.name.as_str()));
+                    if member {
+                        assert!(inside(node, group), "{:?}: {} outside {}", direction, node.id, group.name);
+                    } else {
+                        assert!(apart(node, group), "{:?}: {} inside {}", direction, node.id, group.name);
+                    }
+                }
+            }
+
+            let (a, b) = (&layout.groups[0], &layout.groups[1]);
+            let disjoint = a.x + a.width < b.x
+                || b.x + b.width < a.x
+                || a.y + a.height < b.y
+                || b.y + b.height < a.y;
+            assert!(disjoint, "{:?}: groups overlap", direction);
+        }
+    }
+
+    #[test]
+    fn test_notes() {
+        let input = r#"
+            /// Anyone who has signed up, whether or not they ever ordered
+            entity User { id int pk }
+            entity Order {
+                id int pk
+                user_id int
+            }
+            entity Product { id int pk }
+            rel {
+                User 1 -- * Order
+                User 1 -- * User
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+
+        let hidden = LayoutEngine::default().layout(&ir);
+        assert!(hidden.notes.is_empty());
+
+        for direction in [
+            Direction::TopDown,
+            Direction::LeftRight,
+            Direction::BottomUp,
+            Direction::RightLeft,
+        ] {
+            let layout = LayoutEngine::with_direction(direction)
+                .with_notes(Notes::Show)
+                .layout(&ir);
+            assert_eq!(layout.notes.len(), 1);
+            let note = &layout.notes[0];
+            let user = layout.nodes.iter().find(|n| n.id == "User").unwrap();
+
+            // After its entity along the level, past the self-reference loop
+            let beside = match direction {
+                Direction::TopDown | Direction::BottomUp => note.x > user.x + user.width,
+                Direction::LeftRight | Direction::RightLeft => note.y > user.y + user.height,
+            };
+            assert!(beside, "{:?}: note not beside User", direction);
+            for edge in &layout.edges {
+                for &(x, y) in &edge.waypoints {
+                    let inside = x > note.x && x < note.x + note.width && y > note.y && y < note.y + note.height;
+                    assert!(!inside, "{:?}: edge runs through the note", direction);
+                }
+            }
+
+            for node in &layout.nodes {
+                let apart = node.x >= note.x + note.width
+                    || note.x >= node.x + node.width
+                    || node.y >= note.y + note.height
+                    || note.y >= node.y + node.height;
+                assert!(apart, "{:?}: note covers {}", direction, node.id);
+            }
+            assert!(note.x + note.width <= layout.width && note.y + note.height <= layout.height);
         }
     }
 }
@@ -76,9 +76,15 @@ pub fn assign_channel_lanes<'a>(
 }
 
 /// Sort channel edges for lane assignment.
+///
+/// Each edge gets one key, so the order is total however the edges compare:
+/// edges heading down come first, then by the corridor they use (left to
+/// right going down, right to left going up), then by their target. Edges
+/// turning left from their corridor go by target right to left, those
+/// turning right left to right. Ties keep the edges' own order.
 #[allow(clippy::too_many_arguments)]
 pub fn sort_channel_edges<'a>(
-    edges: &mut Vec<(usize, f64, bool)>,
+    edges: &mut [(usize, f64, bool)],
     ir: &'a GraphIR,
     node_level: &HashMap<&str, i64>,
     node_positions: &HashMap<&str, &LayoutNode>,
@@ -88,100 +94,32 @@ pub fn sort_channel_edges<'a>(
     channel_level: i64,
     entity_margin: f64,
 ) {
-    edges.sort_by(|a, b| {
-        let edge_a = &ir.edges[a.0];
-        let edge_b = &ir.edges[b.0];
-        let from_level_a = *node_level.get(edge_a.from.as_str()).unwrap_or(&0);
-        let from_level_b = *node_level.get(edge_b.from.as_str()).unwrap_or(&0);
-        let to_level_a = *node_level.get(edge_a.to.as_str()).unwrap_or(&0);
-        let to_level_b = *node_level.get(edge_b.to.as_str()).unwrap_or(&0);
-        let is_down_a = to_level_a > channel_level;
-        let is_down_b = to_level_b > channel_level;
+    let center_x = |id: &str| node_positions.get(id).map(|n| n.x + n.width / 2.0).unwrap_or(0.0);
 
-        let get_corridor_x = |edge_idx: usize| -> f64 {
-            if let Some(&gap_idx) = edge_gap_index.get(&edge_idx) {
-                find_gap_center_x(layout_nodes, levels, channel_level + 1, gap_idx, entity_margin)
-            } else {
-                let edge = &ir.edges[edge_idx];
-                node_positions
-                    .get(edge.from.as_str())
-                    .map(|n| n.x + n.width / 2.0)
-                    .unwrap_or(0.0)
-            }
-        };
+    let key = |edge_idx: usize| -> (bool, f64, f64) {
+        let edge = &ir.edges[edge_idx];
+        let to_level = *node_level.get(edge.to.as_str()).unwrap_or(&0);
+        let is_down = to_level > channel_level;
 
-        let get_to_x = |edge: &crate::ir::Edge| -> f64 {
-            node_positions
-                .get(edge.to.as_str())
-                .map(|n| n.x + n.width / 2.0)
-                .unwrap_or(0.0)
+        let corridor_x = match edge_gap_index.get(&edge_idx) {
+            Some(&gap_idx) => find_gap_center_x(layout_nodes, levels, channel_level + 1, gap_idx, entity_margin),
+            None => center_x(&edge.from),
         };
+        let to_x = center_x(&edge.to);
+        let corridor = if is_down { corridor_x } else { -corridor_x };
+        let target = if to_x < corridor_x { -to_x } else { to_x };
+        (!is_down, corridor, target)
+    };
+    let keys: HashMap<usize, (bool, f64, f64)> = edges.iter().map(|&(idx, _, _)| (idx, key(idx))).collect();
 
-        match is_down_b.cmp(&is_down_a) {
-            std::cmp::Ordering::Equal => {
-                let a_multi = (to_level_a - from_level_a).abs() > 1;
-                let b_multi = (to_level_b - from_level_b).abs() > 1;
-
-                if a_multi || b_multi {
-                    let corridor_x_a = get_corridor_x(a.0);
-                    let corridor_x_b = get_corridor_x(b.0);
-                    let corridor_diff = (corridor_x_a - corridor_x_b).abs();
-
-                    if corridor_diff > 1.0 {
-                        if is_down_a {
-                            corridor_x_a
-                                .partial_cmp(&corridor_x_b)
-                                .unwrap_or(std::cmp::Ordering::Equal)
-                        } else {
-                            corridor_x_b
-                                .partial_cmp(&corridor_x_a)
-                                .unwrap_or(std::cmp::Ordering::Equal)
-                        }
-                    } else {
-                        let to_x_a = get_to_x(edge_a);
-                        let to_x_b = get_to_x(edge_b);
-                        let avg_to_x = (to_x_a + to_x_b) / 2.0;
-                        if corridor_x_a < avg_to_x {
-                            to_x_a
-                                .partial_cmp(&to_x_b)
-                                .unwrap_or(std::cmp::Ordering::Equal)
-                        } else {
-                            to_x_b
-                                .partial_cmp(&to_x_a)
-                                .unwrap_or(std::cmp::Ordering::Equal)
-                        }
-                    }
-                } else {
-                    let dist_cmp = if is_down_a {
-                        to_level_a.cmp(&to_level_b)
-                    } else {
-                        to_level_b.cmp(&to_level_a)
-                    };
-                    match dist_cmp {
-                        std::cmp::Ordering::Equal => {
-                            let to_x_a = get_to_x(edge_a);
-                            let to_x_b = get_to_x(edge_b);
-                            let from_x_a = node_positions
-                                .get(edge_a.from.as_str())
-                                .map(|n| n.x + n.width / 2.0)
-                                .unwrap_or(0.0);
-                            let avg_to_x = (to_x_a + to_x_b) / 2.0;
-                            if from_x_a > avg_to_x {
-                                to_x_b
-                                    .partial_cmp(&to_x_a)
-                                    .unwrap_or(std::cmp::Ordering::Equal)
-                            } else {
-                                to_x_a
-                                    .partial_cmp(&to_x_b)
-                                    .unwrap_or(std::cmp::Ordering::Equal)
-                            }
-                        }
-                        ord => ord,
-                    }
-                }
-            }
-            ord => ord,
-        }
+    edges.sort_by(|a, b| {
+        let (key_a, key_b) = (keys[&a.0], keys[&b.0]);
+        key_a
+            .0
+            .cmp(&key_b.0)
+            .then(key_a.1.total_cmp(&key_b.1))
+            .then(key_a.2.total_cmp(&key_b.2))
+            .then(a.0.cmp(&b.0))
     });
 }
 
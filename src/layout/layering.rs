@@ -0,0 +1,329 @@
+//! Automatic levels for nodes that have neither `@hint.level` nor a place in
+//! the arrangement.
+//!
+//! This is the layering step of a Sugiyama layout: relationships point from
+//! the "one" end to the "many" end, cycles are broken, and every unhinted node
+//! goes one level below the lowest of its parents. Rows that come out much
+//! wider than the rest then hand nodes down to the next level.
+
+use crate::ir::{GraphIR, Node};
+use crate::measure::TextMetrics;
+use std::collections::{BTreeMap, HashMap};
+
+use super::placement::content_size;
+
+/// Return a copy of `ir` in which every node has a level. Levels that were
+/// given explicitly are kept as they are.
+///
+/// When `transposed`, rows are balanced by the height of the nodes, which is
+/// what they will span once the layout is turned sideways.
+pub fn assign_levels(ir: &GraphIR, metrics: &TextMetrics, node_gap_x: f64, transposed: bool) -> GraphIR {
+    let mut ranked = ir.clone();
+    if ranked.nodes.iter().all(|n| n.level.is_some()) {
+        return ranked;
+    }
+
+    let count = ir.nodes.len();
+    let index: HashMap<&str, usize> = ir
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+
+    let mut edges = Vec::new();
+    let mut connected = vec![false; count];
+    for edge in &ir.edges {
+        let (Some(&from), Some(&to)) = (index.get(edge.from.as_str()), index.get(edge.to.as_str()))
+        else {
+            continue;
+        };
+        if from == to {
+            continue;
+        }
+        connected[from] = true;
+        connected[to] = true;
+        // The parent is the "one" end; `A * -- 1 B` hangs A below B.
+        if edge.from_cardinality.is_many() && !edge.to_cardinality.is_many() {
+            edges.push((to, from));
+        } else {
+            edges.push((from, to));
+        }
+    }
+
+    let children = break_cycles(count, &edges);
+    let mut levels: Vec<Option<i64>> = ir.nodes.iter().map(|n| n.level).collect();
+    longest_path_levels(&children, &connected, &mut levels);
+
+    let widths: Vec<f64> = ir
+        .nodes
+        .iter()
+        .map(|n| node_extent(n, metrics, transposed) + node_gap_x)
+        .collect();
+    let fixed: Vec<bool> = ir.nodes.iter().map(|n| n.level.is_some()).collect();
+    balance_rows(&children, &connected, &fixed, &widths, &mut levels);
+
+    for (node, level) in ranked.nodes.iter_mut().zip(levels) {
+        node.level = level;
+    }
+    ranked
+}
+
+fn node_extent(node: &Node, metrics: &TextMetrics, transposed: bool) -> f64 {
+    let (width, height) = content_size(node, metrics);
+    if transposed { height } else { width }
+}
+
+/// Child lists of `edges` with enough edges dropped to leave no cycle. Edges
+/// that close a cycle in a depth-first walk, in node order, are the ones
+/// dropped.
+fn break_cycles(count: usize, edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut out: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for &(parent, child) in edges {
+        if !out[parent].contains(&child) {
+            out[parent].push(child);
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        Open,
+        Done,
+    }
+
+    fn visit(node: usize, out: &[Vec<usize>], state: &mut [State], kept: &mut [Vec<usize>]) {
+        state[node] = State::Open;
+        for &child in &out[node] {
+            match state[child] {
+                State::Open => {} // back edge: closes a cycle
+                State::New => {
+                    kept[node].push(child);
+                    visit(child, out, state, kept);
+                }
+                State::Done => kept[node].push(child),
+            }
+        }
+        state[node] = State::Done;
+    }
+
+    let mut state = vec![State::New; count];
+    let mut kept = vec![Vec::new(); count];
+    for node in 0..count {
+        if state[node] == State::New {
+            visit(node, &out, &mut state, &mut kept);
+        }
+    }
+    kept
+}
+
+/// Give each unhinted, connected node the level below its lowest parent, or
+/// the top level when it has none. Isolated nodes are left for `balance_rows`.
+fn longest_path_levels(children: &[Vec<usize>], connected: &[bool], levels: &mut [Option<i64>]) {
+    let count = children.len();
+    let mut in_degree = vec![0; count];
+    for list in children {
+        for &child in list {
+            in_degree[child] += 1;
+        }
+    }
+
+    let top = levels.iter().flatten().copied().min().unwrap_or(0);
+    let mut below: Vec<Option<i64>> = vec![None; count];
+    let mut queue: Vec<usize> = (0..count).filter(|&n| in_degree[n] == 0).collect();
+    queue.reverse();
+
+    while let Some(node) = queue.pop() {
+        if levels[node].is_none() && connected[node] {
+            levels[node] = Some(below[node].unwrap_or(top));
+        }
+        for &child in &children[node] {
+            if let Some(level) = levels[node] {
+                below[child] = Some(below[child].map_or(level + 1, |b| b.max(level + 1)));
+            }
+            in_degree[child] -= 1;
+            if in_degree[child] == 0 {
+                queue.push(child);
+            }
+        }
+    }
+}
+
+/// Rounding allowance when comparing a row against the target width.
+const ROW_SLACK: f64 = 0.5;
+
+/// Even out row widths. Rows wider than the target hand unhinted nodes down
+/// a level, as long as that keeps them above their children; isolated nodes
+/// then fill whichever rows have room left.
+fn balance_rows(
+    children: &[Vec<usize>],
+    connected: &[bool],
+    fixed: &[bool],
+    widths: &[f64],
+    levels: &mut [Option<i64>],
+) {
+    let count = widths.len();
+    let total: f64 = widths.iter().sum();
+    let widest = widths.iter().copied().fold(0.0, f64::max);
+    let target = widest.max(total / (count as f64).sqrt().ceil());
+
+    let mut rows: BTreeMap<i64, f64> = BTreeMap::new();
+    for (node, level) in levels.iter().enumerate() {
+        if let Some(level) = level {
+            *rows.entry(*level).or_default() += widths[node];
+        }
+    }
+
+    let mut level = rows.keys().next().copied().unwrap_or(0);
+    while let Some((&current, _)) = rows.range(level..).next() {
+        level = current;
+        while rows[&level] > target + ROW_SLACK {
+            let movable = (0..count).rev().find(|&n| {
+                levels[n] == Some(level)
+                    && !fixed[n]
+                    && children[n].iter().all(|&c| levels[c].is_some_and(|l| l > level + 1))
+            });
+            let Some(node) = movable else {
+                break;
+            };
+            levels[node] = Some(level + 1);
+            *rows.get_mut(&level).unwrap() -= widths[node];
+            *rows.entry(level + 1).or_default() += widths[node];
+        }
+        level += 1;
+    }
+
+    for node in 0..count {
+        if levels[node].is_some() || connected[node] {
+            continue;
+        }
+        let roomy = rows
+            .iter()
+            .filter(|&(_, &width)| width + widths[node] <= target + ROW_SLACK)
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(&level, _)| level);
+        let level = roomy.unwrap_or_else(|| rows.keys().next_back().map_or(0, |l| l + 1));
+        levels[node] = Some(level);
+        *rows.entry(level).or_default() += widths[node];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::DetailLevel;
+    use crate::parser::Parser;
+
+    fn levels(input: &str) -> HashMap<String, i64> {
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        assign_levels(&ir, &TextMetrics::default(), 100.0, false)
+            .nodes
+            .into_iter()
+            .map(|n| (n.id, n.level.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_ranks_from_relationships() {
+        let levels = levels(
+            r#"
+            entity Order { id int pk }
+            entity User { id int pk }
+            entity Item { id int pk }
+            rel {
+                User 1 -- * Order
+                Item * -- 1 Order
+            }
+        "#,
+        );
+        assert_eq!(levels["User"], 0);
+        assert_eq!(levels["Order"], 1);
+        assert_eq!(levels["Item"], 2);
+    }
+
+    #[test]
+    fn test_keeps_explicit_levels() {
+        let levels = levels(
+            r#"
+            entity User {
+                @hint.level = 3
+                id int pk
+            }
+            entity Order { id int pk }
+            rel { User 1 -- * Order }
+        "#,
+        );
+        assert_eq!(levels["User"], 3);
+        assert_eq!(levels["Order"], 4);
+    }
+
+    #[test]
+    fn test_breaks_cycles() {
+        let levels = levels(
+            r#"
+            entity A { id int pk }
+            entity B { id int pk }
+            entity C { id int pk }
+            rel {
+                A 1 -- * B
+                B 1 -- * C
+                C 1 -- * A
+            }
+        "#,
+        );
+        assert_eq!((levels["A"], levels["B"], levels["C"]), (0, 1, 2));
+    }
+
+    #[test]
+    fn test_balances_wide_rows() {
+        let levels = levels(
+            r#"
+            entity Hub { id int pk }
+            entity A { id int pk }
+            entity B { id int pk }
+            entity C { id int pk }
+            entity D { id int pk }
+            entity E { id int pk }
+            entity F { id int pk }
+            entity G { id int pk }
+            entity H { id int pk }
+            rel {
+                Hub 1 -- * A
+                Hub 1 -- * B
+                Hub 1 -- * C
+                Hub 1 -- * D
+                Hub 1 -- * E
+                Hub 1 -- * F
+                Hub 1 -- * G
+                Hub 1 -- * H
+            }
+        "#,
+        );
+        assert_eq!(levels["Hub"], 0);
+
+        let mut rows: BTreeMap<i64, usize> = BTreeMap::new();
+        for level in levels.values() {
+            *rows.entry(*level).or_default() += 1;
+        }
+        assert!(rows.values().all(|&n| n <= 3), "{:?}", rows);
+        assert!(levels.values().filter(|&&l| l > 0).count() == 8);
+    }
+
+    #[test]
+    fn test_spreads_isolated_nodes() {
+        let levels = levels(
+            r#"
+            entity A { id int pk }
+            entity B { id int pk }
+            entity C { id int pk }
+            entity D { id int pk }
+        "#,
+        );
+        let mut rows: BTreeMap<i64, usize> = BTreeMap::new();
+        for level in levels.values() {
+            *rows.entry(*level).or_default() += 1;
+        }
+        assert_eq!(rows.values().copied().collect::<Vec<_>>(), vec![2, 2]);
+    }
+}
@@ -8,6 +8,9 @@ mod anchors;
 mod corridor;
 mod engine;
 mod lanes;
+mod layering;
+mod notes;
+mod ordering;
 mod placement;
 mod routing;
 mod straighten;
@@ -15,7 +18,7 @@ mod types;
 mod waypoints;
 
 pub use engine::LayoutEngine;
-pub use types::{Layout, LayoutEdge, LayoutNode};
+pub use types::{Direction, Layout, LayoutEdge, LayoutGroup, LayoutNode, LayoutNote, Notes};
 
 #[cfg(test)]
 mod tests {
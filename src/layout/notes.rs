@@ -0,0 +1,71 @@
+//! Note boxes beside documented entities.
+//!
+//! A note is measured like a node and placed after its entity in the same
+//! level, past any self-reference loops and row stubs, so it never sits in a
+//! routing channel.
+
+use crate::ir::{GraphIR, NodeKind};
+use crate::measure::TextMetrics;
+use std::collections::HashMap;
+
+use super::types::{LayoutNode, LayoutNote};
+
+/// Widest line of a note, in characters of `TextMetrics::char_width`.
+const NOTE_COLUMNS: f64 = 32.0;
+
+/// A note's wrapped text and its size in the top-down frame.
+pub struct NoteBox {
+    pub lines: Vec<String>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Wrap and measure the note of every node with documentation. Ghosts keep
+/// theirs as a tooltip only.
+pub fn measure_notes<'a>(
+    ir: &'a GraphIR,
+    metrics: &TextMetrics,
+    transposed: bool,
+) -> HashMap<&'a str, NoteBox> {
+    ir.nodes
+        .iter()
+        .filter(|node| node.kind != NodeKind::Ghost)
+        .filter_map(|node| {
+            let lines = metrics.wrap(node.doc.as_deref()?, NOTE_COLUMNS * metrics.char_width);
+            if lines.is_empty() {
+                return None;
+            }
+            let text_width = lines.iter().map(|l| metrics.text_width(l)).fold(0.0, f64::max);
+            let width = text_width + metrics.padding_x * 2.0;
+            let height = lines.len() as f64 * metrics.line_height + metrics.padding_y * 2.0;
+            // Sideways layouts are computed top-down and transposed afterwards.
+            let (width, height) = if transposed { (height, width) } else { (width, height) };
+            Some((node.id.as_str(), NoteBox { lines, width, height }))
+        })
+        .collect()
+}
+
+/// Put each note `gap` past whatever else hangs off its node's right border,
+/// level with the node's top.
+pub fn place_notes(
+    nodes: &[LayoutNode],
+    notes: &HashMap<&str, NoteBox>,
+    side_reserve: &HashMap<&str, f64>,
+    gap: f64,
+) -> Vec<LayoutNote> {
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let note = notes.get(node.id.as_str())?;
+            let reserve = *side_reserve.get(node.id.as_str()).unwrap_or(&0.0);
+            Some(LayoutNote {
+                id: node.id.clone(),
+                x: node.x + node.width + reserve + gap,
+                y: node.y,
+                width: note.width,
+                height: note.height,
+                lines: note.lines.clone(),
+            })
+        })
+        .collect()
+}
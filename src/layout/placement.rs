@@ -1,6 +1,6 @@
 //! Node placement and sizing.
 
-use crate::ir::{GraphIR, Node};
+use crate::ir::{GraphIR, Node, NodeKind};
 use crate::measure::TextMetrics;
 use std::collections::HashMap;
 
@@ -12,16 +12,14 @@ pub fn calculate_node_sizes(
     edge_count_per_node: &HashMap<(&str, bool), usize>,
     metrics: &TextMetrics,
     anchor_spacing: f64,
+    transposed: bool,
 ) -> HashMap<String, (f64, f64)> {
     let mut node_sizes: HashMap<String, (f64, f64)> = HashMap::new();
 
     for node in &ir.nodes {
-        let columns: Vec<(String, String)> = node
-            .columns
-            .iter()
-            .map(|c| (c.name.clone(), c.typ.clone()))
-            .collect();
-        let (content_w, h) = metrics.node_size(&node.label, &columns);
+        let (content_w, h) = content_size(node, metrics);
+        // Sideways layouts are computed top-down and transposed afterwards.
+        let (content_w, h) = if transposed { (h, content_w) } else { (content_w, h) };
 
         let down_edges = *edge_count_per_node
             .get(&(node.id.as_str(), true))
@@ -44,6 +42,23 @@ pub fn calculate_node_sizes(
     node_sizes
 }
 
+/// Size of the box drawn for `node`, before room is made for its edges.
+pub fn content_size(node: &Node, metrics: &TextMetrics) -> (f64, f64) {
+    if node.kind == NodeKind::Ghost {
+        return metrics.stub_size(&node.label);
+    }
+    if node.detailed {
+        let rows: Vec<Vec<String>> = node.columns.iter().map(|c| c.cells()).collect();
+        return metrics.with_compartment(metrics.table_size(&node.label, &rows), &node.indexes);
+    }
+    let columns: Vec<(String, String)> = node
+        .columns
+        .iter()
+        .map(|c| (c.name.clone(), c.typ.clone()))
+        .collect();
+    metrics.node_size(&node.label, &columns)
+}
+
 /// Group nodes by level and sort within each level.
 pub fn group_nodes_by_level(ir: &GraphIR) -> (HashMap<i64, Vec<&Node>>, Vec<i64>) {
     let mut levels: HashMap<i64, Vec<&Node>> = HashMap::new();
@@ -64,60 +79,127 @@ pub fn group_nodes_by_level(ir: &GraphIR) -> (HashMap<i64, Vec<&Node>>, Vec<i64>
 }
 
 /// Place nodes with calculated gap widths.
+///
+/// Grouped nodes are placed in bands, one per group in the order of
+/// `group_bands`: a band spans the same columns in every row, wide enough for
+/// the group's widest run of nodes (and at least the width given with its
+/// name), with `group_padding` on every side. Bands whose rows overlap sit side
+/// by side, so group regions never overlap. Ungrouped nodes follow the last
+/// group region in their row.
 #[allow(clippy::too_many_arguments)]
 pub fn place_nodes(
     levels: &HashMap<i64, Vec<&Node>>,
     level_keys: &[i64],
     node_sizes: &HashMap<String, (f64, f64)>,
     gap_extra_width: &HashMap<usize, f64>,
-    self_ref_reserve: &HashMap<&str, f64>,
+    side_reserve: &HashMap<&str, f64>,
+    note_heights: &HashMap<&str, f64>,
     dynamic_channel_gap: &HashMap<i64, f64>,
+    group_bands: &[(&str, f64)],
+    group_padding: f64,
     node_gap_x: f64,
     node_gap_y: f64,
     base_channel_gap: f64,
 ) -> NodePlacement {
+    let band_of = |node: &Node| {
+        node.group
+            .as_deref()
+            .and_then(|g| group_bands.iter().position(|(name, _)| *name == g))
+    };
+
+    // Horizontal room a node takes up, including what follows it in its row.
+    let advance = |node_idx: usize, node: &Node| {
+        let (w, _) = node_sizes[&node.id];
+        let extra_gap = *gap_extra_width.get(&(node_idx + 1)).unwrap_or(&0.0);
+        // Self-reference loops, row stubs and notes hang off the right
+        // border and need room of their own, whether the next thing is an
+        // entity or the SVG edge.
+        let reserve = *side_reserve.get(node.id.as_str()).unwrap_or(&0.0);
+        w + node_gap_x + extra_gap + reserve
+    };
+
+    // Width and first and last row of each group.
+    let mut band_width: Vec<f64> = group_bands.iter().map(|&(_, min)| min).collect();
+    let mut band_rows: Vec<Option<(usize, usize)>> = vec![None; group_bands.len()];
+    for (row, level) in level_keys.iter().enumerate() {
+        let mut run = vec![0.0; group_bands.len()];
+        for (node_idx, node) in levels[level].iter().enumerate() {
+            if let Some(band) = band_of(node) {
+                run[band] += advance(node_idx, node);
+                let span = band_rows[band].get_or_insert((row, row));
+                span.1 = row;
+            }
+        }
+        for (band, run) in run.into_iter().enumerate() {
+            band_width[band] = band_width[band].max(run - node_gap_x);
+        }
+    }
+
+    let left = 40.0 + *gap_extra_width.get(&0).unwrap_or(&0.0);
+    let mut band_x = vec![0.0; group_bands.len()];
+    let mut band_end = vec![0.0; group_bands.len()];
+    for band in 0..group_bands.len() {
+        let Some((first, last)) = band_rows[band] else {
+            continue;
+        };
+        let after = (0..band)
+            .filter(|&b| band_rows[b].is_some_and(|(f, l)| f <= last && first <= l))
+            .map(|b| band_end[b] + node_gap_x)
+            .fold(left, f64::max);
+        band_x[band] = after + group_padding;
+        band_end[band] = band_x[band] + band_width[band] + group_padding;
+    }
+    let spans_row = |band: usize, row: usize| band_rows[band].is_some_and(|(f, l)| f <= row && row <= l);
+    let starts_at = |row: usize| band_rows.iter().flatten().any(|&(first, _)| first == row);
+    let ends_at = |row: usize| band_rows.iter().flatten().any(|&(_, last)| last == row);
+
     let mut layout_nodes = Vec::new();
     let mut channel_y: HashMap<i64, f64> = HashMap::new();
-    let mut y: f64 = 40.0;
-    let mut max_width: f64 = 0.0;
+    let mut y: f64 = 40.0 + if starts_at(0) { group_padding } else { 0.0 };
+    let mut max_width = band_end.iter().fold(0.0, |w: f64, &end| w.max(end + 40.0));
 
     for (i, &level) in level_keys.iter().enumerate() {
         let nodes_in_level = &levels[&level];
-        let gap0_extra = *gap_extra_width.get(&0).unwrap_or(&0.0);
-        let mut x: f64 = 40.0 + gap0_extra;
+        let mut cursor = band_x.clone();
+        let mut x = (0..group_bands.len())
+            .filter(|&b| spans_row(b, i))
+            .map(|b| band_end[b] + node_gap_x)
+            .fold(left, f64::max);
         let mut max_height: f64 = 0.0;
 
         for (node_idx, node) in nodes_in_level.iter().enumerate() {
             let (w, h) = node_sizes[&node.id];
+            let at = match band_of(node) {
+                Some(band) => &mut cursor[band],
+                None => &mut x,
+            };
             layout_nodes.push(LayoutNode {
                 id: node.id.clone(),
-                x,
+                x: *at,
                 y,
                 width: w,
                 height: h,
             });
-
-            let next_gap_idx = node_idx + 1;
-            let extra_gap = *gap_extra_width.get(&next_gap_idx).unwrap_or(&0.0);
-            // Self-reference loops hang off the right border and need room of
-            // their own, whether the next thing is an entity or the SVG edge.
-            let reserve = *self_ref_reserve.get(node.id.as_str()).unwrap_or(&0.0);
-            let effective_gap_x = node_gap_x + extra_gap + reserve;
-
-            x += w + effective_gap_x;
-            max_height = max_height.max(h);
+            *at += advance(node_idx, node);
+            // A note beside the node must not reach into the channel below.
+            let note = *note_heights.get(node.id.as_str()).unwrap_or(&0.0);
+            max_height = max_height.max(h).max(note);
         }
 
-        max_width = max_width.max(x - node_gap_x + 40.0);
+        if nodes_in_level.iter().any(|n| band_of(n).is_none()) {
+            max_width = max_width.max(x - node_gap_x + 40.0);
+        }
 
+        let below = if ends_at(i) { group_padding } else { 0.0 };
         if i < level_keys.len() - 1 {
+            let above_next = if starts_at(i + 1) { group_padding } else { 0.0 };
             let gap = *dynamic_channel_gap.get(&level).unwrap_or(&base_channel_gap);
             let total_space = node_gap_y + gap;
-            let channel_center = y + max_height + total_space / 2.0;
+            let channel_center = y + max_height + below + total_space / 2.0;
             channel_y.insert(level, channel_center);
-            y += max_height + total_space;
+            y += max_height + below + total_space + above_next;
         } else {
-            y += max_height + node_gap_y;
+            y += max_height + below + node_gap_y;
         }
     }
 
@@ -8,6 +8,7 @@
 
 use std::collections::HashMap;
 
+use super::anchors::ColumnAnchors;
 use super::types::{LayoutEdge, LayoutNode};
 
 const EPS: f64 = 1e-6;
@@ -39,17 +40,24 @@ struct Anchor<'a> {
     border: Border,
     /// Index into the anchor slots of this (node, border) pair.
     slot: usize,
+    /// Where routing put the anchor, and how far from there it may slide:
+    /// anywhere along the border, or only within its column row.
+    origin: f64,
+    reach: f64,
 }
 
 impl Anchor<'_> {
-    /// True when an anchor at `target` stays inside the border it slides along.
+    /// True when an anchor at `target` stays inside the border it slides along,
+    /// and within its reach.
     fn accepts(&self, target: f64) -> bool {
         let (min, max) = if self.border.slides_horizontally() {
             (self.node.x, self.node.x + self.node.width)
         } else {
             (self.node.y, self.node.y + self.node.height)
         };
-        target >= min + BORDER_MARGIN && target <= max - BORDER_MARGIN
+        target >= min + BORDER_MARGIN
+            && target <= max - BORDER_MARGIN
+            && (target - self.origin).abs() <= self.reach
     }
 }
 
@@ -63,9 +71,11 @@ struct AnchorMap<'a> {
 ///
 /// `jog_tolerance` is the longest jog segment that gets absorbed and
 /// `min_anchor_gap` the clearance kept between anchors on the same border.
+/// Ends pinned to a column row only slide within the row.
 pub fn straighten_edges<'a>(
     edges: &mut [LayoutEdge],
     node_positions: &HashMap<&'a str, &'a LayoutNode>,
+    column_anchors: &ColumnAnchors,
     jog_tolerance: f64,
     min_anchor_gap: f64,
 ) {
@@ -80,8 +90,10 @@ pub fn straighten_edges<'a>(
             if edge.is_self_ref {
                 return None;
             }
-            let from = anchors.register(node_positions, &edge.from, *edge.waypoints.first()?)?;
-            let to = anchors.register(node_positions, &edge.to, *edge.waypoints.last()?)?;
+            let mut from = anchors.register(node_positions, &edge.from, *edge.waypoints.first()?)?;
+            let mut to = anchors.register(node_positions, &edge.to, *edge.waypoints.last()?)?;
+            from.reach = column_anchors.reach(edge.edge_index, true);
+            to.reach = column_anchors.reach(edge.edge_index, false);
             Some((from, to))
         })
         .collect();
@@ -125,6 +137,8 @@ impl<'a> AnchorMap<'a> {
             node,
             border,
             slot: slots.len() - 1,
+            origin: coord,
+            reach: f64::INFINITY,
         })
     }
 
@@ -277,7 +291,7 @@ mod tests {
     fn straighten(nodes: &[LayoutNode], edges: &mut [LayoutEdge]) {
         let positions: HashMap<&str, &LayoutNode> =
             nodes.iter().map(|n| (n.id.as_str(), n)).collect();
-        straighten_edges(edges, &positions, 20.0, 12.0);
+        straighten_edges(edges, &positions, &ColumnAnchors::default(), 20.0, 12.0);
     }
 
     #[test]
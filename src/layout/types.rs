@@ -2,6 +2,59 @@
 
 use std::collections::HashMap;
 
+/// Which way the levels run: level 0 first, deeper levels after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// Top to bottom (default)
+    #[default]
+    TopDown,
+    /// Left to right
+    LeftRight,
+    /// Bottom to top
+    BottomUp,
+    /// Right to left
+    RightLeft,
+}
+
+impl Direction {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "tb" | "td" | "top-down" => Some(Self::TopDown),
+            "lr" | "left-right" => Some(Self::LeftRight),
+            "bt" | "bottom-up" => Some(Self::BottomUp),
+            "rl" | "right-left" => Some(Self::RightLeft),
+            _ => None,
+        }
+    }
+
+    /// True when levels are columns rather than rows.
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Self::LeftRight | Self::RightLeft)
+    }
+}
+
+/// Whether documented entities get a note box beside them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notes {
+    /// Documentation only shows as a tooltip (default)
+    #[default]
+    Hide,
+    /// Each documented entity has a note linked to it
+    Show,
+}
+
+impl Notes {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "hide" => Some(Self::Hide),
+            "show" => Some(Self::Show),
+            _ => None,
+        }
+    }
+}
+
 /// A positioned node in the layout.
 #[derive(Debug, Clone)]
 pub struct LayoutNode {
@@ -24,11 +77,38 @@ pub struct LayoutEdge {
     pub edge_index: usize,
 }
 
+/// The region drawn behind the members of a group.
+#[derive(Debug, Clone)]
+pub struct LayoutGroup {
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A note box holding an entity's documentation.
+#[derive(Debug, Clone)]
+pub struct LayoutNote {
+    /// The node the note belongs to
+    pub id: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// The documentation, wrapped to the note's width
+    pub lines: Vec<String>,
+}
+
 /// The complete layout result.
 #[derive(Debug, Clone)]
 pub struct Layout {
     pub nodes: Vec<LayoutNode>,
     pub edges: Vec<LayoutEdge>,
+    /// Group regions, in the order of `GraphIR::groups`
+    pub groups: Vec<LayoutGroup>,
+    /// Notes, when they are shown
+    pub notes: Vec<LayoutNote>,
     pub width: f64,
     pub height: f64,
     /// Gap for routing channels between levels
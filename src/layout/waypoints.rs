@@ -3,6 +3,7 @@
 use crate::ir::{GraphIR, Node};
 use std::collections::HashMap;
 
+use super::anchors::ColumnAnchors;
 use super::corridor::find_safe_corridors;
 use super::routing::{
     calculate_lane_offset, distribute_anchor, route_adjacent_level_direct,
@@ -22,6 +23,8 @@ pub fn route_edges<'a>(
     node_placement: &NodePlacement,
     levels: &HashMap<i64, Vec<&'a Node>>,
     multi_level_corridor_x: &HashMap<usize, f64>,
+    column_anchors: &ColumnAnchors,
+    self_ref_reserve: &HashMap<&str, f64>,
     anchor_spacing: f64,
     lane_spacing: f64,
     channel_gap: f64,
@@ -30,6 +33,20 @@ pub fn route_edges<'a>(
 ) -> Vec<LayoutEdge> {
     // Self-references on one node nest, so each needs to know its position.
     let mut self_ref_seen: HashMap<&str, usize> = HashMap::new();
+    let row_exits = if column_anchors.side_exits() {
+        assign_row_exits(
+            ir,
+            node_positions,
+            node_level,
+            column_anchors,
+            self_ref_reserve,
+            entity_margin,
+            lane_spacing,
+            node_gap_x,
+        )
+    } else {
+        HashMap::new()
+    };
 
     ir.edges
         .iter()
@@ -40,8 +57,11 @@ pub fn route_edges<'a>(
 
             if edge.from == edge.to {
                 let loop_index = self_ref_seen.entry(edge.from.as_str()).or_insert(0);
-                let waypoints = route_self_ref(from_node, *loop_index, lane_spacing);
+                let mut waypoints = route_self_ref(from_node, *loop_index, lane_spacing);
                 *loop_index += 1;
+                if column_anchors.side_exits() {
+                    pin_self_ref(&mut waypoints, from_node, idx, column_anchors);
+                }
                 return Some(LayoutEdge {
                     from: edge.from.clone(),
                     to: edge.to.clone(),
@@ -55,15 +75,23 @@ pub fn route_edges<'a>(
             let to_level = *node_level.get(edge.to.as_str()).unwrap_or(&0);
             let going_down = to_level >= from_level;
 
-            let from_exits = node_exits.get(&(edge.from.as_str(), going_down))?;
-            let from_pos = from_exits.iter().position(|(i, _)| *i == idx).unwrap_or(0);
-            let from_cx = distribute_anchor(from_node, from_pos, from_exits.len(), anchor_spacing);
-
-            let to_exits = node_exits.get(&(edge.to.as_str(), !going_down))?;
-            let to_pos = to_exits.iter().position(|(i, _)| *i == idx).unwrap_or(0);
-            let to_cx = distribute_anchor(to_node, to_pos, to_exits.len(), anchor_spacing);
+            // A pinned end sits at its row, or, leaving from a side, is
+            // routed as if it left from the stub beside that row.
+            let anchor_x = |node: &LayoutNode, id: &str, exits_down: bool, from: bool| {
+                if let Some(exit) = row_exits.get(&(idx, from)) {
+                    return Some(exit.stub_x);
+                }
+                if let Some(x) = column_anchors.position(node, idx, from) {
+                    return Some(x);
+                }
+                let exits = node_exits.get(&(id, exits_down))?;
+                let pos = exits.iter().position(|(i, _)| *i == idx).unwrap_or(0);
+                Some(distribute_anchor(node, pos, exits.len(), anchor_spacing))
+            };
+            let from_cx = anchor_x(from_node, edge.from.as_str(), going_down, true)?;
+            let to_cx = anchor_x(to_node, edge.to.as_str(), !going_down, false)?;
 
-            let waypoints = calculate_waypoints(
+            let mut waypoints = calculate_waypoints(
                 idx,
                 from_node,
                 to_node,
@@ -81,6 +109,11 @@ pub fn route_edges<'a>(
                 node_gap_x,
                 entity_margin,
             );
+            for from in [true, false] {
+                if let Some(exit) = row_exits.get(&(idx, from)) {
+                    attach_to_row(&mut waypoints, from, exit);
+                }
+            }
 
             Some(LayoutEdge {
                 from: edge.from.clone(),
@@ -93,9 +126,156 @@ pub fn route_edges<'a>(
         .collect()
 }
 
+/// A pinned end leaving from a side border: it runs level with its row out to
+/// `stub_x`, where it turns towards the channel.
+#[derive(Debug, Clone, Copy)]
+struct RowExit {
+    side_x: f64,
+    stub_x: f64,
+    row_y: f64,
+}
+
+/// Give every pinned end a side and a stub.
+///
+/// An end leaves from the right, where placement reserved room for its stub,
+/// unless the other entity lies wholly to the left. Right-hand stubs start
+/// beyond any self-reference loops. Ends sharing a side and heading for the
+/// same channel are stacked outwards, the row nearest the channel innermost,
+/// so their lines to the channel do not cross each other's stubs.
+#[allow(clippy::too_many_arguments)]
+fn assign_row_exits(
+    ir: &GraphIR,
+    node_positions: &HashMap<&str, &LayoutNode>,
+    node_level: &HashMap<&str, i64>,
+    column_anchors: &ColumnAnchors,
+    self_ref_reserve: &HashMap<&str, f64>,
+    entity_margin: f64,
+    lane_spacing: f64,
+    node_gap_x: f64,
+) -> HashMap<(usize, bool), RowExit> {
+    // (node, right side, channel below) -> (edge index, from end, row)
+    type Side<'a> = (&'a str, bool, bool);
+    let mut sides: HashMap<Side, Vec<(usize, bool, f64)>> = HashMap::new();
+    for (idx, edge) in ir.edges.iter().enumerate() {
+        if edge.from == edge.to {
+            continue;
+        }
+        let (Some(from_node), Some(to_node)) =
+            (node_positions.get(edge.from.as_str()), node_positions.get(edge.to.as_str()))
+        else {
+            continue;
+        };
+        let from_level = *node_level.get(edge.from.as_str()).unwrap_or(&0);
+        let to_level = *node_level.get(edge.to.as_str()).unwrap_or(&0);
+
+        for (from, node, other, below) in [
+            (true, from_node, to_node, to_level >= from_level),
+            (false, to_node, from_node, to_level <= from_level),
+        ] {
+            let Some(row_y) = column_anchors.position(node, idx, from) else {
+                continue;
+            };
+            let right = other.x + other.width > node.x;
+            sides
+                .entry((node.id.as_str(), right, below))
+                .or_default()
+                .push((idx, from, row_y));
+        }
+    }
+
+    let mut exits = HashMap::new();
+    for ((id, right, below), mut ends) in sides {
+        let Some(node) = node_positions.get(id) else {
+            continue;
+        };
+        ends.sort_by(|a, b| {
+            let order = a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal);
+            if below { order.reverse() } else { order }
+        });
+        let loops = if right { *self_ref_reserve.get(id).unwrap_or(&0.0) } else { 0.0 };
+        for (k, (idx, from, row_y)) in ends.into_iter().enumerate() {
+            let reach = entity_margin + k as f64 * lane_spacing;
+            let (side_x, stub_x) = if right {
+                (node.x + node.width, node.x + node.width + loops + reach)
+            } else {
+                (node.x, node.x - reach)
+            };
+            exits.insert((idx, from), RowExit { side_x, stub_x, row_y });
+        }
+    }
+
+    // Both ends on the same side, a short way apart: they share the outer
+    // stub, so the edge runs straight between them rather than jogging.
+    for idx in 0..ir.edges.len() {
+        let (Some(from), Some(to)) = (exits.get(&(idx, true)), exits.get(&(idx, false))) else {
+            continue;
+        };
+        let right = from.stub_x > from.side_x;
+        if right != (to.stub_x > to.side_x) || (from.stub_x - to.stub_x).abs() >= node_gap_x / 2.0 {
+            continue;
+        }
+        let outer = if right { from.stub_x.max(to.stub_x) } else { from.stub_x.min(to.stub_x) };
+        for from in [true, false] {
+            if let Some(exit) = exits.get_mut(&(idx, from)) {
+                exit.stub_x = outer;
+            }
+        }
+    }
+    exits
+}
+
+/// Bring one end of a routed path to its row.
+///
+/// A path that already leaves sideways only moves its first run to the row;
+/// one that leaves vertically, from the stub, gains the run from the side
+/// border out to the stub.
+fn attach_to_row(waypoints: &mut Vec<(f64, f64)>, from: bool, exit: &RowExit) {
+    if waypoints.len() < 2 {
+        return;
+    }
+    let (end, next) = if from {
+        (0, 1)
+    } else {
+        (waypoints.len() - 1, waypoints.len() - 2)
+    };
+
+    if waypoints[end].1 == waypoints[next].1 {
+        waypoints[end].1 = exit.row_y;
+        waypoints[next].1 = exit.row_y;
+        return;
+    }
+
+    waypoints[end] = (waypoints[end].0, exit.row_y);
+    let side = (exit.side_x, exit.row_y);
+    if from {
+        waypoints.insert(0, side);
+    } else {
+        waypoints.push(side);
+    }
+}
+
+/// Move the ends of a self-reference loop to their rows, when the two rows
+/// still leave the loop some height.
+fn pin_self_ref(
+    waypoints: &mut [(f64, f64)],
+    node: &LayoutNode,
+    idx: usize,
+    column_anchors: &ColumnAnchors,
+) {
+    let from_y = column_anchors.position(node, idx, true).unwrap_or(waypoints[0].1);
+    let to_y = column_anchors.position(node, idx, false).unwrap_or(waypoints[3].1);
+    if (from_y - to_y).abs() < 1.0 {
+        return;
+    }
+    waypoints[0].1 = from_y;
+    waypoints[1].1 = from_y;
+    waypoints[2].1 = to_y;
+    waypoints[3].1 = to_y;
+}
+
 /// Calculate waypoints for a single edge.
 #[allow(clippy::too_many_arguments)]
-fn calculate_waypoints<'a>(
+fn calculate_waypoints(
     idx: usize,
     from_node: &LayoutNode,
     to_node: &LayoutNode,
@@ -106,7 +286,7 @@ fn calculate_waypoints<'a>(
     channel_edge_count: &HashMap<i64, usize>,
     channel_lane_assignments: &HashMap<(i64, usize), usize>,
     node_placement: &NodePlacement,
-    levels: &HashMap<i64, Vec<&'a Node>>,
+    levels: &HashMap<i64, Vec<&Node>>,
     multi_level_corridor_x: &HashMap<usize, f64>,
     lane_spacing: f64,
     channel_gap: f64,
@@ -247,7 +427,7 @@ fn route_adjacent_level(
 
 /// Route multi-level edges through corridors.
 #[allow(clippy::too_many_arguments)]
-fn route_multi_level<'a>(
+fn route_multi_level(
     idx: usize,
     from_node: &LayoutNode,
     to_node: &LayoutNode,
@@ -261,7 +441,7 @@ fn route_multi_level<'a>(
     channel_edge_count: &HashMap<i64, usize>,
     channel_lane_assignments: &HashMap<(i64, usize), usize>,
     node_placement: &NodePlacement,
-    levels: &HashMap<i64, Vec<&'a Node>>,
+    levels: &HashMap<i64, Vec<&Node>>,
     multi_level_corridor_x: &HashMap<usize, f64>,
     lane_spacing: f64,
     channel_gap: f64,
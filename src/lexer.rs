@@ -1,11 +1,15 @@
+use crate::diagnostic::{self, Severity, Span};
+use std::fmt;
 use std::iter::Peekable;
-use std::str::Chars;
+use std::str::CharIndices;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Ident(String),
     Str(String),
     Num(i64),
+    /// `/// text`: documentation for the item on the following line
+    Doc(String),
 
     LBrace,   // {
     RBrace,   // }
@@ -28,26 +32,100 @@ pub enum Token {
     Eof,
 }
 
+impl Token {
+    /// How the token reads in an "expected ..." message.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Token::Ident(_) => "identifier",
+            Token::Str(_) => "string",
+            Token::Num(_) => "number",
+            Token::Doc(_) => "doc comment",
+            Token::LBrace => "`{`",
+            Token::RBrace => "`}`",
+            Token::LParen => "`(`",
+            Token::RParen => "`)`",
+            Token::LBracket => "`[`",
+            Token::RBracket => "`]`",
+            Token::Comma => "`,`",
+            Token::Semicolon => "`;`",
+            Token::Colon => "`:`",
+            Token::Eq => "`=`",
+            Token::At => "`@`",
+            Token::Star => "`*`",
+            Token::Dot => "`.`",
+            Token::Arrow => "`->`",
+            Token::Dash => "`--`",
+            Token::DotDot => "`..`",
+            Token::Newline => "end of line",
+            Token::Eof => "end of input",
+        }
+    }
+}
+
+/// The token as written, for "unexpected ..." messages: names, numbers
+/// and strings as they appear in the source, anything else as described.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "`{}`", s),
+            Token::Str(s) => write!(f, "`\"{}\"`", s),
+            Token::Num(n) => write!(f, "`{}`", n),
+            Token::Doc(_) => write!(f, "doc comment"),
+            other => write!(f, "{}", other.describe()),
+        }
+    }
+}
+
+/// A token and where it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum LexError {
     #[error("Unexpected character: {0}")]
-    UnexpectedChar(char),
+    UnexpectedChar(char, Span),
     #[error("Unterminated string")]
-    UnterminatedString,
+    UnterminatedString(Span),
     #[error("Invalid number: {0}")]
-    InvalidNumber(String),
+    InvalidNumber(String, Span),
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedChar(_, span)
+            | LexError::UnterminatedString(span)
+            | LexError::InvalidNumber(_, span) => *span,
+        }
+    }
+
+    /// Report the error against `source`, pointing at the offending text.
+    pub fn render(&self, file: &str, source: &str) -> String {
+        diagnostic::render(file, source, Severity::Error, self.span(), &self.to_string())
+    }
 }
 
 pub struct Lexer<'a> {
-    chars: Peekable<Chars<'a>>,
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
     preserve_newlines: bool,
+    line: usize,
+    column: usize,
+    file: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Self {
-            chars: input.chars().peekable(),
+            input,
+            chars: input.char_indices().peekable(),
             preserve_newlines: false,
+            line: 1,
+            column: 1,
+            file: 0,
         }
     }
 
@@ -56,19 +134,64 @@ impl<'a> Lexer<'a> {
         self.preserve_newlines = preserve;
     }
 
+    /// Mark every span with `file`, the input's index among loaded files.
+    pub fn set_file(&mut self, file: usize) {
+        self.file = file;
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    /// Consume one character, keeping the line and column up to date.
+    fn bump(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Byte offset of the next character.
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.input.len(), |&(i, _)| i)
+    }
+
+    /// A zero-length span at the next character.
+    fn here(&mut self) -> Span {
+        let start = self.offset();
+        Span {
+            start,
+            end: start,
+            line: self.line,
+            column: self.column,
+            file: self.file,
+        }
+    }
+
+    /// Extend a span from `here()` up to the next character.
+    fn close(&mut self, start: Span) -> Span {
+        Span {
+            end: self.offset(),
+            ..start
+        }
+    }
+
     fn skip_whitespace_and_comments(&mut self) {
         loop {
-            match self.chars.peek() {
+            match self.peek_char() {
                 Some('\n') if self.preserve_newlines => {
                     // Don't skip newlines when preserving
                     break;
                 }
                 Some(c) if c.is_whitespace() => {
-                    self.chars.next();
+                    self.bump();
                 }
                 Some('#') => {
-                    while let Some(&c) = self.chars.peek() {
-                        self.chars.next();
+                    while let Some(c) = self.bump() {
                         if c == '\n' {
                             break;
                         }
@@ -81,10 +204,10 @@ impl<'a> Lexer<'a> {
 
     fn read_ident(&mut self, first: char) -> String {
         let mut s = String::from(first);
-        while let Some(&c) = self.chars.peek() {
+        while let Some(c) = self.peek_char() {
             if c.is_alphanumeric() || c == '_' {
                 s.push(c);
-                self.chars.next();
+                self.bump();
             } else {
                 break;
             }
@@ -92,13 +215,13 @@ impl<'a> Lexer<'a> {
         s
     }
 
-    fn read_string(&mut self) -> Result<String, LexError> {
+    fn read_string(&mut self, start: Span) -> Result<String, LexError> {
         let mut s = String::new();
         loop {
-            match self.chars.next() {
+            match self.bump() {
                 Some('"') => return Ok(s),
                 Some('\\') => {
-                    if let Some(c) = self.chars.next() {
+                    if let Some(c) = self.bump() {
                         match c {
                             'n' => s.push('\n'),
                             't' => s.push('\t'),
@@ -108,30 +231,57 @@ impl<'a> Lexer<'a> {
                     }
                 }
                 Some(c) => s.push(c),
-                None => return Err(LexError::UnterminatedString),
+                None => return Err(LexError::UnterminatedString(self.close(start))),
+            }
+        }
+    }
+
+    /// Read the rest of a `///` line, without the space that usually follows
+    /// the slashes.
+    fn read_doc(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek_char() {
+            if c == '\n' {
+                break;
             }
+            s.push(c);
+            self.bump();
         }
+        let text = s.strip_prefix(' ').unwrap_or(&s);
+        text.trim_end().to_string()
     }
 
-    fn read_number(&mut self, first: char) -> Result<i64, LexError> {
+    fn read_number(&mut self, first: char, start: Span) -> Result<i64, LexError> {
         let mut s = String::from(first);
-        while let Some(&c) = self.chars.peek() {
+        while let Some(c) = self.peek_char() {
             if c.is_ascii_digit() {
                 s.push(c);
-                self.chars.next();
+                self.bump();
             } else {
                 break;
             }
         }
-        s.parse().map_err(|_| LexError::InvalidNumber(s))
+        s.parse()
+            .map_err(|_| LexError::InvalidNumber(s, self.close(start)))
     }
 
     pub fn next_token(&mut self) -> Result<Token, LexError> {
+        self.next_spanned().map(|t| t.token)
+    }
+
+    /// Read the next token along with its location.
+    pub fn next_spanned(&mut self) -> Result<SpannedToken, LexError> {
         self.skip_whitespace_and_comments();
 
-        let c = match self.chars.next() {
+        let start = self.here();
+        let c = match self.bump() {
             Some(c) => c,
-            None => return Ok(Token::Eof),
+            None => {
+                return Ok(SpannedToken {
+                    token: Token::Eof,
+                    span: start,
+                });
+            }
         };
 
         let tok = match c {
@@ -149,38 +299,57 @@ impl<'a> Lexer<'a> {
             '@' => Token::At,
             '*' => Token::Star,
             '.' => {
-                if self.chars.peek() == Some(&'.') {
-                    self.chars.next();
+                if self.peek_char() == Some('.') {
+                    self.bump();
                     Token::DotDot
                 } else {
                     Token::Dot
                 }
             }
             '-' => {
-                if self.chars.peek() == Some(&'-') {
-                    self.chars.next();
+                if self.peek_char() == Some('-') {
+                    self.bump();
                     Token::Dash
-                } else if self.chars.peek() == Some(&'>') {
-                    self.chars.next();
+                } else if self.peek_char() == Some('>') {
+                    self.bump();
                     Token::Arrow
                 } else {
-                    return Err(LexError::UnexpectedChar(c));
+                    return Err(LexError::UnexpectedChar(c, self.close(start)));
+                }
+            }
+            '/' => {
+                if self.peek_char() == Some('/') {
+                    self.bump();
+                }
+                if self.peek_char() != Some('/') {
+                    return Err(LexError::UnexpectedChar(c, self.close(start)));
                 }
+                self.bump();
+                Token::Doc(self.read_doc())
             }
-            '"' => Token::Str(self.read_string()?),
-            c if c.is_ascii_digit() => Token::Num(self.read_number(c)?),
+            '"' => Token::Str(self.read_string(start)?),
+            c if c.is_ascii_digit() => Token::Num(self.read_number(c, start)?),
             c if c.is_alphabetic() || c == '_' => Token::Ident(self.read_ident(c)),
-            _ => return Err(LexError::UnexpectedChar(c)),
+            _ => return Err(LexError::UnexpectedChar(c, self.close(start))),
         };
 
-        Ok(tok)
+        Ok(SpannedToken {
+            token: tok,
+            span: self.close(start),
+        })
     }
 
-    pub fn tokenize(mut self) -> Result<Vec<Token>, LexError> {
+    pub fn tokenize(self) -> Result<Vec<Token>, LexError> {
+        let tokens = self.tokenize_spanned()?;
+        Ok(tokens.into_iter().map(|t| t.token).collect())
+    }
+
+    /// Collect all tokens with their locations, ending with `Eof`.
+    pub fn tokenize_spanned(mut self) -> Result<Vec<SpannedToken>, LexError> {
         let mut tokens = Vec::new();
         loop {
-            let tok = self.next_token()?;
-            if tok == Token::Eof {
+            let tok = self.next_spanned()?;
+            if tok.token == Token::Eof {
                 tokens.push(tok);
                 break;
             }
@@ -188,6 +357,26 @@ impl<'a> Lexer<'a> {
         }
         Ok(tokens)
     }
+
+    /// Collect all tokens like `tokenize_spanned`, but keep going past errors.
+    /// Whatever could not be read is dropped from the token stream.
+    pub fn tokenize_recovering(mut self) -> (Vec<SpannedToken>, Vec<LexError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        loop {
+            match self.next_spanned() {
+                Ok(tok) => {
+                    let done = tok.token == Token::Eof;
+                    tokens.push(tok);
+                    if done {
+                        break;
+                    }
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        (tokens, errors)
+    }
 }
 
 #[cfg(test)]
@@ -232,6 +421,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_doc_comments() {
+        let input = "/// Registered customer\n///\nentity # not a doc\n";
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Doc("Registered customer".into()),
+                Token::Doc("".into()),
+                Token::Ident("entity".into()),
+                Token::Eof,
+            ]
+        );
+        assert!(Lexer::new("// plain").tokenize().is_err());
+    }
+
     #[test]
     fn test_cardinality_tokens() {
         let tokens = Lexer::new("1 0..1 * 1..*").tokenize().unwrap();
@@ -251,6 +456,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_spans() {
+        let tokens = Lexer::new("entity User {\n  名前 string\n}")
+            .tokenize_spanned()
+            .unwrap();
+
+        assert_eq!(
+            tokens[1].span,
+            Span {
+                start: 7,
+                end: 11,
+                line: 1,
+                column: 8,
+                file: 0
+            }
+        );
+        // Columns count characters, offsets count bytes.
+        assert_eq!(tokens[3].token, Token::Ident("名前".into()));
+        assert_eq!(
+            tokens[3].span,
+            Span {
+                start: 16,
+                end: 22,
+                line: 2,
+                column: 3,
+                file: 0
+            }
+        );
+        assert_eq!(tokens[5].span.line, 3);
+    }
+
+    #[test]
+    fn test_error_span() {
+        let err = Lexer::new("entity User {\n  id int ?\n}").tokenize().unwrap_err();
+        let span = err.span();
+        assert_eq!((span.line, span.column), (2, 10));
+    }
+
+    #[test]
+    fn test_recovering_drops_bad_characters() {
+        let (tokens, errors) = Lexer::new("a ? b $ c").tokenize_recovering();
+        let tokens: Vec<Token> = tokens.into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Ident("a".into()),
+                Token::Ident("b".into()),
+                Token::Ident("c".into()),
+                Token::Eof,
+            ]
+        );
+        assert_eq!(errors.len(), 2);
+    }
+
     #[test]
     fn test_symbols() {
         let tokens = Lexer::new("-- -> : = @ ;").tokenize().unwrap();
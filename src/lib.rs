@@ -1,19 +1,28 @@
 pub mod ast;
+pub mod diagnostic;
 pub mod ir;
 pub mod layout;
 pub mod lexer;
+pub mod loader;
 pub mod measure;
 pub mod parser;
+pub mod render;
 pub mod serializer;
 pub mod sql;
 pub mod svg;
+pub mod validate;
+pub mod view;
 
 use wasm_bindgen::prelude::*;
 
-use ir::{DetailLevel, GraphIR};
-use layout::LayoutEngine;
-use parser::Parser;
-use svg::{Notation, SvgRenderer};
+use ir::{Anchoring, DetailLevel, EdgeSource, EnumBoxes, Ghosts, InheritedColumns, JoinEntities};
+use layout::{Direction, Notes};
+use loader::MemoryResolver;
+use render::RenderOptions;
+use svg::{Notation, Subtypes};
+
+/// Name given to the source in error reports, which have no file to point at.
+const SOURCE_NAME: &str = "<input>";
 
 #[wasm_bindgen(start)]
 fn init() {
@@ -21,6 +30,110 @@ fn init() {
     console_error_panic_hook::set_once();
 }
 
+/// Sources for `import`, given from JS as an object of path -> source.
+/// Paths are relative to the source passed in.
+fn memory_resolver(files: Option<&js_sys::Object>) -> MemoryResolver {
+    let mut resolver = MemoryResolver::new();
+    for entry in files.map(js_sys::Object::entries).iter().flat_map(|e| e.iter()) {
+        let pair = js_sys::Array::from(&entry);
+        if let (Some(path), Some(source)) = (pair.get(0).as_string(), pair.get(1).as_string()) {
+            resolver.insert(&path, source);
+        }
+    }
+    resolver
+}
+
+/// A property of a JS object, if it is set.
+fn property(object: &js_sys::Object, key: &str) -> Option<JsValue> {
+    js_sys::Reflect::get(object, &JsValue::from_str(key))
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+}
+
+/// Entities to focus on, given from JS as `{ focus, depth, include, exclude }`.
+/// Each list is a string of comma-separated names or patterns, or an array
+/// of them. `depth` must be a whole number of at least 0, and needs `focus`.
+fn focus_options(options: Option<&js_sys::Object>) -> Result<view::Focus, String> {
+    let list = |key: &str| -> Vec<String> {
+        let Some(value) = options.and_then(|o| property(o, key)) else {
+            return Vec::new();
+        };
+        let items: Vec<String> = if js_sys::Array::is_array(&value) {
+            js_sys::Array::from(&value).iter().filter_map(|v| v.as_string()).collect()
+        } else {
+            value.as_string().into_iter().collect()
+        };
+        items
+            .iter()
+            .flat_map(|item| item.split(','))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let mut focus = view::Focus {
+        focus: list("focus"),
+        include: list("include"),
+        exclude: list("exclude"),
+        ..view::Focus::default()
+    };
+    if let Some(value) = options.and_then(|o| property(o, "depth")) {
+        let depth = value.as_f64().filter(|d| *d >= 0.0 && d.fract() == 0.0).ok_or_else(|| {
+            let given = value.as_f64().map(|d| d.to_string()).or_else(|| value.as_string());
+            format!("Invalid depth: {}", given.as_deref().unwrap_or("not a number"))
+        })?;
+        if focus.focus.is_empty() {
+            return Err("depth needs focus".to_string());
+        }
+        focus.depth = depth as usize;
+    }
+    Ok(focus)
+}
+
+/// A setting given as a string, read with `parse`. Unset is `None`; a value
+/// `parse` does not know is an error naming the valid ones.
+fn setting<T>(
+    value: Option<String>,
+    what: &str,
+    parse: fn(&str) -> Option<T>,
+    valid: &str,
+) -> Result<Option<T>, String> {
+    match value {
+        None => Ok(None),
+        Some(value) => parse(&value)
+            .map(Some)
+            .ok_or_else(|| format!("Invalid {}: {} (valid options: {})", what, value, valid)),
+    }
+}
+
+/// Everything `erdToSvg` takes, as the options the renderer reads. The
+/// object may also set `{ anchors, edges, notes, enums, subtypes, inherited,
+/// joins, ghosts }`, as the command line does.
+fn render_options(
+    view: Option<String>,
+    detail: Option<String>,
+    notation: Option<String>,
+    direction: Option<String>,
+    options: Option<&js_sys::Object>,
+) -> Result<RenderOptions, String> {
+    let string = |key: &str| options.and_then(|o| property(o, key)).and_then(|v| v.as_string());
+    Ok(RenderOptions {
+        view,
+        focus: focus_options(options)?,
+        detail: setting(detail, "detail level", DetailLevel::from_str, "tables, pk, pk_fk, all, full, physical")?,
+        notation: setting(notation, "notation", Notation::from_str, "crowsfoot, text")?,
+        direction: setting(direction, "direction", Direction::from_str, "tb, lr, bt, rl")?,
+        anchoring: setting(string("anchors"), "anchoring", Anchoring::from_str, "border, columns")?,
+        edges: setting(string("edges"), "edge source", EdgeSource::from_str, "rel, fk")?,
+        notes: setting(string("notes"), "notes setting", Notes::from_str, "show, hide")?,
+        enums: setting(string("enums"), "enums setting", EnumBoxes::from_str, "show, hide")?,
+        subtypes: setting(string("subtypes"), "subtypes setting", Subtypes::from_str, "uml, idef1x")?,
+        inherited: setting(string("inherited"), "inherited setting", InheritedColumns::from_str, "show, hide")?,
+        joins: setting(string("joins"), "joins setting", JoinEntities::from_str, "show, collapse")?,
+        ghosts: setting(string("ghosts"), "ghosts setting", Ghosts::from_str, "show, all, hide")?,
+    })
+}
+
 /// Render ERD source to SVG
 #[wasm_bindgen(js_name = "erdToSvg")]
 pub fn render_erd(
@@ -28,35 +141,57 @@ pub fn render_erd(
     view: Option<String>,
     detail: Option<String>,
     notation: Option<String>,
+    direction: Option<String>,
+    files: Option<js_sys::Object>,
+    options: Option<js_sys::Object>,
 ) -> Result<String, String> {
-    let mut parser = Parser::new(source).map_err(|e| e.to_string())?;
-    let schema = parser.parse().map_err(|e| e.to_string())?;
-
-    if let Some(name) = view.as_deref() {
-        if schema.find_view(name).is_none() {
-            return Err(format!(
-                "Unknown view: {} (available: {})",
-                name,
-                schema.view_names().join(", ")
-            ));
-        }
+    let loaded = loader::load_source(SOURCE_NAME, source, &memory_resolver(files.as_ref()));
+    if !loaded.diagnostics.is_empty() {
+        let reports: Vec<String> = loaded.diagnostics.iter().map(|d| loaded.render(d)).collect();
+        return Err(reports.join("\n"));
     }
+    let schema = &loaded.schema;
 
-    let detail_level = detail
-        .as_deref()
-        .and_then(DetailLevel::from_str)
-        .unwrap_or(DetailLevel::All);
-
-    let notation = notation
-        .as_deref()
-        .and_then(Notation::from_str)
-        .unwrap_or_default();
+    let diagnostics = validate::validate(schema);
+    if diagnostics.iter().any(|d| d.is_error()) {
+        let reports: Vec<String> = diagnostics.iter().map(|d| loaded.render(d)).collect();
+        return Err(reports.join("\n"));
+    }
 
-    let ir = GraphIR::from_schema(&schema, view.as_deref(), detail_level);
-    let layout = LayoutEngine::default().layout(&ir);
-    let svg = SvgRenderer::with_notation(notation).render(&ir, &layout);
+    let options = render_options(view, detail, notation, direction, options.as_ref())?;
+    render::render(schema, &options).map_err(|errors| {
+        let reports: Vec<String> = errors.iter().map(|d| format!("{}: {}", d.severity, d.message)).collect();
+        reports.join("\n")
+    })
+}
 
-    Ok(svg)
+/// Check ERD source without rendering it. Returns every parse error and
+/// validation warning or error as a report, or an empty string when there
+/// are none. Given the `view` and options `erdToSvg` takes, it also reports
+/// what they pick out: an unknown view, or a focus that matches nothing.
+#[wasm_bindgen(js_name = "checkErd")]
+pub fn check_erd(
+    source: &str,
+    files: Option<js_sys::Object>,
+    view: Option<String>,
+    options: Option<js_sys::Object>,
+) -> String {
+    let loaded = loader::load_source(SOURCE_NAME, source, &memory_resolver(files.as_ref()));
+    let mut reports: Vec<String> = loaded.diagnostics.iter().map(|d| loaded.render(d)).collect();
+    let diagnostics = validate::validate(&loaded.schema);
+    reports.extend(diagnostics.iter().map(|d| loaded.render(d)));
+    // Only a schema that would render is worth checking the selection of.
+    if loaded.diagnostics.is_empty() && !diagnostics.iter().any(|d| d.is_error()) {
+        match render_options(view, None, None, None, options.as_ref()) {
+            Ok(options) => reports.extend(
+                render::check(&loaded.schema, &options)
+                    .iter()
+                    .map(|d| format!("{}: {}", d.severity, d.message)),
+            ),
+            Err(error) => reports.push(error),
+        }
+    }
+    reports.join("\n")
 }
 
 /// Render ERD source to SVG data URI (for use with <img src={...}>)
@@ -66,8 +201,11 @@ pub fn render_erd_data_uri(
     view: Option<String>,
     detail: Option<String>,
     notation: Option<String>,
+    direction: Option<String>,
+    files: Option<js_sys::Object>,
+    options: Option<js_sys::Object>,
 ) -> Result<String, String> {
-    let svg = render_erd(source, view, detail, notation)?;
+    let svg = render_erd(source, view, detail, notation, direction, files, options)?;
     Ok(format!(
         "data:image/svg+xml,{}",
         js_sys::encode_uri_component(&svg)
@@ -94,7 +232,8 @@ pub fn sql_to_svg(
     view: Option<String>,
     detail: Option<String>,
     notation: Option<String>,
+    direction: Option<String>,
 ) -> Result<String, String> {
     let erd = sql_to_erd(sql_source, dialect)?;
-    render_erd(&erd, view, detail, notation)
+    render_erd(&erd, view, detail, notation, direction, None, None)
 }
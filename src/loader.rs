@@ -0,0 +1,286 @@
+//! Schemas spread over several files, joined with `import "other.erd"`.
+//!
+//! The loader parses the root file, follows its imports depth first, and
+//! merges everything into one [`Schema`]. Imported files come before the file
+//! that imports them, and each file is read once however often it is
+//! imported. Files are found through a [`FileResolver`], so the same loader
+//! works on disk and on sources held in memory.
+
+use crate::ast::Schema;
+use crate::diagnostic::{Diagnostic, Span};
+use crate::parser::Parser;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Where imported files come from.
+pub trait FileResolver {
+    /// The path of `import`, written in the file at `from`. By default it is
+    /// relative to the directory of `from`.
+    fn resolve(&self, from: &str, import: &str) -> String {
+        join_relative(from, import)
+    }
+
+    /// The contents of the file at a path returned by `resolve`.
+    fn read(&self, path: &str) -> Result<String, String>;
+}
+
+/// Reads imports from the file system.
+pub struct FsResolver;
+
+impl FileResolver for FsResolver {
+    fn read(&self, path: &str) -> Result<String, String> {
+        std::fs::read_to_string(path).map_err(|e| e.to_string())
+    }
+}
+
+/// Serves imports from sources held in memory, keyed by path.
+#[derive(Debug, Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `source` available at `path`, relative to the root file's
+    /// directory.
+    pub fn insert(&mut self, path: &str, source: impl Into<String>) {
+        self.files.insert(join_relative("", path), source.into());
+    }
+}
+
+impl FileResolver for MemoryResolver {
+    fn read(&self, path: &str) -> Result<String, String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| "no such file".to_string())
+    }
+}
+
+/// `import` resolved against the directory of `from`, with `.` and `..`
+/// folded away so one file always gets the same path.
+pub fn join_relative(from: &str, import: &str) -> String {
+    let joined = Path::new(from).parent().unwrap_or(Path::new("")).join(import);
+    let mut parts: Vec<Component> = Vec::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(parts.last(), Some(Component::Normal(_))) => {
+                parts.pop();
+            }
+            other => parts.push(other),
+        }
+    }
+    parts.iter().collect::<PathBuf>().to_string_lossy().into_owned()
+}
+
+/// One file the loader read.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: String,
+    pub source: String,
+}
+
+/// The merged schema, the files it came from, and the problems found while
+/// parsing and importing them.
+#[derive(Debug)]
+pub struct Loaded {
+    pub schema: Schema,
+    /// Indexed by `Span::file`; the root file is first.
+    pub files: Vec<SourceFile>,
+    /// Parse errors and failed imports, in file order.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Loaded {
+    /// Report `diagnostic` against the files its spans are in.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        diagnostic.render_in(|file| {
+            self.files
+                .get(file)
+                .map_or(("", ""), |f| (f.path.as_str(), f.source.as_str()))
+        })
+    }
+}
+
+/// Read the file at `path` and everything it imports.
+pub fn load(path: &str, resolver: &dyn FileResolver) -> Result<Loaded, String> {
+    let source = resolver.read(path)?;
+    Ok(load_source(path, source, resolver))
+}
+
+/// Like [`load`], for a root file whose source is already at hand. `path` is
+/// used to name it in reports and to resolve its imports.
+pub fn load_source(path: &str, source: impl Into<String>, resolver: &dyn FileResolver) -> Loaded {
+    let mut loader = Loader {
+        resolver,
+        files: Vec::new(),
+        seen: HashSet::new(),
+        stack: Vec::new(),
+        diagnostics: Vec::new(),
+        schema: Schema {
+            entities: Vec::new(),
+            enums: Vec::new(),
+            relationships: Vec::new(),
+            views: Vec::new(),
+            arrangement: None,
+            hints: Vec::new(),
+            imports: Vec::new(),
+        },
+    };
+    loader.visit(path.to_string(), source.into());
+
+    // Files were numbered in the order they were read, which puts the root
+    // first.
+    Loaded {
+        schema: loader.schema,
+        files: loader.files,
+        diagnostics: loader.diagnostics,
+    }
+}
+
+struct Loader<'a> {
+    resolver: &'a dyn FileResolver,
+    files: Vec<SourceFile>,
+    seen: HashSet<String>,
+    /// Files being loaded, outermost first, for cycle detection.
+    stack: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+    schema: Schema,
+}
+
+impl Loader<'_> {
+    fn visit(&mut self, path: String, source: String) {
+        let file = self.files.len();
+        let (schema, errors) = Parser::new_recovering_in(&source, file).parse_recovering();
+        self.files.push(SourceFile {
+            path: path.clone(),
+            source,
+        });
+        self.seen.insert(path.clone());
+        self.diagnostics.extend(errors.iter().map(|e| {
+            let span = e.span().unwrap_or(Span {
+                file,
+                ..Span::default()
+            });
+            Diagnostic::error(e.to_string(), span)
+        }));
+
+        self.stack.push(path.clone());
+        for import in &schema.imports {
+            let target = self.resolver.resolve(&path, &import.path);
+            if let Some(at) = self.stack.iter().position(|p| *p == target) {
+                let mut cycle = self.stack[at..].to_vec();
+                cycle.push(target);
+                self.diagnostics.push(Diagnostic::error(
+                    format!("Import cycle: {}", cycle.join(" -> ")),
+                    import.span,
+                ));
+                continue;
+            }
+            if self.seen.contains(&target) {
+                continue;
+            }
+            match self.resolver.read(&target) {
+                Ok(source) => self.visit(target, source),
+                Err(e) => self.diagnostics.push(Diagnostic::error(
+                    format!("Cannot import `{}`: {}", import.path, e),
+                    import.span,
+                )),
+            }
+        }
+        self.stack.pop();
+
+        self.merge(schema);
+    }
+
+    /// Add a file's items after those already loaded. A later arrangement
+    /// replaces an earlier one, so the importing file's arrangement wins.
+    fn merge(&mut self, schema: Schema) {
+        self.schema.entities.extend(schema.entities);
+        self.schema.enums.extend(schema.enums);
+        self.schema.relationships.extend(schema.relationships);
+        self.schema.views.extend(schema.views);
+        self.schema.hints.extend(schema.hints);
+        self.schema.imports.extend(schema.imports);
+        if schema.arrangement.is_some() {
+            self.schema.arrangement = schema.arrangement;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::validate;
+
+    fn files(entries: &[(&str, &str)]) -> MemoryResolver {
+        let mut resolver = MemoryResolver::new();
+        for (path, source) in entries {
+            resolver.insert(path, *source);
+        }
+        resolver
+    }
+
+    #[test]
+    fn test_join_relative() {
+        assert_eq!(join_relative("schema/main.erd", "billing.erd"), "schema/billing.erd");
+        assert_eq!(join_relative("schema/main.erd", "./a/../b.erd"), "schema/b.erd");
+        assert_eq!(join_relative("schema/main.erd", "../shared/users.erd"), "shared/users.erd");
+        assert_eq!(join_relative("main.erd", "billing.erd"), "billing.erd");
+    }
+
+    #[test]
+    fn test_merges_imports_relative_to_the_importer() {
+        let resolver = files(&[
+            ("domains/billing.erd", "import \"../shared/users.erd\"\nentity Invoice { id int pk }\n"),
+            ("shared/users.erd", "entity User { id int pk }\n"),
+        ]);
+        let root = "import \"domains/billing.erd\"\nimport \"shared/users.erd\"\n\
+                    entity Order { id int pk }\nrel { User 1 -- * Order }\n";
+        let loaded = load_source("main.erd", root, &resolver);
+
+        assert!(loaded.diagnostics.is_empty(), "{:?}", loaded.diagnostics);
+        let names: Vec<&str> = loaded.schema.entities.iter().map(|e| e.name.as_str()).collect();
+        // users.erd is read once, although two files import it.
+        assert_eq!(names, vec!["User", "Invoice", "Order"]);
+        let paths: Vec<&str> = loaded.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["main.erd", "domains/billing.erd", "shared/users.erd"]);
+        assert_eq!(loaded.schema.relationships.len(), 1);
+    }
+
+    #[test]
+    fn test_reports_cycles_and_missing_files() {
+        let resolver = files(&[("b.erd", "import \"main.erd\"\nentity B {}\n")]);
+        let root = "import \"b.erd\"\nimport \"missing.erd\"\nentity A {}\n";
+        let loaded = load_source("main.erd", root, &resolver);
+
+        let messages: Vec<&str> = loaded.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Import cycle: main.erd -> b.erd -> main.erd",
+                "Cannot import `missing.erd`: no such file",
+            ]
+        );
+        assert_eq!(loaded.diagnostics[0].span.file, 1);
+        assert!(loaded.render(&loaded.diagnostics[0]).contains(" --> b.erd:1:1\n"));
+        assert_eq!(loaded.schema.entities.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicates_point_at_both_files() {
+        let resolver = files(&[("users.erd", "entity User { id int pk }\n")]);
+        let root = "import \"users.erd\"\n\nentity User { id int pk }\n";
+        let loaded = load_source("main.erd", root, &resolver);
+
+        let diagnostics = validate(&loaded.schema);
+        assert_eq!(diagnostics.len(), 1);
+        let report = loaded.render(&diagnostics[0]);
+        assert!(report.contains(" --> main.erd:3:1\n"), "{report}");
+        assert!(report.contains("note: first defined here\n --> users.erd:1:1\n"), "{report}");
+    }
+}
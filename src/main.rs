@@ -1,9 +1,11 @@
-use rusterd::ir::{DetailLevel, GraphIR};
-use rusterd::layout::LayoutEngine;
-use rusterd::parser::Parser;
+use rusterd::ir::{Anchoring, DetailLevel, EdgeSource, EnumBoxes, Ghosts, InheritedColumns, JoinEntities};
+use rusterd::layout::{Direction, Notes};
+use rusterd::loader::{self, FsResolver};
+use rusterd::render::{self, RenderOptions};
 use rusterd::serializer;
 use rusterd::sql::{parse_sql, Dialect};
-use rusterd::svg::{Notation, SvgRenderer};
+use rusterd::svg::{Notation, Subtypes};
+use rusterd::validate::validate;
 use std::env;
 use std::fs;
 use std::io::{self, Read, Write};
@@ -21,6 +23,14 @@ fn read_input(path: &str) -> Result<String, String> {
     }
 }
 
+/// The value given to the flag at `args[i]`, or exit when there is none.
+fn flag_value(args: &[String], i: usize) -> &str {
+    args.get(i + 1).map(String::as_str).unwrap_or_else(|| {
+        eprintln!("Missing value for {}", args[i]);
+        process::exit(1);
+    })
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
@@ -64,8 +74,21 @@ fn run_render(program: &str, args: &[String]) {
         eprintln!("Options:");
         eprintln!("  -o, --output <file>   Output file (default: stdout)");
         eprintln!("  -v, --view <name>     Render specific view");
-        eprintln!("  -d, --detail <level>  Detail level: tables, pk, pk_fk, all (default: all)");
-        eprintln!("  -n, --notation <n>    Cardinality notation: crowsfoot, text (default: crowsfoot)");
+        eprintln!("  -f, --focus <names>   Render only these entities and their neighbours (names or * patterns)");
+        eprintln!("      --depth <n>       How many links out --focus reaches (default: 1)");
+        eprintln!("  -i, --include <names> Render these entities too (names or * patterns)");
+        eprintln!("  -x, --exclude <names> Leave these entities out (names or * patterns)");
+        eprintln!("  -d, --detail <level>  Detail level: tables, pk, pk_fk, all, full, physical (default: @hint.detail or all)");
+        eprintln!("  -n, --notation <n>    Cardinality notation: crowsfoot, text (default: @hint.notation or crowsfoot)");
+        eprintln!("  -D, --direction <d>   Layout direction: tb, lr, bt, rl (default: @hint.direction or tb)");
+        eprintln!("  -a, --anchors <a>     Edge ends: border, columns (default: @hint.anchors or border)");
+        eprintln!("  -e, --edges <e>       Edges from: rel, fk (default: @hint.edges or rel)");
+        eprintln!("  -N, --notes <n>       Entity notes: show, hide (default: @hint.notes or hide)");
+        eprintln!("  -E, --enums <e>       Enum boxes: show, hide (default: @hint.enums or hide)");
+        eprintln!("  -s, --subtypes <s>    Subtype links: uml, idef1x (default: @hint.subtypes or uml)");
+        eprintln!("  -I, --inherited <i>   Inherited columns: show, hide (default: @hint.inherited or hide)");
+        eprintln!("  -j, --joins <j>       Join entities of 'via': show, collapse (default: @hint.joins or show)");
+        eprintln!("  -g, --ghosts <g>      Entities outside the view: show, all, hide (default: @hint.ghosts or hide)");
         if args.is_empty() {
             process::exit(1);
         }
@@ -74,43 +97,137 @@ fn run_render(program: &str, args: &[String]) {
 
     let input_path = &args[0];
     let mut output_path: Option<String> = None;
-    let mut view: Option<String> = None;
-    let mut detail = DetailLevel::All;
-    let mut notation = Notation::default();
+    let mut options = RenderOptions::default();
+    let mut depth: Option<usize> = None;
 
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
             "-o" | "--output" => {
+                let value = flag_value(args, i);
                 i += 1;
-                if i < args.len() {
-                    output_path = Some(args[i].clone());
-                }
+                output_path = Some(value.to_string());
             }
             "-v" | "--view" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.view = Some(value.to_string());
+            }
+            "-f" | "--focus" | "-i" | "--include" | "-x" | "--exclude" => {
+                let list = match args[i].as_str() {
+                    "-f" | "--focus" => &mut options.focus.focus,
+                    "-i" | "--include" => &mut options.focus.include,
+                    _ => &mut options.focus.exclude,
+                };
+                let value = flag_value(args, i);
                 i += 1;
-                if i < args.len() {
-                    view = Some(args[i].clone());
-                }
+                list.extend(value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string));
+            }
+            "--depth" => {
+                let value = flag_value(args, i);
+                i += 1;
+                depth = Some(value.parse().unwrap_or_else(|_| {
+                    eprintln!("Invalid depth: {}", value);
+                    process::exit(1);
+                }));
             }
             "-d" | "--detail" => {
+                let value = flag_value(args, i);
                 i += 1;
-                if i < args.len() {
-                    detail = DetailLevel::from_str(&args[i]).unwrap_or_else(|| {
-                        eprintln!("Invalid detail level: {}", args[i]);
-                        process::exit(1);
-                    });
-                }
+                options.detail = Some(DetailLevel::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid detail level: {}", value);
+                    process::exit(1);
+                }));
             }
             "-n" | "--notation" => {
+                let value = flag_value(args, i);
                 i += 1;
-                if i < args.len() {
-                    notation = Notation::from_str(&args[i]).unwrap_or_else(|| {
-                        eprintln!("Invalid notation: {}", args[i]);
-                        eprintln!("Valid options: crowsfoot, text");
-                        process::exit(1);
-                    });
-                }
+                options.notation = Some(Notation::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid notation: {}", value);
+                    eprintln!("Valid options: crowsfoot, text");
+                    process::exit(1);
+                }));
+            }
+            "-D" | "--direction" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.direction = Some(Direction::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid direction: {}", value);
+                    eprintln!("Valid options: tb, lr, bt, rl");
+                    process::exit(1);
+                }));
+            }
+            "-a" | "--anchors" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.anchoring = Some(Anchoring::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid anchoring: {}", value);
+                    eprintln!("Valid options: border, columns");
+                    process::exit(1);
+                }));
+            }
+            "-e" | "--edges" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.edges = Some(EdgeSource::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid edge source: {}", value);
+                    eprintln!("Valid options: rel, fk");
+                    process::exit(1);
+                }));
+            }
+            "-N" | "--notes" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.notes = Some(Notes::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid notes setting: {}", value);
+                    eprintln!("Valid options: show, hide");
+                    process::exit(1);
+                }));
+            }
+            "-E" | "--enums" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.enums = Some(EnumBoxes::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid enums setting: {}", value);
+                    eprintln!("Valid options: show, hide");
+                    process::exit(1);
+                }));
+            }
+            "-s" | "--subtypes" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.subtypes = Some(Subtypes::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid subtypes setting: {}", value);
+                    eprintln!("Valid options: uml, idef1x");
+                    process::exit(1);
+                }));
+            }
+            "-I" | "--inherited" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.inherited = Some(InheritedColumns::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid inherited setting: {}", value);
+                    eprintln!("Valid options: show, hide");
+                    process::exit(1);
+                }));
+            }
+            "-j" | "--joins" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.joins = Some(JoinEntities::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid joins setting: {}", value);
+                    eprintln!("Valid options: show, collapse");
+                    process::exit(1);
+                }));
+            }
+            "-g" | "--ghosts" => {
+                let value = flag_value(args, i);
+                i += 1;
+                options.ghosts = Some(Ghosts::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid ghosts setting: {}", value);
+                    eprintln!("Valid options: show, all, hide");
+                    process::exit(1);
+                }));
             }
             _ => {
                 eprintln!("Unknown option: {}", args[i]);
@@ -120,46 +237,57 @@ fn run_render(program: &str, args: &[String]) {
         i += 1;
     }
 
-    let input = match read_input(input_path) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("{}", e);
+    if let Some(depth) = depth {
+        if options.focus.focus.is_empty() {
+            eprintln!("--depth needs --focus");
             process::exit(1);
         }
-    };
+        options.focus.depth = depth;
+    }
 
-    let mut parser = match Parser::new(&input) {
-        Ok(p) => p,
+    let input = match read_input(input_path) {
+        Ok(s) => s,
         Err(e) => {
             eprintln!("{}", e);
             process::exit(1);
         }
     };
 
-    let schema = match parser.parse() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Parse error: {}", e);
-            process::exit(1);
-        }
+    let file_name = if input_path == "-" {
+        "<stdin>"
+    } else {
+        input_path.as_str()
     };
 
-    if let Some(name) = view.as_deref() {
-        if schema.find_view(name).is_none() {
-            eprintln!("Unknown view: {}", name);
-            let names = schema.view_names();
-            if names.is_empty() {
-                eprintln!("This file defines no views.");
-            } else {
-                eprintln!("Available views: {}", names.join(", "));
-            }
-            process::exit(1);
+    // Imports are resolved relative to the input file, or to the working
+    // directory for stdin.
+    let loaded = loader::load_source(file_name, input, &FsResolver);
+    if !loaded.diagnostics.is_empty() {
+        for d in &loaded.diagnostics {
+            eprintln!("{}", loaded.render(d));
         }
+        process::exit(1);
+    }
+    let schema = &loaded.schema;
+
+    let diagnostics = validate(schema);
+    for d in &diagnostics {
+        eprintln!("{}", loaded.render(d));
+    }
+    if diagnostics.iter().any(|d| d.is_error()) {
+        process::exit(1);
     }
 
-    let ir = GraphIR::from_schema(&schema, view.as_deref(), detail);
-    let layout = LayoutEngine::default().layout(&ir);
-    let svg = SvgRenderer::with_notation(notation).render(&ir, &layout);
+    let diagram = render::layout(schema, &options).unwrap_or_else(|errors| {
+        for d in &errors {
+            eprintln!("{}: {}", d.severity, d.message);
+        }
+        process::exit(1);
+    });
+    for d in &diagram.warnings {
+        eprintln!("{}: {}", d.severity, d.message);
+    }
+    let svg = diagram.to_svg();
 
     match output_path {
         Some(path) => {
@@ -169,11 +297,11 @@ fn run_render(program: &str, args: &[String]) {
             }
         }
         None => {
-            if let Err(e) = io::stdout().write_all(svg.as_bytes()) {
-                if e.kind() != io::ErrorKind::BrokenPipe {
-                    eprintln!("Failed to write to stdout: {}", e);
-                    process::exit(1);
-                }
+            if let Err(e) = io::stdout().write_all(svg.as_bytes())
+                && e.kind() != io::ErrorKind::BrokenPipe
+            {
+                eprintln!("Failed to write to stdout: {}", e);
+                process::exit(1);
             }
         }
     }
@@ -203,20 +331,18 @@ fn run_convert(program: &str, args: &[String]) {
     while i < args.len() {
         match args[i].as_str() {
             "-o" | "--output" => {
+                let value = flag_value(args, i);
                 i += 1;
-                if i < args.len() {
-                    output_path = Some(args[i].clone());
-                }
+                output_path = Some(value.to_string());
             }
             "-d" | "--dialect" => {
+                let value = flag_value(args, i);
                 i += 1;
-                if i < args.len() {
-                    dialect = Dialect::from_str(&args[i]).unwrap_or_else(|| {
-                        eprintln!("Invalid dialect: {}", args[i]);
-                        eprintln!("Valid options: auto, generic, postgres, mysql");
-                        process::exit(1);
-                    });
-                }
+                dialect = Dialect::from_str(value).unwrap_or_else(|| {
+                    eprintln!("Invalid dialect: {}", value);
+                    eprintln!("Valid options: auto, generic, postgres, mysql");
+                    process::exit(1);
+                });
             }
             _ => {
                 eprintln!("Unknown option: {}", args[i]);
@@ -252,11 +378,11 @@ fn run_convert(program: &str, args: &[String]) {
             }
         }
         None => {
-            if let Err(e) = io::stdout().write_all(erd.as_bytes()) {
-                if e.kind() != io::ErrorKind::BrokenPipe {
-                    eprintln!("Failed to write to stdout: {}", e);
-                    process::exit(1);
-                }
+            if let Err(e) = io::stdout().write_all(erd.as_bytes())
+                && e.kind() != io::ErrorKind::BrokenPipe
+            {
+                eprintln!("Failed to write to stdout: {}", e);
+                process::exit(1);
             }
         }
     }
@@ -31,27 +31,125 @@ impl TextMetrics {
     }
 
     pub fn node_size(&self, label: &str, columns: &[(String, String)]) -> (f64, f64) {
-        let header_width = self.text_width(label);
-
         let max_col_width = columns
             .iter()
             .map(|(name, typ)| self.text_width(name) + self.text_width(typ) + self.char_width * 2.0)
             .fold(0.0, f64::max);
+        self.box_size(label, max_col_width, columns.len())
+    }
 
-        let content_width = header_width.max(max_col_width) + self.padding_x * 2.0;
-        let width = content_width.max(self.min_node_width);
+    /// Like `node_size`, for rows drawn as cells in aligned columns.
+    pub fn table_size(&self, label: &str, rows: &[Vec<String>]) -> (f64, f64) {
+        let offsets = self.cell_offsets(rows);
+        let widths = self.cell_widths(rows);
+        let row_width = offsets
+            .iter()
+            .zip(&widths)
+            .map(|(offset, width)| offset + width)
+            .fold(0.0, f64::max);
+        self.box_size(label, row_width, rows.len())
+    }
 
-        let header_height = self.line_height + self.header_padding * 2.0;
-        let body_height = if columns.is_empty() {
+    /// Width of each column of cells: its widest cell.
+    pub fn cell_widths(&self, rows: &[Vec<String>]) -> Vec<f64> {
+        let count = rows.iter().map(Vec::len).max().unwrap_or(0);
+        (0..count)
+            .map(|i| {
+                rows.iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| self.text_width(cell))
+                    .fold(0.0, f64::max)
+            })
+            .collect()
+    }
+
+    /// Where each column of cells starts, from the left of the text. Columns
+    /// are two characters apart, and one that is empty in every row takes
+    /// no room.
+    pub fn cell_offsets(&self, rows: &[Vec<String>]) -> Vec<f64> {
+        let mut offsets = Vec::new();
+        let mut x = 0.0;
+        for width in self.cell_widths(rows) {
+            offsets.push(x);
+            if width > 0.0 {
+                x += width + self.char_width * 2.0;
+            }
+        }
+        offsets
+    }
+
+    /// `size` grown to hold a compartment of `lines` under the columns,
+    /// below a separator.
+    pub fn with_compartment(&self, size: (f64, f64), lines: &[String]) -> (f64, f64) {
+        let (width, height) = size;
+        let line_width = lines.iter().map(|l| self.text_width(l)).fold(0.0, f64::max);
+        (
+            width.max(line_width + self.padding_x * 2.0),
+            height + self.body_height(lines.len()),
+        )
+    }
+
+    /// Height of a box's rows, with padding above and below; nothing when
+    /// there are none.
+    pub fn body_height(&self, rows: usize) -> f64 {
+        if rows == 0 {
             0.0
         } else {
-            columns.len() as f64 * self.line_height + self.padding_y * 2.0
-        };
+            rows as f64 * self.line_height + self.padding_y * 2.0
+        }
+    }
 
-        let height = (header_height + body_height).max(self.min_node_height);
+    /// A box holding only its name, no taller than its header.
+    pub fn stub_size(&self, label: &str) -> (f64, f64) {
+        let width = (self.text_width(label) + self.padding_x * 2.0).max(self.min_node_width);
+        (width, self.line_height + self.header_padding * 2.0)
+    }
+
+    fn box_size(&self, label: &str, row_width: f64, rows: usize) -> (f64, f64) {
+        let content_width = self.text_width(label).max(row_width) + self.padding_x * 2.0;
+        let width = content_width.max(self.min_node_width);
+
+        let header_height = self.line_height + self.header_padding * 2.0;
+        let height = (header_height + self.body_height(rows)).max(self.min_node_height);
 
         (width, height)
     }
+
+    /// Break `text` into lines no wider than `max_width`, at spaces where
+    /// possible. Line breaks already in the text are kept.
+    pub fn wrap(&self, text: &str, max_width: f64) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                let joined = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+                if self.text_width(&joined) <= max_width {
+                    line = joined;
+                    continue;
+                }
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                // A word wider than a whole line is split wherever it overflows.
+                for c in word.chars() {
+                    let mut longer = line.clone();
+                    longer.push(c);
+                    if !line.is_empty() && self.text_width(&longer) > max_width {
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    line.push(c);
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Distance from the top of a node to the middle of its column row `index`.
+    pub fn row_center(&self, index: usize) -> f64 {
+        let header_height = self.line_height + self.header_padding * 2.0;
+        header_height + self.padding_y + self.line_height * (index as f64 + 0.5)
+    }
 }
 
 #[cfg(test)]
@@ -97,4 +195,45 @@ mod tests {
         assert!(w > 0.0);
         assert!(h > m.line_height);
     }
+
+    #[test]
+    fn test_table_size() {
+        let m = TextMetrics::default();
+        let rows = vec![
+            vec!["◆ id".to_string(), "int".to_string(), String::new()],
+            vec!["  email".to_string(), "string".to_string(), String::new()],
+        ];
+        // The empty third column takes no room.
+        assert_eq!(m.cell_offsets(&rows), vec![0.0, 9.0 * 8.0, 17.0 * 8.0]);
+        let (w, h) = m.table_size("A User Account Name", &rows);
+        assert_eq!(w, 19.0 * 8.0 + 24.0);
+        assert_eq!(h, m.node_size("User", &vec![(String::new(), String::new()); 2]).1);
+
+        // A compartment adds its own rows and widens the box for long lines.
+        let lines = vec!["UNIQUE INDEX ix_user_email (email)".to_string()];
+        assert_eq!(m.with_compartment((w, h), &lines), (34.0 * 8.0 + 24.0, h + 20.0 + 16.0));
+        assert_eq!(m.with_compartment((w, h), &[]), (w, h));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let m = TextMetrics::default();
+        let width = 10.0 * m.char_width;
+        assert_eq!(
+            m.wrap("Registered customer\nof the shop", width),
+            vec!["Registered", "customer", "of the", "shop"]
+        );
+        assert_eq!(m.wrap("abcdefghijklmn", width), vec!["abcdefghij", "klmn"]);
+        assert!(m.wrap("", width).is_empty());
+    }
+
+    #[test]
+    fn test_row_center() {
+        let m = TextMetrics::default();
+        let columns = vec![("id".to_string(), "int".to_string())];
+        let (_, h) = m.node_size("User", &columns);
+        // One row, centred in the body below the header
+        assert_eq!(m.row_center(0), 28.0 + 8.0 + 10.0);
+        assert_eq!(m.row_center(0) + m.line_height / 2.0 + m.padding_y, h);
+    }
 }
@@ -1,39 +1,118 @@
 use crate::ast::*;
-use crate::lexer::{LexError, Lexer, Token};
+use crate::diagnostic::{self, Severity, Span};
+use crate::lexer::{LexError, Lexer, SpannedToken, Token};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
     #[error("Lex error: {0}")]
     Lex(#[from] LexError),
-    #[error("Unexpected token: {0:?}, expected {1}")]
-    Unexpected(Token, &'static str),
+    #[error("Unexpected {0}, expected {1}")]
+    Unexpected(Token, &'static str, Span),
     #[error("Unexpected end of input")]
     UnexpectedEof,
+    #[error(
+        "Invalid cardinality `{0}`, expected `1`, `0..1`, `*`, `1..*` or `min..max` \
+         with an upper bound of at least 1 and no less than `min`"
+    )]
+    InvalidCardinality(String, Span),
+}
+
+impl ParseError {
+    /// Where the error was found, when it is known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::Lex(e) => Some(e.span()),
+            ParseError::Unexpected(_, _, span) => Some(*span),
+            ParseError::UnexpectedEof => None,
+            ParseError::InvalidCardinality(_, span) => Some(*span),
+        }
+    }
+
+    /// Report the error against `source` as `file:line:col`, with the
+    /// offending line underlined.
+    pub fn render(&self, file: &str, source: &str) -> String {
+        let span = self.span().unwrap_or_default();
+        diagnostic::render(file, source, Severity::Error, span, &self.to_string())
+    }
 }
 
 pub struct Parser {
-    tokens: Vec<Token>,
+    tokens: Vec<SpannedToken>,
     pos: usize,
+    /// Errors recovered from so far, in the order they were found.
+    errors: Vec<ParseError>,
+    /// Inside a view, where `@hint.arrangement` belongs to the view rather
+    /// than starting a new item.
+    in_view: bool,
 }
 
 impl Parser {
     pub fn new(input: &str) -> Result<Self, ParseError> {
         let mut lexer = Lexer::new(input);
         lexer.set_preserve_newlines(true);
-        let tokens = lexer.tokenize()?;
-        Ok(Self { tokens, pos: 0 })
+        let tokens = lexer.tokenize_spanned()?;
+        Ok(Self {
+            tokens,
+            pos: 0,
+            errors: Vec::new(),
+            in_view: false,
+        })
+    }
+
+    /// Like `new`, but never fails: characters the lexer cannot read are
+    /// dropped, and reported later by `parse_recovering`.
+    pub fn new_recovering(input: &str) -> Self {
+        Self::new_recovering_in(input, 0)
+    }
+
+    /// Like `new_recovering`, for the loader's `file`-th file: every span
+    /// records that index.
+    pub fn new_recovering_in(input: &str, file: usize) -> Self {
+        let mut lexer = Lexer::new(input);
+        lexer.set_preserve_newlines(true);
+        lexer.set_file(file);
+        let (tokens, errors) = lexer.tokenize_recovering();
+        Self {
+            tokens,
+            pos: 0,
+            errors: errors.into_iter().map(ParseError::Lex).collect(),
+            in_view: false,
+        }
     }
 
     fn peek(&self) -> &Token {
-        self.tokens.get(self.pos).unwrap_or(&Token::Eof)
+        self.tokens.get(self.pos).map_or(&Token::Eof, |t| &t.token)
+    }
+
+    /// Span of the next token. Past the end, that is the `Eof` token.
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|t| t.span)
+            .unwrap_or_default()
+    }
+
+    /// Span of the token consumed last.
+    fn prev_span(&self) -> Span {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|t| t.span)
+            .unwrap_or_default()
     }
 
     fn advance(&mut self) -> &Token {
-        let tok = self.tokens.get(self.pos).unwrap_or(&Token::Eof);
+        let tok = self.tokens.get(self.pos).map_or(&Token::Eof, |t| &t.token);
         self.pos += 1;
         tok
     }
 
+    /// Error for the next token, which is not what the grammar allows here.
+    fn unexpected(&self, expected: &'static str) -> ParseError {
+        ParseError::Unexpected(self.peek().clone(), expected, self.span())
+    }
+
     /// Skip all newline tokens.
     fn skip_newlines(&mut self) {
         while self.peek() == &Token::Newline {
@@ -42,18 +121,21 @@ impl Parser {
     }
 
     fn expect_ident(&mut self) -> Result<String, ParseError> {
-        match self.advance().clone() {
-            Token::Ident(s) => Ok(s),
-            tok => Err(ParseError::Unexpected(tok, "identifier")),
+        match self.peek().clone() {
+            Token::Ident(s) => {
+                self.advance();
+                Ok(s)
+            }
+            _ => Err(self.unexpected("identifier")),
         }
     }
 
     fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
-        let tok = self.advance().clone();
-        if tok == expected {
+        if *self.peek() == expected {
+            self.advance();
             Ok(())
         } else {
-            Err(ParseError::Unexpected(tok, "specific token"))
+            Err(self.unexpected(expected.describe()))
         }
     }
 
@@ -61,50 +143,192 @@ impl Parser {
         matches!(self.peek(), Token::Ident(s) if s == name)
     }
 
-    pub fn parse(&mut self) -> Result<Schema, ParseError> {
-        let mut entities = Vec::new();
-        let mut relationships = Vec::new();
-        let mut views = Vec::new();
-        let mut arrangement = None;
+    /// True if the next tokens open a top-level item: `entity Name {`,
+    /// `enum Name {`, `rel {`, `view name {` or `@hint.arrangement =`.
+    fn at_item_start(&self) -> bool {
+        let mut ahead = self.tokens[self.pos.min(self.tokens.len())..]
+            .iter()
+            .map(|t| &t.token)
+            .filter(|t| !matches!(t, Token::Newline | Token::Doc(_)));
+        let mut next = || ahead.next().unwrap_or(&Token::Eof);
+
+        match next() {
+            Token::Ident(k) if k == "entity" => {
+                matches!(next(), Token::Ident(_))
+                    && matches!(next(), Token::LBrace | Token::Str(_) | Token::Ident(_))
+            }
+            Token::Ident(k) if k == "enum" => matches!(next(), Token::Ident(_)) && *next() == Token::LBrace,
+            // `view name extends base {`
+            Token::Ident(k) if k == "view" => {
+                matches!(next(), Token::Ident(_)) && matches!(next(), Token::LBrace | Token::Ident(_))
+            }
+            Token::Ident(k) if k == "rel" => *next() == Token::LBrace,
+            Token::Ident(k) if k == "import" => matches!(next(), Token::Str(_)),
+            Token::At if !self.in_view => {
+                matches!(next(), Token::Ident(k) if k == "hint")
+                    && *next() == Token::Dot
+                    && matches!(next(), Token::Ident(k) if k == "arrangement")
+            }
+            _ => false,
+        }
+    }
+
+    /// Skip the rest of a broken line, up to the newline or the `}` that
+    /// closes the enclosing block.
+    fn skip_line(&mut self) {
+        while !matches!(self.peek(), Token::Newline | Token::RBrace | Token::Eof) {
+            self.advance();
+        }
+    }
+
+    /// Skip to the next line that starts a top-level item.
+    fn synchronize(&mut self) {
+        loop {
+            match self.peek() {
+                Token::Eof => return,
+                Token::Newline => {
+                    self.skip_newlines();
+                    if self.at_item_start() {
+                        return;
+                    }
+                }
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
 
+    /// Collect the `///` lines before the next item, one line of text each.
+    fn parse_doc(&mut self) -> Option<String> {
+        let mut lines = Vec::new();
         loop {
             self.skip_newlines();
+            let Token::Doc(line) = self.peek().clone() else {
+                break;
+            };
+            self.advance();
+            lines.push(line);
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    /// Add a `///` comment left at the end of a line to `doc`, the lines
+    /// written above it.
+    fn parse_trailing_doc(&mut self, doc: Option<String>) -> Option<String> {
+        let Token::Doc(line) = self.peek().clone() else {
+            return doc;
+        };
+        self.advance();
+        Some(match doc {
+            Some(lines) => format!("{}\n{}", lines, line),
+            None => line,
+        })
+    }
+
+    /// Parse the members of a block whose `{` has been consumed, up to and
+    /// including its `}`, each with the doc comment written above it. A member
+    /// that fails is recorded and the rest of its line skipped; a block left
+    /// open is reported where the next item starts.
+    fn parse_block(
+        &mut self,
+        mut member: impl FnMut(&mut Self, Option<String>) -> Result<(), ParseError>,
+    ) {
+        loop {
+            let doc = self.parse_doc();
+            if *self.peek() == Token::RBrace {
+                self.advance();
+                return;
+            }
+            if *self.peek() == Token::Eof || self.at_item_start() {
+                self.errors.push(self.unexpected("`}`"));
+                return;
+            }
+            if let Err(e) = member(self, doc) {
+                self.errors.push(e);
+                self.skip_line();
+            }
+        }
+    }
+
+    /// Parse the whole input, stopping at the first error.
+    pub fn parse(&mut self) -> Result<Schema, ParseError> {
+        let (schema, mut errors) = self.parse_recovering();
+        if errors.is_empty() {
+            Ok(schema)
+        } else {
+            Err(errors.swap_remove(0))
+        }
+    }
+
+    /// Parse the whole input, recovering from errors at line ends and item
+    /// boundaries. Returns what could be parsed along with every error found,
+    /// in source order.
+    pub fn parse_recovering(&mut self) -> (Schema, Vec<ParseError>) {
+        let mut schema = Schema {
+            entities: Vec::new(),
+            enums: Vec::new(),
+            relationships: Vec::new(),
+            views: Vec::new(),
+            arrangement: None,
+            hints: Vec::new(),
+            imports: Vec::new(),
+        };
+
+        loop {
+            let doc = self.parse_doc();
             if *self.peek() == Token::Eof {
                 break;
             }
+            if let Err(e) = self.parse_item(&mut schema, doc) {
+                self.errors.push(e);
+                self.synchronize();
+            }
+        }
 
-            if *self.peek() == Token::At {
-                // Could be @hint.arrangement at top level
-                if self.try_parse_arrangement()? {
-                    arrangement = Some(self.parse_arrangement_block()?);
-                } else {
-                    return Err(ParseError::Unexpected(
-                        self.peek().clone(),
-                        "entity, rel, view, or @hint.arrangement",
-                    ));
-                }
-            } else if self.check_ident("entity") {
-                self.advance();
-                entities.push(self.parse_entity()?);
-            } else if self.check_ident("rel") {
-                self.advance();
-                relationships.extend(self.parse_rel_block()?);
-            } else if self.check_ident("view") {
-                self.advance();
-                views.push(self.parse_view()?);
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.sort_by_key(|e| e.span().map_or(usize::MAX, |s| s.start));
+        (schema, errors)
+    }
+
+    /// Parse one top-level item. `doc` only documents an entity or an enum;
+    /// above anything else it is dropped.
+    fn parse_item(&mut self, schema: &mut Schema, doc: Option<String>) -> Result<(), ParseError> {
+        if *self.peek() == Token::At {
+            // Could be @hint.arrangement at top level
+            if self.try_parse_arrangement()? {
+                schema.arrangement = Some(self.parse_arrangement_block()?);
             } else {
-                return Err(ParseError::Unexpected(
-                    self.peek().clone(),
-                    "entity, rel, view, or @hint.arrangement",
-                ));
+                schema.hints.push(self.parse_hint()?);
             }
+        } else if self.check_ident("entity") {
+            schema.entities.push(self.parse_entity(doc)?);
+        } else if self.check_ident("enum") {
+            schema.enums.push(self.parse_enum(doc)?);
+        } else if self.check_ident("rel") {
+            self.advance();
+            schema.relationships.extend(self.parse_rel_block()?);
+        } else if self.check_ident("view") {
+            schema.views.push(self.parse_view()?);
+        } else if self.check_ident("import") {
+            schema.imports.push(self.parse_import()?);
+        } else {
+            return Err(self.unexpected("entity, enum, rel, view, import, or a hint"));
         }
+        Ok(())
+    }
 
-        Ok(Schema {
-            entities,
-            relationships,
-            views,
-            arrangement,
+    /// Parse `import "path"`.
+    fn parse_import(&mut self) -> Result<Import, ParseError> {
+        let start = self.span();
+        self.advance(); // import
+        let Token::Str(path) = self.peek().clone() else {
+            return Err(self.unexpected("file name in quotes"));
+        };
+        self.advance();
+        Ok(Import {
+            path,
+            span: start.to(self.prev_span()),
         })
     }
 
@@ -147,19 +371,33 @@ impl Parser {
 
     /// Parse arrangement block: { Entity1 Entity2; Entity3 Entity4; ... }
     /// Rows can be separated by semicolons or newlines.
-    fn parse_arrangement_block(&mut self) -> Result<Vec<Vec<String>>, ParseError> {
+    fn parse_arrangement_block(&mut self) -> Result<Vec<Vec<Name>>, ParseError> {
         self.skip_newlines();
         self.expect(Token::LBrace)?;
 
-        let mut rows: Vec<Vec<String>> = Vec::new();
-        let mut current_row: Vec<String> = Vec::new();
+        let mut rows: Vec<Vec<Name>> = Vec::new();
+        let mut current_row: Vec<Name> = Vec::new();
 
         loop {
             match self.peek().clone() {
-                Token::RBrace => break,
+                Token::RBrace => {
+                    self.advance();
+                    break;
+                }
+                Token::Eof => {
+                    self.errors.push(self.unexpected("`}`"));
+                    break;
+                }
+                _ if current_row.is_empty() && self.at_item_start() => {
+                    self.errors.push(self.unexpected("`}`"));
+                    break;
+                }
                 Token::Ident(name) => {
                     self.advance();
-                    current_row.push(name);
+                    current_row.push(Name {
+                        name,
+                        span: self.prev_span(),
+                    });
                 }
                 Token::Semicolon | Token::Newline => {
                     self.advance();
@@ -168,8 +406,10 @@ impl Parser {
                         current_row = Vec::new();
                     }
                 }
-                tok => {
-                    return Err(ParseError::Unexpected(tok, "entity name, semicolon, or newline"));
+                _ => {
+                    self.errors
+                        .push(self.unexpected("entity name, semicolon, or newline"));
+                    self.advance();
                 }
             }
         }
@@ -179,13 +419,29 @@ impl Parser {
             rows.push(current_row);
         }
 
-        self.expect(Token::RBrace)?;
         Ok(rows)
     }
 
-    fn parse_entity(&mut self) -> Result<Entity, ParseError> {
+    /// Parse `entity Name ["description"] { ... }`. The description follows
+    /// any `///` lines in `doc`.
+    fn parse_entity(&mut self, mut doc: Option<String>) -> Result<Entity, ParseError> {
+        let start = self.span();
+        self.advance(); // entity
         self.skip_newlines();
         let name = self.expect_ident()?;
+        let extends = if self.check_ident("extends") {
+            self.advance();
+            Some(self.parse_extends()?)
+        } else {
+            None
+        };
+        if let Token::Str(description) = self.peek().clone() {
+            self.advance();
+            doc = Some(match doc {
+                Some(lines) => format!("{}\n{}", lines, description),
+                None => description,
+            });
+        }
         self.skip_newlines();
         self.expect(Token::LBrace)?;
 
@@ -193,40 +449,112 @@ impl Parser {
         let mut constraints = Vec::new();
         let mut hints = Vec::new();
 
-        loop {
-            self.skip_newlines();
-            if *self.peek() == Token::RBrace {
-                break;
-            }
-            if *self.peek() == Token::At {
-                hints.push(self.parse_hint()?);
-            } else if self.check_ident("primary_key") {
-                self.advance();
-                constraints.push(self.parse_primary_key()?);
-            } else if self.check_ident("foreign_key") {
-                self.advance();
-                constraints.push(self.parse_foreign_key()?);
-            } else if self.check_ident("index") {
-                self.advance();
-                constraints.push(self.parse_index()?);
+        self.parse_block(|p, doc| {
+            if *p.peek() == Token::At {
+                hints.push(p.parse_hint()?);
+            } else if p.check_ident("primary_key") {
+                p.advance();
+                constraints.push(p.parse_primary_key()?);
+            } else if p.check_ident("foreign_key") {
+                p.advance();
+                constraints.push(p.parse_foreign_key()?);
+            } else if p.check_ident("index") {
+                p.advance();
+                constraints.push(p.parse_index()?);
             } else {
-                columns.push(self.parse_column()?);
+                columns.push(p.parse_column(doc)?);
             }
-        }
-
-        self.expect(Token::RBrace)?;
+            Ok(())
+        });
 
         Ok(Entity {
             name,
             columns,
             constraints,
             hints,
+            doc,
+            extends,
+            span: start.to(self.prev_span()),
+        })
+    }
+
+    /// Parse the rest of `extends Parent [disjoint | overlapping]
+    /// [total | partial]`.
+    fn parse_extends(&mut self) -> Result<Extends, ParseError> {
+        let span = self.span();
+        let parent = Name {
+            name: self.expect_ident()?,
+            span,
+        };
+        let mut extends = Extends {
+            parent,
+            disjointness: None,
+            completeness: None,
+        };
+        loop {
+            let Token::Ident(word) = self.peek() else {
+                return Ok(extends);
+            };
+            match word.as_str() {
+                "disjoint" if extends.disjointness.is_none() => {
+                    extends.disjointness = Some(Disjointness::Disjoint)
+                }
+                "overlapping" if extends.disjointness.is_none() => {
+                    extends.disjointness = Some(Disjointness::Overlapping)
+                }
+                "total" if extends.completeness.is_none() => {
+                    extends.completeness = Some(Completeness::Total)
+                }
+                "partial" if extends.completeness.is_none() => {
+                    extends.completeness = Some(Completeness::Partial)
+                }
+                _ => return Err(self.unexpected("disjoint, overlapping, total, partial or '{'")),
+            }
+            self.advance();
+        }
+    }
+
+    /// Parse `enum Name { a, b, c }`. Values are identifiers or strings,
+    /// separated by commas, line breaks or both.
+    fn parse_enum(&mut self, doc: Option<String>) -> Result<Enum, ParseError> {
+        let start = self.span();
+        self.advance(); // enum
+        self.skip_newlines();
+        let name = self.expect_ident()?;
+        self.skip_newlines();
+        self.expect(Token::LBrace)?;
+
+        let mut values = Vec::new();
+        self.parse_block(|p, _| {
+            loop {
+                match p.peek().clone() {
+                    Token::Ident(value) | Token::Str(value) => values.push(value),
+                    _ => return Err(p.unexpected("enum value")),
+                }
+                p.advance();
+                if *p.peek() != Token::Comma {
+                    return Ok(());
+                }
+                p.advance();
+                // A comma may end the line, or the last value.
+                if matches!(p.peek(), Token::Newline | Token::RBrace) {
+                    return Ok(());
+                }
+            }
+        });
+
+        Ok(Enum {
+            name,
+            values,
+            doc,
+            span: start.to(self.prev_span()),
         })
     }
 
-    fn parse_column(&mut self) -> Result<Column, ParseError> {
+    fn parse_column(&mut self, doc: Option<String>) -> Result<Column, ParseError> {
+        let start = self.span();
         let name = self.expect_ident()?;
-        let typ = self.expect_ident()?;
+        let typ = self.parse_column_type()?;
         let mut modifiers = Vec::new();
 
         loop {
@@ -250,6 +578,13 @@ impl Parser {
                 self.advance();
                 let val = self.parse_default_value()?;
                 modifiers.push(ColumnModifier::Default(val));
+            } else if self.check_ident("check") {
+                self.advance();
+                match self.peek().clone() {
+                    Token::Str(condition) => modifiers.push(ColumnModifier::Check(condition)),
+                    _ => return Err(self.unexpected("quoted check condition")),
+                }
+                self.advance();
             } else if self.check_ident("fk") {
                 self.advance();
                 self.expect(Token::Arrow)?;
@@ -261,14 +596,47 @@ impl Parser {
                 break;
             }
         }
+        let doc = self.parse_trailing_doc(doc);
 
         Ok(Column {
             name,
             typ,
             modifiers,
+            doc,
+            span: start.to(self.prev_span()),
         })
     }
 
+    /// Parse `name`, `name(arg, ...)` and any `[]` suffixes.
+    fn parse_column_type(&mut self) -> Result<ColumnType, ParseError> {
+        let mut typ = ColumnType::new(self.expect_ident()?);
+
+        if *self.peek() == Token::LParen {
+            self.advance();
+            loop {
+                match self.peek().clone() {
+                    Token::Num(n) => typ.args.push(n.to_string()),
+                    Token::Ident(s) => typ.args.push(s),
+                    _ => return Err(self.unexpected("type parameter")),
+                }
+                self.advance();
+                if *self.peek() != Token::Comma {
+                    break;
+                }
+                self.advance();
+            }
+            self.expect(Token::RParen)?;
+        }
+
+        while *self.peek() == Token::LBracket {
+            self.advance();
+            self.expect(Token::RBracket)?;
+            typ.array_dims += 1;
+        }
+
+        Ok(typ)
+    }
+
     fn parse_default_value(&mut self) -> Result<String, ParseError> {
         match self.advance().clone() {
             Token::Ident(s) => {
@@ -317,11 +685,12 @@ impl Parser {
             }
             Token::Str(s) => Ok(format!("\"{}\"", s)),
             Token::Num(n) => Ok(n.to_string()),
-            tok => Err(ParseError::Unexpected(tok, "default value")),
+            tok => Err(ParseError::Unexpected(tok, "default value", self.prev_span())),
         }
     }
 
     fn parse_hint(&mut self) -> Result<Hint, ParseError> {
+        let start = self.span();
         self.expect(Token::At)?;
         let mut key = self.expect_ident()?;
 
@@ -332,15 +701,25 @@ impl Parser {
         }
 
         self.expect(Token::Eq)?;
+        let value = self.parse_value("hint value")?;
 
-        let value = match self.advance().clone() {
+        Ok(Hint {
+            key,
+            value,
+            span: start.to(self.prev_span()),
+        })
+    }
+
+    /// Parse the number, string or identifier on the right of an `=`.
+    fn parse_value(&mut self, expected: &'static str) -> Result<HintValue, ParseError> {
+        let value = match self.peek().clone() {
             Token::Num(n) => HintValue::Int(n),
             Token::Str(s) => HintValue::Str(s),
             Token::Ident(s) => HintValue::Ident(s),
-            tok => return Err(ParseError::Unexpected(tok, "hint value")),
+            _ => return Err(self.unexpected(expected)),
         };
-
-        Ok(Hint { key, value })
+        self.advance();
+        Ok(value)
     }
 
     fn parse_primary_key(&mut self) -> Result<Constraint, ParseError> {
@@ -356,10 +735,7 @@ impl Parser {
         self.expect(Token::RParen)?;
 
         if !self.check_ident("references") {
-            return Err(ParseError::Unexpected(
-                self.peek().clone(),
-                "references",
-            ));
+            return Err(self.unexpected("references"));
         }
         self.advance();
 
@@ -375,10 +751,10 @@ impl Parser {
             self.advance();
             if self.check_ident("delete") {
                 self.advance();
-                on_delete = Some(self.expect_ident()?);
+                on_delete = Some(self.parse_action()?);
             } else if self.check_ident("update") {
                 self.advance();
-                on_update = Some(self.expect_ident()?);
+                on_update = Some(self.parse_action()?);
             }
         }
 
@@ -391,23 +767,53 @@ impl Parser {
         })
     }
 
+    /// A referential action: `cascade`, `restrict`, or one of the two-word
+    /// `set null`, `set default` and `no action`.
+    fn parse_action(&mut self) -> Result<String, ParseError> {
+        let action = self.expect_ident()?;
+        let second = match action.as_str() {
+            "set" => ["null", "default"].into_iter().find(|w| self.check_ident(w)),
+            "no" => ["action"].into_iter().find(|w| self.check_ident(w)),
+            _ => None,
+        };
+        match second {
+            Some(word) => {
+                self.advance();
+                Ok(format!("{} {}", action, word))
+            }
+            None => Ok(action),
+        }
+    }
+
     fn parse_index(&mut self) -> Result<Constraint, ParseError> {
         self.expect(Token::LParen)?;
         let columns = self.parse_ident_list()?;
         self.expect(Token::RParen)?;
 
         let mut name = None;
+        let mut unique = false;
         if *self.peek() == Token::LBracket {
             self.advance();
-            if self.check_ident("name") {
+            while *self.peek() != Token::RBracket {
+                if self.check_ident("name") {
+                    self.advance();
+                    self.expect(Token::Eq)?;
+                    name = Some(self.expect_ident()?);
+                } else if self.check_ident("unique") {
+                    self.advance();
+                    unique = true;
+                } else {
+                    return Err(self.unexpected("`name` or `unique`"));
+                }
+                if *self.peek() != Token::Comma {
+                    break;
+                }
                 self.advance();
-                self.expect(Token::Eq)?;
-                name = Some(self.expect_ident()?);
             }
             self.expect(Token::RBracket)?;
         }
 
-        Ok(Constraint::Index { columns, name })
+        Ok(Constraint::Index { columns, name, unique })
     }
 
     fn parse_ident_list(&mut self) -> Result<Vec<String>, ParseError> {
@@ -424,104 +830,278 @@ impl Parser {
         self.expect(Token::LBrace)?;
         let mut rels = Vec::new();
 
-        loop {
-            self.skip_newlines();
-            if *self.peek() == Token::RBrace {
-                break;
-            }
-            rels.push(self.parse_relationship()?);
-        }
+        self.parse_block(|p, doc| {
+            rels.push(p.parse_relationship(doc)?);
+            Ok(())
+        });
 
-        self.expect(Token::RBrace)?;
         Ok(rels)
     }
 
-    fn parse_relationship(&mut self) -> Result<Relationship, ParseError> {
+    fn parse_relationship(&mut self, doc: Option<String>) -> Result<Relationship, ParseError> {
         self.skip_newlines();
+        let start = self.span();
         let left = self.expect_ident()?;
+        let left_column = self.parse_end_column()?;
         let left_cardinality = self.parse_cardinality()?;
         self.expect(Token::Dash)?;
         let right_cardinality = self.parse_cardinality()?;
         let right = self.expect_ident()?;
+        let right_column = self.parse_end_column()?;
+
+        let via = if self.check_ident("via") {
+            self.advance();
+            Some(self.expect_ident()?)
+        } else {
+            None
+        };
 
         let mut label = None;
-        let mut role = None;
+        let mut left_role = None;
+        let mut right_role = None;
+        let mut identifying = None;
 
         if *self.peek() == Token::Colon {
             self.advance();
-            match self.advance().clone() {
+            match self.peek().clone() {
                 Token::Str(s) => label = Some(s),
-                tok => return Err(ParseError::Unexpected(tok, "string label")),
+                _ => return Err(self.unexpected("string label")),
             }
+            self.advance();
+        }
+
+        if self.check_ident("identifying") {
+            self.advance();
+            identifying = Some(true);
+        } else if self.check_ident("non_identifying") {
+            self.advance();
+            identifying = Some(false);
         }
 
         if self.check_ident("as") {
             self.advance();
-            role = Some(self.expect_ident()?);
+            left_role = Some(self.expect_ident()?).filter(|role| role != "_");
+            if *self.peek() == Token::Comma {
+                self.advance();
+                right_role = Some(self.expect_ident()?).filter(|role| role != "_");
+            }
         }
+        let attributes = self.parse_attributes()?;
+        let doc = self.parse_trailing_doc(doc);
 
         Ok(Relationship {
             left,
+            left_column,
             left_cardinality,
             right,
+            right_column,
             right_cardinality,
+            via,
             label,
-            role,
+            left_role,
+            right_role,
+            identifying,
+            attributes,
+            doc,
+            span: start.to(self.prev_span()),
         })
     }
 
+    /// Parse `[dashed, color = red, width = 2]` at the end of a relationship,
+    /// if there is one.
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, ParseError> {
+        let mut attributes = Vec::new();
+        if *self.peek() != Token::LBracket {
+            return Ok(attributes);
+        }
+        self.advance();
+        loop {
+            let start = self.span();
+            let name = self.expect_ident()?;
+            let value = if *self.peek() == Token::Eq {
+                self.advance();
+                Some(self.parse_value("attribute value")?)
+            } else {
+                None
+            };
+            attributes.push(Attribute {
+                name,
+                value,
+                span: start.to(self.prev_span()),
+            });
+            if *self.peek() != Token::Comma {
+                break;
+            }
+            self.advance();
+        }
+        self.expect(Token::RBracket)?;
+        Ok(attributes)
+    }
+
+    /// Parse the `.column` after an entity name in a relationship, if any.
+    fn parse_end_column(&mut self) -> Result<Option<String>, ParseError> {
+        if *self.peek() != Token::Dot {
+            return Ok(None);
+        }
+        self.advance();
+        Ok(Some(self.expect_ident()?))
+    }
+
+    /// Parse `*`, `n` or `min..max`, where `max` may be `*`.
     fn parse_cardinality(&mut self) -> Result<Cardinality, ParseError> {
-        match self.peek().clone() {
+        let start = self.span();
+        let min = match self.peek().clone() {
             Token::Star => {
                 self.advance();
-                Ok(Cardinality::Many)
+                return Ok(Cardinality::MANY);
             }
-            Token::Num(0) => {
-                self.advance();
-                self.expect(Token::DotDot)?;
-                match self.advance().clone() {
-                    Token::Num(1) => Ok(Cardinality::ZeroOrOne),
-                    tok => Err(ParseError::Unexpected(tok, "1 after 0..")),
-                }
+            Token::Num(n) => n,
+            _ => return Err(self.unexpected("cardinality (1, 0..1, *, 1..*, 2..5)")),
+        };
+        self.advance();
+
+        let max = if *self.peek() == Token::DotDot {
+            self.advance();
+            let max = match self.peek() {
+                Token::Star => None,
+                Token::Num(n) => Some(*n),
+                _ => return Err(self.unexpected("upper bound or `*`")),
+            };
+            self.advance();
+            max
+        } else {
+            Some(min)
+        };
+
+        let bound = |n: i64| u32::try_from(n).ok();
+        match (bound(min), max.map(bound)) {
+            (Some(min), None) => Ok(Cardinality::range(min, None)),
+            (Some(min), Some(Some(max))) if max >= 1 && max >= min => {
+                Ok(Cardinality::range(min, Some(max)))
             }
-            Token::Num(1) => {
-                self.advance();
-                if *self.peek() == Token::DotDot {
-                    self.advance();
-                    self.expect(Token::Star)?;
-                    Ok(Cardinality::OneOrMore)
-                } else {
-                    Ok(Cardinality::One)
-                }
+            _ => {
+                let span = start.to(self.prev_span());
+                let written = match max {
+                    Some(max) if max != min => format!("{}..{}", min, max),
+                    _ => min.to_string(),
+                };
+                Err(ParseError::InvalidCardinality(written, span))
             }
-            tok => Err(ParseError::Unexpected(tok, "cardinality (1, 0..1, *, 1..*)")),
         }
     }
 
     fn parse_view(&mut self) -> Result<View, ParseError> {
+        let start = self.span();
+        self.advance(); // view
         self.skip_newlines();
         let name = self.expect_ident()?;
+        let extends = if self.check_ident("extends") {
+            self.advance();
+            let span = self.span();
+            let name = self.expect_ident()?;
+            Some(Name { name, span })
+        } else {
+            None
+        };
         self.skip_newlines();
         self.expect(Token::LBrace)?;
 
-        let mut includes = Vec::new();
+        let mut rules = Vec::new();
+        let mut arrangement = None;
+        let mut hints = Vec::new();
 
-        loop {
-            self.skip_newlines();
-            if *self.peek() == Token::RBrace {
-                break;
+        self.in_view = true;
+        self.parse_block(|p, _| {
+            if *p.peek() == Token::At {
+                if p.try_parse_arrangement()? {
+                    arrangement = Some(p.parse_arrangement_block()?);
+                } else {
+                    hints.push(p.parse_hint()?);
+                }
+                return Ok(());
             }
-            if self.check_ident("include") {
-                self.advance();
-                includes.extend(self.parse_ident_list()?);
+            let exclude = if p.check_ident("include") {
+                false
+            } else if p.check_ident("exclude") {
+                true
             } else {
-                return Err(ParseError::Unexpected(self.peek().clone(), "include"));
+                return Err(p.unexpected("include, exclude or a hint"));
+            };
+            p.advance();
+            let mut selectors = vec![p.parse_selector()?];
+            while *p.peek() == Token::Comma {
+                p.advance();
+                selectors.push(p.parse_selector()?);
             }
-        }
+            rules.push(ViewRule { exclude, selectors });
+            Ok(())
+        });
+        self.in_view = false;
 
-        self.expect(Token::RBrace)?;
+        Ok(View {
+            name,
+            extends,
+            rules,
+            arrangement,
+            hints,
+            span: start.to(self.prev_span()),
+        })
+    }
 
-        Ok(View { name, includes })
+    /// One item of an `include` or `exclude` list: a pattern,
+    /// `neighbours(pattern, depth)` or `group name`.
+    fn parse_selector(&mut self) -> Result<Selector, ParseError> {
+        // Both words are also fine entity names, when nothing follows them.
+        let next = self.tokens.get(self.pos + 1).map(|t| &t.token);
+        if self.check_ident("neighbours") && next == Some(&Token::LParen) {
+            self.advance();
+            self.advance();
+            let pattern = self.parse_pattern()?;
+            let mut depth = 1;
+            if *self.peek() == Token::Comma {
+                self.advance();
+                match self.peek().clone() {
+                    Token::Num(n) if n >= 0 => depth = n as usize,
+                    _ => return Err(self.unexpected("depth")),
+                }
+                self.advance();
+            }
+            self.expect(Token::RParen)?;
+            return Ok(Selector::Neighbours { pattern, depth });
+        }
+        if self.check_ident("group")
+            && let Some(Token::Str(name) | Token::Ident(name)) = next
+        {
+            let name = name.clone();
+            self.advance();
+            let span = self.span();
+            self.advance();
+            return Ok(Selector::Group(Name { name, span }));
+        }
+        Ok(Selector::Pattern(self.parse_pattern()?))
+    }
+
+    /// An entity name, or a pattern of name parts and `*` written without
+    /// spaces between them, such as `Order*` or `*_audit`.
+    fn parse_pattern(&mut self) -> Result<Name, ParseError> {
+        let start = self.span();
+        let mut pattern = String::new();
+        loop {
+            let adjoins = pattern.is_empty() || self.span().start == self.prev_span().end;
+            match self.peek().clone() {
+                Token::Ident(s) if adjoins => pattern.push_str(&s),
+                Token::Star if adjoins => pattern.push('*'),
+                _ => break,
+            }
+            self.advance();
+        }
+        if pattern.is_empty() {
+            return Err(self.unexpected("entity name or pattern"));
+        }
+        Ok(Name {
+            name: pattern,
+            span: start.to(self.prev_span()),
+        })
     }
 }
 
@@ -536,27 +1116,163 @@ mod tests {
                 id int pk
                 name string not null
                 email string unique
+                age int check "age >= 18"
             }
         "#;
         let schema = Parser::new(input).unwrap().parse().unwrap();
         assert_eq!(schema.entities.len(), 1);
         assert_eq!(schema.entities[0].name, "User");
-        assert_eq!(schema.entities[0].columns.len(), 3);
+        assert_eq!(schema.entities[0].columns.len(), 4);
+        assert_eq!(
+            schema.entities[0].columns[3].modifiers,
+            vec![ColumnModifier::Check("age >= 18".into())]
+        );
     }
 
     #[test]
     fn test_parse_relationship() {
-        let input = r#"
+        let input = r##"
             rel {
                 User 1 -- * Order : "places"
                 User 0..1 -- 1..* Post as author
+                Order 1 -- * OrderItem : "contains" identifying
+                User 1 -- * Session non_identifying as owner
+                Order * -- 1..* Tag via OrderTag : "tagged"
+                User 1 -- * Order as buyer [dashed, color = "#c33", width = 2]
+                Employee 0..1 -- * Employee as manager, reports
+                User 1 -- * Order as _, purchases
+                User 1 -- * Order as seller, _
             }
-        "#;
+        "##;
         let schema = Parser::new(input).unwrap().parse().unwrap();
-        assert_eq!(schema.relationships.len(), 2);
+        assert_eq!(schema.relationships.len(), 9);
         assert_eq!(schema.relationships[0].left, "User");
         assert_eq!(schema.relationships[0].label, Some("places".into()));
-        assert_eq!(schema.relationships[1].role, Some("author".into()));
+        assert_eq!(schema.relationships[0].identifying, None);
+        assert_eq!(schema.relationships[1].left_role, Some("author".into()));
+        assert_eq!(schema.relationships[2].identifying, Some(true));
+        assert_eq!(schema.relationships[3].identifying, Some(false));
+        assert_eq!(schema.relationships[3].left_role, Some("owner".into()));
+        assert_eq!(schema.relationships[0].via, None);
+        assert_eq!(schema.relationships[4].via.as_deref(), Some("OrderTag"));
+        assert_eq!(schema.relationships[4].label.as_deref(), Some("tagged"));
+        assert!(schema.relationships[4].attributes.is_empty());
+        let attributes = &schema.relationships[5].attributes;
+        assert_eq!(schema.relationships[5].left_role.as_deref(), Some("buyer"));
+        assert_eq!(attributes.len(), 3);
+        assert_eq!((attributes[0].name.as_str(), &attributes[0].value), ("dashed", &None));
+        assert_eq!(attributes[1].value, Some(HintValue::Str("#c33".into())));
+        assert_eq!(attributes[2].value, Some(HintValue::Int(2)));
+        assert_eq!(schema.relationships[5].right_role, None);
+        let roles = |i: usize| {
+            let r = &schema.relationships[i];
+            (r.left_role.as_deref(), r.right_role.as_deref())
+        };
+        assert_eq!(roles(6), (Some("manager"), Some("reports")));
+        assert_eq!(roles(7), (None, Some("purchases")));
+        assert_eq!(roles(8), (Some("seller"), None));
+    }
+
+    #[test]
+    fn test_parse_column_anchored_relationship() {
+        let input = "rel {\n    User.id 1 -- * Order.user_id\n    User 1 -- 0..1 Profile.user_id\n}\n";
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let rels = &schema.relationships;
+        assert_eq!(rels[0].left_column.as_deref(), Some("id"));
+        assert_eq!(rels[0].right_column.as_deref(), Some("user_id"));
+        assert_eq!(rels[1].left_column, None);
+        assert_eq!(rels[1].right, "Profile");
+        assert_eq!(rels[1].right_column.as_deref(), Some("user_id"));
+    }
+
+    #[test]
+    fn test_parse_doc_comments() {
+        let input = r#"
+            /// Anyone with an account
+            entity User "Registered customer" {
+                /// Login name,
+                /// shown to other users
+                email string unique
+                name string /// as typed
+                /// not a column
+                @hint.level = 0
+                id int pk
+            }
+
+            rel {
+                /// Every order has a buyer
+                User 1 -- * Order
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let user = &schema.entities[0];
+        assert_eq!(
+            user.doc.as_deref(),
+            Some("Anyone with an account\nRegistered customer")
+        );
+        assert_eq!(
+            user.columns[0].doc.as_deref(),
+            Some("Login name,\nshown to other users")
+        );
+        assert_eq!(user.columns[1].doc.as_deref(), Some("as typed"));
+        assert_eq!(user.columns[2].doc, None);
+        assert_eq!(
+            schema.relationships[0].doc.as_deref(),
+            Some("Every order has a buyer")
+        );
+    }
+
+    #[test]
+    fn test_parse_extends() {
+        let input = r#"
+            entity Party { id int pk }
+            entity Person extends Party disjoint total "A human" {
+                birth_date date
+            }
+            entity Organization extends Party { name string }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        assert!(schema.entities[0].extends.is_none());
+
+        let person = schema.entities[1].extends.as_ref().unwrap();
+        assert_eq!(person.parent, "Party");
+        assert_eq!(&input[person.parent.span.start..person.parent.span.end], "Party");
+        assert_eq!(person.disjointness, Some(Disjointness::Disjoint));
+        assert_eq!(person.completeness, Some(Completeness::Total));
+        assert_eq!(schema.entities[1].doc.as_deref(), Some("A human"));
+
+        let organization = schema.entities[2].extends.as_ref().unwrap();
+        assert_eq!(organization.disjointness, None);
+        assert_eq!(organization.completeness, None);
+
+        let err = Parser::new("entity A extends B total partial {}").unwrap().parse().unwrap_err();
+        assert!(err.to_string().contains("expected disjoint, overlapping"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_enum() {
+        let input = r#"
+            enum Status { pending, paid, shipped }
+            /// How a parcel travels
+            enum Carrier {
+                post,
+                "same day"
+                courier
+            }
+            entity Order {
+                status Status not null
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        assert_eq!(schema.enums.len(), 2);
+        assert_eq!(schema.enums[0].values, vec!["pending", "paid", "shipped"]);
+        let carrier = schema.find_enum("Carrier").unwrap();
+        assert_eq!(carrier.values, vec!["post", "same day", "courier"]);
+        assert_eq!(carrier.doc.as_deref(), Some("How a parcel travels"));
+        assert_eq!(schema.entities[0].columns[0].typ.to_string(), "Status");
+
+        let err = Parser::new("enum Status { 1 }").unwrap().parse().unwrap_err();
+        assert!(err.to_string().contains("expected enum value"), "{err}");
     }
 
     #[test]
@@ -565,10 +1281,106 @@ mod tests {
             view core {
                 include User, Order, Product
             }
+            view sales extends core {
+                include Order*, neighbours(Invoice, 2), neighbours(Tax), group "billing"
+                exclude *_audit, group, neighbours
+            }
         "#;
         let schema = Parser::new(input).unwrap().parse().unwrap();
-        assert_eq!(schema.views.len(), 1);
-        assert_eq!(schema.views[0].includes, vec!["User", "Order", "Product"]);
+        assert_eq!(schema.views.len(), 2);
+        let describe = |rule: &ViewRule| -> Vec<String> {
+            rule.selectors
+                .iter()
+                .map(|s| match s {
+                    Selector::Pattern(name) => name.name.clone(),
+                    Selector::Neighbours { pattern, depth } => format!("neighbours({}, {})", pattern.name, depth),
+                    Selector::Group(name) => format!("group {}", name.name),
+                })
+                .collect()
+        };
+        assert_eq!(schema.views[0].extends, None);
+        assert_eq!(schema.views[0].rules.len(), 1);
+        assert_eq!(describe(&schema.views[0].rules[0]), vec!["User", "Order", "Product"]);
+
+        let sales = &schema.views[1];
+        assert_eq!(sales.extends.as_ref().unwrap().name, "core");
+        assert!(!sales.rules[0].exclude);
+        assert_eq!(
+            describe(&sales.rules[0]),
+            vec!["Order*", "neighbours(Invoice, 2)", "neighbours(Tax, 1)", "group billing"]
+        );
+        // `group` and `neighbours` alone are entity names.
+        assert!(sales.rules[1].exclude);
+        assert_eq!(describe(&sales.rules[1]), vec!["*_audit", "group", "neighbours"]);
+
+        // A pattern is written without spaces.
+        assert!(Parser::new("view v { include Order * }").unwrap().parse().is_err());
+    }
+
+    #[test]
+    fn test_parse_column_types() {
+        let input = r#"
+            entity Price {
+                code varchar(3) pk
+                amount decimal(10, 2) not null
+                tags text[]
+                grid int[][]
+                body varchar(max)
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let columns = &schema.entities[0].columns;
+
+        assert_eq!(columns[0].typ.name, "varchar");
+        assert_eq!(columns[0].typ.args, vec!["3"]);
+        assert_eq!(columns[0].modifiers, vec![ColumnModifier::Pk]);
+        assert_eq!(columns[1].typ.args, vec!["10", "2"]);
+        assert_eq!(columns[1].typ.to_string(), "decimal(10, 2)");
+        assert_eq!(columns[2].typ.array_dims, 1);
+        assert_eq!(columns[3].typ.to_string(), "int[][]");
+        assert_eq!(columns[4].typ.args, vec!["max"]);
+    }
+
+    #[test]
+    fn test_parse_cardinality_ranges() {
+        let input = r#"
+            rel {
+                Order 1..1 -- 1..5 Shipment
+                Order 0..* -- 2 Approval
+                Order 3..* -- 0..1 Note
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let rels = &schema.relationships;
+
+        assert_eq!(rels[0].left_cardinality, Cardinality::ONE);
+        assert_eq!(rels[0].right_cardinality, Cardinality::range(1, Some(5)));
+        assert_eq!(rels[1].left_cardinality, Cardinality::MANY);
+        assert_eq!(rels[1].right_cardinality, Cardinality::range(2, Some(2)));
+        assert_eq!(rels[2].left_cardinality, Cardinality::range(3, None));
+        assert_eq!(rels[2].right_cardinality, Cardinality::ZERO_OR_ONE);
+    }
+
+    #[test]
+    fn test_invalid_cardinality() {
+        for (input, written) in [("A 5..2 -- * B", "5..2"), ("A 0 -- * B", "0"), ("A 0..0 -- * B", "0")] {
+            let err = Parser::new(&format!("rel {{ {} }}", input))
+                .unwrap()
+                .parse()
+                .unwrap_err();
+            match err {
+                ParseError::InvalidCardinality(w, span) => {
+                    assert_eq!(w, written);
+                    assert_eq!(span.column, 9);
+                }
+                other => panic!("unexpected error: {}", other),
+            }
+        }
+
+        let err = Parser::new("rel { A 0 -- * B }").unwrap().parse().unwrap_err();
+        assert!(err.to_string().starts_with("Invalid cardinality `0`, expected `1`, `0..1`, `*`, `1..*`"));
+        let err = Parser::new("rel { A x -- * B }").unwrap().parse().unwrap_err();
+        assert_eq!(err.to_string(), "Unexpected `x`, expected cardinality (1, 0..1, *, 1..*, 2..5)");
     }
 
     #[test]
@@ -583,6 +1395,89 @@ mod tests {
         assert_eq!(schema.entities[0].columns[0].name, "名前");
     }
 
+    #[test]
+    fn test_parse_spans() {
+        let input = "entity User {\n    id int pk\n}\nrel {\n    User 1 -- * User\n}\n";
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+
+        let entity = &schema.entities[0];
+        assert_eq!((entity.span.line, entity.span.column), (1, 1));
+        assert_eq!(&input[entity.span.start..entity.span.end], "entity User {\n    id int pk\n}");
+
+        let column = &entity.columns[0];
+        assert_eq!((column.span.line, column.span.column), (2, 5));
+        assert_eq!(&input[column.span.start..column.span.end], "id int pk");
+
+        let rel = &schema.relationships[0];
+        assert_eq!(rel.span.line, 5);
+        assert_eq!(&input[rel.span.start..rel.span.end], "User 1 -- * User");
+    }
+
+    #[test]
+    fn test_error_location() {
+        let input = "entity User {\n    name varchar(\"n\")\n}\n";
+        let err = Parser::new(input).unwrap().parse().unwrap_err();
+
+        let span = err.span().unwrap();
+        assert_eq!((span.line, span.column), (2, 18));
+
+        let report = err.render("schema.erd", input);
+        assert!(report.contains("schema.erd:2:18"));
+        assert!(report.contains("2 |     name varchar(\"n\")"));
+    }
+
+    #[test]
+    fn test_recovers_from_multiple_errors() {
+        let input = "\
+entity User {
+    id int pk
+    name varchar pk )
+    email string unique
+}
+
+entity Order (
+    id int pk
+}
+
+rel {
+    User 1 -- Order
+    User 1 -- * Order
+}
+
+entity Product {
+    id int pk
+";
+        let (schema, errors) = Parser::new_recovering(input).parse_recovering();
+
+        let lines: Vec<usize> = errors.iter().map(|e| e.span().unwrap().line).collect();
+        assert_eq!(lines, vec![3, 7, 12, 18]);
+
+        let names: Vec<&str> = schema.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["User", "Product"]);
+        assert_eq!(schema.entities[0].columns.len(), 3);
+        assert_eq!(schema.relationships.len(), 1);
+    }
+
+    #[test]
+    fn test_recovers_from_unclosed_block() {
+        let input = "entity User {\n    id int pk\n\nentity Order {\n    id int pk\n}\n";
+        let (schema, errors) = Parser::new_recovering(input).parse_recovering();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span().unwrap().line, 4);
+        assert_eq!(schema.entities.len(), 2);
+    }
+
+    #[test]
+    fn test_recovering_reports_lex_errors() {
+        let input = "entity User {\n    id int pk $\n    name string ?\n}\n";
+        let (schema, errors) = Parser::new_recovering(input).parse_recovering();
+
+        assert!(matches!(errors[0], ParseError::Lex(_)));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(schema.entities[0].columns.len(), 2);
+    }
+
     #[test]
     fn test_parse_arrangement() {
         let input = r#"
@@ -613,4 +1508,27 @@ mod tests {
         assert_eq!(arr[1], vec!["Product", "Order", "Review", "Cart"]);
         assert_eq!(arr[2], vec!["ProductImage", "OrderItem", "CartItem", "Payment"]);
     }
+
+    #[test]
+    fn test_parse_top_level_hints() {
+        let input = r#"
+            @hint.direction = LR
+            entity User { id int pk }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+
+        assert_eq!(schema.hints.len(), 1);
+        assert_eq!(schema.hint_str("hint.direction"), Some("LR"));
+        assert_eq!(schema.hint_str("hint.missing"), None);
+    }
+
+    #[test]
+    fn test_parse_imports() {
+        let input = "import \"billing.erd\"\nimport \"../shared/users.erd\"\nentity Order { id int pk }\n";
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+
+        let paths: Vec<&str> = schema.imports.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, vec!["billing.erd", "../shared/users.erd"]);
+        assert_eq!(&input[schema.imports[0].span.start..schema.imports[0].span.end], "import \"billing.erd\"");
+    }
 }
@@ -0,0 +1,297 @@
+//! The whole way from a schema to a picture, shared by the command line, the
+//! WASM bindings and the tests.
+//!
+//! Rendering picks out the entities of the view and focus, builds the graph,
+//! applies the passes the options and hints ask for, lays it out and draws
+//! it. Every option left unset falls back to the hint of the same name, taken
+//! from the view when it sets one and from the file otherwise, then to its
+//! default.
+
+use crate::ast::Schema;
+use crate::diagnostic::{Diagnostic, Span};
+use crate::ir::{Anchoring, DetailLevel, EdgeSource, EnumBoxes, Ghosts, GraphIR, InheritedColumns, JoinEntities};
+use crate::layout::{Direction, Layout, LayoutEngine, Notes};
+use crate::svg::{Notation, Subtypes, SvgRenderer};
+use crate::view::{self, Focus, ResolvedView};
+
+/// How to render a schema. `None` means "as the hints say".
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Name of the view to render; the whole schema when unset
+    pub view: Option<String>,
+    /// Entities to narrow the view, or the whole schema, down to
+    pub focus: Focus,
+    pub detail: Option<DetailLevel>,
+    pub notation: Option<Notation>,
+    pub direction: Option<Direction>,
+    pub anchoring: Option<Anchoring>,
+    pub edges: Option<EdgeSource>,
+    pub notes: Option<Notes>,
+    pub enums: Option<EnumBoxes>,
+    pub subtypes: Option<Subtypes>,
+    pub inherited: Option<InheritedColumns>,
+    pub joins: Option<JoinEntities>,
+    pub ghosts: Option<Ghosts>,
+}
+
+/// A schema laid out and ready to draw.
+pub struct Diagram {
+    pub ir: GraphIR,
+    pub layout: Layout,
+    /// Warnings from picking out the entities, such as a pattern that matches
+    /// none of them
+    pub warnings: Vec<Diagnostic>,
+    notation: Notation,
+    subtypes: Subtypes,
+}
+
+impl Diagram {
+    pub fn to_svg(&self) -> String {
+        SvgRenderer::with_notation(self.notation)
+            .with_subtypes(self.subtypes)
+            .render(&self.ir, &self.layout)
+    }
+}
+
+/// Render `schema` to SVG, or return the errors that stop it.
+pub fn render(schema: &Schema, options: &RenderOptions) -> Result<String, Vec<Diagnostic>> {
+    layout(schema, options).map(|diagram| diagram.to_svg())
+}
+
+/// What rendering `schema` would report short of drawing it: the errors that
+/// stop it, or else the warnings about the entities it picks out.
+pub fn check(schema: &Schema, options: &RenderOptions) -> Vec<Diagnostic> {
+    match select(schema, options) {
+        Ok((_, warnings)) => warnings,
+        Err(errors) => errors,
+    }
+}
+
+/// Lay `schema` out without drawing it, or return the errors that stop it:
+/// an unknown view, or a focus naming an entity that does not exist.
+pub fn layout(schema: &Schema, options: &RenderOptions) -> Result<Diagram, Vec<Diagnostic>> {
+    let (view, warnings) = select(schema, options)?;
+
+    // The view's hints apply over the file's.
+    let hint = |key: &str| view::hint_str(schema, view.as_ref(), key);
+    let detail = options
+        .detail
+        .or_else(|| hint("hint.detail").and_then(DetailLevel::from_str))
+        .unwrap_or(DetailLevel::All);
+
+    let mut ir = GraphIR::from_schema(schema, view.as_ref(), detail);
+    let edges = options
+        .edges
+        .or_else(|| hint("hint.edges").and_then(EdgeSource::from_str))
+        .unwrap_or_default();
+    let ghosts = options
+        .ghosts
+        .or_else(|| hint("hint.ghosts").and_then(Ghosts::from_str))
+        .unwrap_or_default();
+    ir.add_ghosts(schema, ghosts, edges);
+    let joins = options
+        .joins
+        .or_else(|| hint("hint.joins").and_then(JoinEntities::from_str))
+        .unwrap_or_default();
+    if joins == JoinEntities::Collapse {
+        ir.collapse_joins(schema);
+    }
+    let inherited = options
+        .inherited
+        .or_else(|| hint("hint.inherited").and_then(InheritedColumns::from_str))
+        .unwrap_or_default();
+    if inherited == InheritedColumns::Show {
+        ir.inherit_columns(schema, detail);
+    }
+    if edges == EdgeSource::Fk {
+        ir.derive_fk_edges(schema);
+    }
+    let anchoring = options
+        .anchoring
+        .or_else(|| hint("hint.anchors").and_then(Anchoring::from_str))
+        .unwrap_or_default();
+    if anchoring == Anchoring::Columns {
+        ir.anchor_fk_columns(schema);
+    }
+    let enums = options
+        .enums
+        .or_else(|| hint("hint.enums").and_then(EnumBoxes::from_str))
+        .unwrap_or_default();
+    if enums == EnumBoxes::Show {
+        ir.add_enums(schema);
+    }
+
+    let direction = options
+        .direction
+        .or_else(|| hint("hint.direction").and_then(Direction::from_str))
+        .unwrap_or_default();
+    let notes = options
+        .notes
+        .or_else(|| hint("hint.notes").and_then(Notes::from_str))
+        .unwrap_or_default();
+    let layout = LayoutEngine::with_direction(direction).with_notes(notes).layout(&ir);
+
+    let notation = options
+        .notation
+        .or_else(|| hint("hint.notation").and_then(Notation::from_str))
+        .unwrap_or_default();
+    let subtypes = options
+        .subtypes
+        .or_else(|| hint("hint.subtypes").and_then(Subtypes::from_str))
+        .unwrap_or_default();
+
+    Ok(Diagram {
+        ir,
+        layout,
+        warnings,
+        notation,
+        subtypes,
+    })
+}
+
+/// The view `options` name, narrowed by their focus, with the focus's
+/// warnings; or the errors that stop rendering.
+fn select(schema: &Schema, options: &RenderOptions) -> Result<(Option<ResolvedView>, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let view = match options.view.as_deref() {
+        Some(name) => match view::resolve_named(schema, name) {
+            Some(view) => Some(view),
+            None => {
+                let names = schema.view_names();
+                let available = if names.is_empty() {
+                    "this file defines no views".to_string()
+                } else {
+                    format!("available: {}", names.join(", "))
+                };
+                let message = format!("Unknown view: {} ({})", name, available);
+                return Err(vec![Diagnostic::error(message, Span::default())]);
+            }
+        },
+        None => None,
+    };
+
+    // The focus narrows the view, or the whole schema. The view's own
+    // problems are reported by validation, so only the focus's are kept.
+    if options.focus.is_empty() {
+        return Ok((view, Vec::new()));
+    }
+    let known = view.as_ref().map_or(0, |v| v.diagnostics.len());
+    let mut resolved = view::resolve_focus(schema, &options.focus, view);
+    let diagnostics = resolved.diagnostics.split_off(known);
+    if diagnostics.iter().any(|d| d.is_error()) {
+        return Err(diagnostics);
+    }
+    Ok((Some(resolved), diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn schema(input: &str) -> Schema {
+        Parser::new(input).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn test_options_override_hints() {
+        let schema = schema(
+            r#"
+            @hint.direction = LR
+            @hint.enums = show
+            enum Status { open, closed }
+            entity Order {
+                id int pk
+                status Status
+            }
+        "#,
+        );
+
+        let hinted = layout(&schema, &RenderOptions::default()).unwrap();
+        assert_eq!(hinted.ir.nodes.len(), 2);
+
+        let options = RenderOptions {
+            enums: Some(EnumBoxes::Hide),
+            ..RenderOptions::default()
+        };
+        assert_eq!(layout(&schema, &options).unwrap().ir.nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_views_and_focus() {
+        let schema = schema(
+            r#"
+            entity User { id int pk }
+            entity Order { id int pk }
+            entity Invoice { id int pk }
+            rel {
+                User 1 -- * Order
+                Order 1 -- * Invoice
+            }
+            view sales {
+                @hint.notation = text
+                include User, Order
+            }
+        "#,
+        );
+
+        let options = RenderOptions {
+            view: Some("sales".to_string()),
+            ..RenderOptions::default()
+        };
+        let svg = render(&schema, &options).unwrap();
+        assert!(svg.contains(">User<") && !svg.contains(">Invoice<"));
+        assert!(!svg.contains(r#"class="edge-symbol""#));
+
+        let options = RenderOptions {
+            focus: Focus {
+                focus: vec!["Invoice".to_string()],
+                ..Focus::default()
+            },
+            ..RenderOptions::default()
+        };
+        let ids: Vec<String> = layout(&schema, &options).unwrap().ir.nodes.into_iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec!["Order", "Invoice"]);
+
+        let options = RenderOptions {
+            view: Some("sale".to_string()),
+            ..RenderOptions::default()
+        };
+        let errors = render(&schema, &options).unwrap_err();
+        assert_eq!(errors[0].message, "Unknown view: sale (available: sales)");
+
+        let options = RenderOptions {
+            focus: Focus {
+                focus: vec!["Invoce".to_string()],
+                ..Focus::default()
+            },
+            ..RenderOptions::default()
+        };
+        assert!(render(&schema, &options).unwrap_err()[0].message.contains("`Invoce`"));
+    }
+
+    #[test]
+    fn test_focus_warnings() {
+        let schema = schema("entity User { id int pk }\nentity Order { id int pk }\n");
+        let options = RenderOptions {
+            focus: Focus {
+                include: vec!["Zz*".to_string()],
+                ..Focus::default()
+            },
+            ..RenderOptions::default()
+        };
+
+        let messages: Vec<String> = check(&schema, &options)
+            .iter()
+            .map(|d| format!("{}: {}", d.severity, d.message))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "warning: `--include` pattern `Zz*` matches no entity",
+                "warning: `--focus`, `--include` and `--exclude` leave no entity",
+            ]
+        );
+        assert_eq!(layout(&schema, &options).unwrap().warnings.len(), 2);
+        assert!(check(&schema, &RenderOptions::default()).is_empty());
+    }
+}
@@ -1,14 +1,20 @@
 //! Serializer for converting AST to ERD notation string.
 
-use crate::ast::{
-    Cardinality, Column, ColumnModifier, Constraint, Entity, Relationship, Schema,
-};
+use crate::ast::{Column, ColumnModifier, Constraint, Entity, Enum, HintValue, Relationship, Schema};
 use std::collections::{HashMap, HashSet};
 
 /// Serialize a Schema to ERD notation string.
 pub fn serialize(schema: &Schema) -> String {
     let mut output = String::new();
 
+    // Serialize enums, which columns below may use as types
+    for e in &schema.enums {
+        serialize_enum(&mut output, e);
+    }
+    if !schema.enums.is_empty() && !schema.entities.is_empty() {
+        output.push('\n');
+    }
+
     // Serialize entities
     for (i, entity) in schema.entities.iter().enumerate() {
         if i > 0 {
@@ -33,7 +39,7 @@ pub fn serialize(schema: &Schema) -> String {
         for row in &arrangement {
             output.push_str("    ");
             output.push_str(&row.join(" "));
-            output.push_str("\n");
+            output.push('\n');
         }
         output.push_str("}\n");
     }
@@ -57,15 +63,22 @@ fn generate_arrangement(schema: &Schema) -> Vec<Vec<String>> {
     }
 
     for rel in &schema.relationships {
-        // A self-reference says nothing about levels, and treating it as a
-        // dependency would leave the table (and everything below it) unranked.
-        if rel.left == rel.right {
-            continue;
-        }
-        // rel.left is parent (1 side), rel.right is child (* side)
-        if entity_names.contains(rel.left.as_str()) && entity_names.contains(rel.right.as_str()) {
-            if let Some(deps) = parents.get_mut(rel.right.as_str()) {
-                deps.insert(rel.left.as_str());
+        // rel.left is parent (1 side), rel.right is child (* side); a
+        // many-to-many relationship puts its join table under both ends
+        let links = match &rel.via {
+            Some(join) => vec![(rel.left.as_str(), join.as_str()), (rel.right.as_str(), join.as_str())],
+            None => vec![(rel.left.as_str(), rel.right.as_str())],
+        };
+        for (parent, child) in links {
+            // A self-reference says nothing about levels, and treating it as a
+            // dependency would leave the table (and everything below it) unranked.
+            if parent == child {
+                continue;
+            }
+            if entity_names.contains(parent) && entity_names.contains(child)
+                && let Some(deps) = parents.get_mut(child)
+            {
+                deps.insert(parent);
             }
         }
     }
@@ -163,8 +176,34 @@ fn generate_arrangement(schema: &Schema) -> Vec<Vec<String>> {
     rows.into_iter().filter(|r| !r.is_empty()).collect()
 }
 
+fn serialize_enum(output: &mut String, e: &Enum) {
+    for line in e.doc.iter().flat_map(|doc| doc.lines()) {
+        output.push_str(format!("/// {}", line).trim_end());
+        output.push('\n');
+    }
+    let values: Vec<String> = e
+        .values
+        .iter()
+        .map(|v| if is_identifier(v) { v.clone() } else { quote(v) })
+        .collect();
+    output.push_str(&format!("enum {} {{ {} }}\n", e.name, values.join(", ")));
+}
+
 fn serialize_entity(output: &mut String, entity: &Entity) {
-    output.push_str(&format!("entity {} {{\n", entity.name));
+    output.push_str(&format!("entity {}", entity.name));
+    if let Some(extends) = &entity.extends {
+        output.push_str(&format!(" extends {}", extends.parent.name));
+        if let Some(disjointness) = extends.disjointness {
+            output.push_str(&format!(" {}", disjointness.as_str()));
+        }
+        if let Some(completeness) = extends.completeness {
+            output.push_str(&format!(" {}", completeness.as_str()));
+        }
+    }
+    if let Some(doc) = &entity.doc {
+        output.push_str(&format!(" {}", quote(doc)));
+    }
+    output.push_str(" {\n");
 
     // Collect PKs from constraints for composite key handling
     let composite_pk_columns: Vec<&str> = entity
@@ -190,12 +229,13 @@ fn serialize_entity(output: &mut String, entity: &Entity) {
 }
 
 fn serialize_column(output: &mut String, column: &Column, composite_pk_columns: &[&str]) {
+    serialize_doc(output, &column.doc);
     output.push_str(&format!("    {} {}", column.name, column.typ));
 
     // Check if this column is part of a composite PK (don't add pk modifier)
     let is_composite_pk_member = composite_pk_columns.contains(&column.name.as_str());
 
-    // Serialize modifiers in order: pk, unique, not null, fk, default
+    // Serialize modifiers in order: pk, unique, not null, fk, default, check
     let has_pk = column.modifiers.iter().any(|m| matches!(m, ColumnModifier::Pk));
     let has_unique = column.modifiers.iter().any(|m| matches!(m, ColumnModifier::Unique));
     let has_not_null = column.modifiers.iter().any(|m| matches!(m, ColumnModifier::NotNull));
@@ -223,15 +263,40 @@ fn serialize_column(output: &mut String, column: &Column, composite_pk_columns:
             if is_bare_default(val) {
                 output.push_str(&format!(" default {}", val));
             } else {
-                let escaped = val.replace('\\', "\\\\").replace('"', "\\\"");
-                output.push_str(&format!(" default \"{}\"", escaped));
+                output.push_str(&format!(" default {}", quote(val)));
             }
         }
     }
 
+    for modifier in &column.modifiers {
+        if let ColumnModifier::Check(condition) = modifier {
+            output.push_str(&format!(" check {}", quote(condition)));
+        }
+    }
+
     output.push('\n');
 }
 
+/// `/// text` lines for a column or relationship, indented like it.
+fn serialize_doc(output: &mut String, doc: &Option<String>) {
+    for line in doc.iter().flat_map(|doc| doc.lines()) {
+        if line.is_empty() {
+            output.push_str("    ///\n");
+        } else {
+            output.push_str(&format!("    /// {}\n", line));
+        }
+    }
+}
+
+/// `value` as an ERD string literal, escaped so the lexer reads it back.
+fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
 /// True when a default value can follow `default` unquoted, that is when the
 /// ERD lexer reads it back as a number, an identifier, or a call like `now()`.
 /// Anything else — `-1`, `'x'`, `n/a` — has to be a quoted string.
@@ -288,10 +353,15 @@ fn serialize_constraint(output: &mut String, constraint: &Constraint) {
             }
             output.push('\n');
         }
-        Constraint::Index { columns, name } => {
+        Constraint::Index { columns, name, unique } => {
             output.push_str(&format!("    index({})", columns.join(", ")));
-            if let Some(n) = name {
-                output.push_str(&format!(" name = {}", n));
+            let options: Vec<String> = unique
+                .then(|| "unique".to_string())
+                .into_iter()
+                .chain(name.iter().map(|n| format!("name = {}", n)))
+                .collect();
+            if !options.is_empty() {
+                output.push_str(&format!(" [{}]", options.join(", ")));
             }
             output.push('\n');
         }
@@ -299,38 +369,64 @@ fn serialize_constraint(output: &mut String, constraint: &Constraint) {
 }
 
 fn serialize_relationship(output: &mut String, rel: &Relationship) {
-    let left_card = serialize_cardinality(&rel.left_cardinality);
-    let right_card = serialize_cardinality(&rel.right_cardinality);
-
+    serialize_doc(output, &rel.doc);
+    let end = |entity: &str, column: &Option<String>| match column {
+        Some(column) => format!("{}.{}", entity, column),
+        None => entity.to_string(),
+    };
     output.push_str(&format!(
         "    {} {} -- {} {}",
-        rel.left, left_card, right_card, rel.right
+        end(&rel.left, &rel.left_column),
+        rel.left_cardinality,
+        rel.right_cardinality,
+        end(&rel.right, &rel.right_column)
     ));
 
+    if let Some(via) = &rel.via {
+        output.push_str(&format!(" via {}", via));
+    }
+
     if let Some(label) = &rel.label {
         output.push_str(&format!(" : \"{}\"", label));
     }
 
-    if let Some(role) = &rel.role {
-        output.push_str(&format!(" as {}", role));
+    match rel.identifying {
+        Some(true) => output.push_str(" identifying"),
+        Some(false) => output.push_str(" non_identifying"),
+        None => {}
     }
 
-    output.push('\n');
-}
+    match (&rel.left_role, &rel.right_role) {
+        (Some(left), None) => output.push_str(&format!(" as {}", left)),
+        (left, Some(right)) => output.push_str(&format!(" as {}, {}", left.as_deref().unwrap_or("_"), right)),
+        (None, None) => {}
+    }
 
-fn serialize_cardinality(card: &Cardinality) -> &'static str {
-    match card {
-        Cardinality::One => "1",
-        Cardinality::ZeroOrOne => "0..1",
-        Cardinality::Many => "*",
-        Cardinality::OneOrMore => "1..*",
+    if !rel.attributes.is_empty() {
+        let attributes: Vec<String> = rel
+            .attributes
+            .iter()
+            .map(|a| match &a.value {
+                Some(HintValue::Int(n)) => format!("{} = {}", a.name, n),
+                Some(HintValue::Str(s)) => format!("{} = \"{}\"", a.name, s),
+                Some(HintValue::Ident(s)) => format!("{} = {}", a.name, s),
+                None => a.name.clone(),
+            })
+            .collect();
+        output.push_str(&format!(" [{}]", attributes.join(", ")));
     }
+
+    output.push('\n');
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::{Cardinality, Column, ColumnModifier, Entity, Relationship, Schema};
+    use crate::ast::{
+        Attribute, Cardinality, Column, ColumnModifier, ColumnType, Disjointness, Entity, Enum, Extends, Name, Relationship,
+        Schema,
+    };
+    use crate::diagnostic::Span;
 
     #[test]
     fn test_serialize_simple_entity() {
@@ -340,27 +436,37 @@ mod tests {
                 columns: vec![
                     Column {
                         name: "id".to_string(),
-                        typ: "int".to_string(),
+                        typ: ColumnType::new("int"),
                         modifiers: vec![ColumnModifier::Pk],
+                        doc: None,
+                        span: Span::default(),
                     },
                     Column {
                         name: "email".to_string(),
-                        typ: "string".to_string(),
+                        typ: ColumnType::new("string"),
                         modifiers: vec![ColumnModifier::NotNull, ColumnModifier::Unique],
+                        doc: Some("Login name".to_string()),
+                        span: Span::default(),
                     },
                 ],
                 constraints: vec![],
                 hints: vec![],
+                doc: Some("Registered \"customer\"".to_string()),
+                extends: None,
+                span: Span::default(),
             }],
+            enums: vec![],
             relationships: vec![],
             views: vec![],
             arrangement: None,
+            hints: vec![],
+            imports: vec![],
         };
 
         let result = serialize(&schema);
-        assert!(result.contains("entity User {"));
+        assert!(result.contains("entity User \"Registered \\\"customer\\\"\" {"));
         assert!(result.contains("id int pk"));
-        assert!(result.contains("email string unique not null"));
+        assert!(result.contains("    /// Login name\n    email string unique not null"));
     }
 
     #[test]
@@ -371,12 +477,14 @@ mod tests {
                 columns: vec![
                     Column {
                         name: "id".to_string(),
-                        typ: "int".to_string(),
+                        typ: ColumnType::new("int"),
                         modifiers: vec![ColumnModifier::Pk],
+                        doc: None,
+                        span: Span::default(),
                     },
                     Column {
                         name: "user_id".to_string(),
-                        typ: "int".to_string(),
+                        typ: ColumnType::new("int"),
                         modifiers: vec![
                             ColumnModifier::NotNull,
                             ColumnModifier::Fk {
@@ -384,14 +492,22 @@ mod tests {
                                 column: "id".to_string(),
                             },
                         ],
+                        doc: None,
+                        span: Span::default(),
                     },
                 ],
                 constraints: vec![],
                 hints: vec![],
+                doc: None,
+                extends: None,
+                span: Span::default(),
             }],
+            enums: vec![],
             relationships: vec![],
             views: vec![],
             arrangement: None,
+            hints: vec![],
+            imports: vec![],
         };
 
         let result = serialize(&schema);
@@ -402,20 +518,122 @@ mod tests {
     fn test_serialize_relationship() {
         let schema = Schema {
             entities: vec![],
+            enums: vec![],
             relationships: vec![Relationship {
                 left: "User".to_string(),
-                left_cardinality: Cardinality::One,
+                left_column: None,
+                left_cardinality: Cardinality::ONE,
                 right: "Order".to_string(),
-                right_cardinality: Cardinality::Many,
+                right_column: Some("user_id".to_string()),
+                right_cardinality: Cardinality::MANY,
+                via: None,
                 label: Some("places".to_string()),
-                role: None,
+                left_role: None,
+                right_role: None,
+                identifying: Some(true),
+                attributes: vec![],
+                doc: Some("Every order has a buyer".to_string()),
+                span: Span::default(),
+            }, Relationship {
+                left: "Order".to_string(),
+                left_column: None,
+                left_cardinality: Cardinality::MANY,
+                right: "Tag".to_string(),
+                right_column: None,
+                right_cardinality: Cardinality::MANY,
+                via: Some("OrderTag".to_string()),
+                label: None,
+                left_role: None,
+                right_role: Some("labels".to_string()),
+                identifying: None,
+                attributes: vec![
+                    Attribute {
+                        name: "dashed".to_string(),
+                        value: None,
+                        span: Span::default(),
+                    },
+                    Attribute {
+                        name: "color".to_string(),
+                        value: Some(HintValue::Str("#c33".to_string())),
+                        span: Span::default(),
+                    },
+                ],
+                doc: None,
+                span: Span::default(),
             }],
             views: vec![],
             arrangement: None,
+            hints: vec![],
+            imports: vec![],
         };
 
         let result = serialize(&schema);
         assert!(result.contains("rel {"));
-        assert!(result.contains("User 1 -- * Order : \"places\""));
+        assert!(result.contains("    /// Every order has a buyer\n    User 1 -- * Order.user_id : \"places\" identifying\n"));
+        assert!(result.contains("    Order * -- * Tag via OrderTag as _, labels [dashed, color = \"#c33\"]\n"));
+    }
+
+    #[test]
+    fn test_serialize_extends() {
+        let schema = Schema {
+            entities: vec![Entity {
+                name: "Person".to_string(),
+                columns: vec![],
+                constraints: vec![],
+                hints: vec![],
+                doc: Some("A human".to_string()),
+                extends: Some(Extends {
+                    parent: Name {
+                        name: "Party".to_string(),
+                        span: Span::default(),
+                    },
+                    disjointness: Some(Disjointness::Disjoint),
+                    completeness: None,
+                }),
+                span: Span::default(),
+            }],
+            enums: vec![],
+            relationships: vec![],
+            views: vec![],
+            arrangement: None,
+            hints: vec![],
+            imports: vec![],
+        };
+
+        let result = serialize(&schema);
+        assert!(result.starts_with("entity Person extends Party disjoint \"A human\" {\n"));
+    }
+
+    #[test]
+    fn test_serialize_enums() {
+        let schema = Schema {
+            entities: vec![],
+            enums: vec![Enum {
+                name: "Status".to_string(),
+                values: vec!["pending".to_string(), "in progress".to_string()],
+                doc: Some("Where an order is".to_string()),
+                span: Span::default(),
+            }],
+            relationships: vec![],
+            views: vec![],
+            arrangement: None,
+            hints: vec![],
+            imports: vec![],
+        };
+
+        let result = serialize(&schema);
+        assert_eq!(result, "/// Where an order is\nenum Status { pending, \"in progress\" }\n");
+    }
+    #[test]
+    fn test_serialize_table_constraints() {
+        let input = "entity Order {\n    user_id int\n    \
+                     foreign_key(user_id) references User(id) on delete set null\n    \
+                     index(user_id) [unique, name = ix_order_user]\n}\n";
+        let schema = crate::parser::Parser::new(input).unwrap().parse().unwrap();
+
+        let result = serialize(&schema);
+        assert!(result.contains("    index(user_id) [unique, name = ix_order_user]\n"), "{result}");
+        let reparsed = crate::parser::Parser::new(&result).unwrap().parse().unwrap();
+        assert_eq!(reparsed.entities[0].constraints, schema.entities[0].constraints);
     }
 }
@@ -16,6 +16,7 @@ pub enum Dialect {
 
 impl Dialect {
     /// Parse dialect from string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "auto" => Some(Self::Auto),
@@ -4,8 +4,10 @@ use super::dialect::Dialect;
 use super::lexer::{Lexer, Token};
 use super::types::map_type;
 use crate::ast::{
-    Cardinality, Column, ColumnModifier, Constraint, Entity, Relationship, Schema,
+    Cardinality, Column, ColumnModifier, ColumnType, Constraint, Entity, Enum, Extends, Name,
+    Relationship, Schema,
 };
+use crate::diagnostic::Span;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -31,6 +33,9 @@ struct Parser {
     tokens: Vec<Token>,
     pos: usize,
     dialect: Dialect,
+    /// Enum types from `CREATE TYPE ... AS ENUM` and MySQL's inline `ENUM(...)`
+    /// columns, in the order they were met.
+    enums: Vec<Enum>,
 }
 
 impl Parser {
@@ -39,6 +44,7 @@ impl Parser {
             tokens,
             pos: 0,
             dialect,
+            enums: Vec::new(),
         }
     }
 
@@ -55,6 +61,7 @@ impl Parser {
     fn parse(&mut self) -> Result<Schema, SqlParseError> {
         let mut entities = Vec::new();
         let mut fk_constraints: Vec<(String, FkInfo)> = Vec::new();
+        let mut comments: Vec<CommentOn> = Vec::new();
 
         while self.current() != &Token::Eof {
             match self.current() {
@@ -87,6 +94,11 @@ impl Parser {
                                 fk_constraints.push((table_name.clone(), fk));
                             }
                         }
+                    } else if matches!(self.current(), Token::Ident(word) if word.eq_ignore_ascii_case("TYPE")) {
+                        // CREATE TYPE name AS ENUM (...)
+                        if let Some(e) = self.parse_create_type() {
+                            self.enums.push(e);
+                        }
                     } else {
                         // Skip other CREATE statements (INDEX, VIEW, etc.)
                         self.skip_statement();
@@ -98,20 +110,51 @@ impl Parser {
                         fk_constraints.push((table_name, fk));
                     }
                 }
+                Token::Ident(word) if word.eq_ignore_ascii_case("COMMENT") => {
+                    // COMMENT ON TABLE/COLUMN ... IS '...'
+                    comments.extend(self.parse_comment_on());
+                }
                 _ => {
                     self.advance();
                 }
             }
         }
 
+        // Comments may come before or after the table they describe
+        for comment in comments {
+            let Some(entity) = entities.iter_mut().find(|e| e.name == comment.table) else {
+                continue;
+            };
+            match comment.column {
+                Some(column) => {
+                    if let Some(col) = entity.columns.iter_mut().find(|c| c.name == column) {
+                        col.doc = comment.text;
+                    }
+                }
+                None => entity.doc = comment.text,
+            }
+        }
+
+        // PostgreSQL folds unquoted type names to lower case, and so does
+        // map_type; give columns back the name their type was declared with.
+        let enums = std::mem::take(&mut self.enums);
+        for col in entities.iter_mut().flat_map(|e| e.columns.iter_mut()) {
+            if let Some(e) = enums.iter().find(|e| e.name.eq_ignore_ascii_case(&col.typ.name)) {
+                col.typ.name = e.name.clone();
+            }
+        }
+
         // Generate relationships from FK constraints
         let relationships = self.generate_relationships(&entities, &fk_constraints);
 
         Ok(Schema {
             entities,
+            enums,
             relationships,
             views: vec![],
             arrangement: None,
+            hints: vec![],
+            imports: vec![],
         })
     }
 
@@ -217,7 +260,7 @@ impl Parser {
                 }
                 Token::Ident(_) => {
                     // Column definition
-                    if let Some(col) = self.parse_column()? {
+                    if let Some(col) = self.parse_column(&table_name)? {
                         columns.push(col);
                     }
                 }
@@ -228,15 +271,16 @@ impl Parser {
             }
         }
 
-        // Skip table options (ENGINE=, etc.)
-        self.skip_statement();
+        // Table options (ENGINE=, etc.), of which only COMMENT= and
+        // INHERITS are kept
+        let options = self.parse_table_options();
 
         // Apply PK modifier to columns
         for col in &mut columns {
-            if pk_columns.contains(&col.name) {
-                if !col.modifiers.iter().any(|m| matches!(m, ColumnModifier::Pk)) {
-                    col.modifiers.insert(0, ColumnModifier::Pk);
-                }
+            if pk_columns.contains(&col.name)
+                && !col.modifiers.iter().any(|m| matches!(m, ColumnModifier::Pk))
+            {
+                col.modifiers.insert(0, ColumnModifier::Pk);
             }
         }
 
@@ -246,12 +290,70 @@ impl Parser {
                 columns,
                 constraints,
                 hints: vec![],
+                doc: options.comment,
+                extends: options.inherits.map(|parent| Extends {
+                    parent: Name {
+                        name: parent,
+                        span: Span::default(),
+                    },
+                    disjointness: None,
+                    completeness: None,
+                }),
+                span: Span::default(),
             },
             fk_infos,
         )))
     }
 
-    fn parse_column(&mut self) -> Result<Option<Column>, SqlParseError> {
+    /// Parse `CREATE TYPE name AS ENUM ('a', 'b')`, the name optionally
+    /// qualified by a schema. Any other kind of type is skipped.
+    fn parse_create_type(&mut self) -> Option<Enum> {
+        self.advance(); // TYPE
+        let mut name = None;
+        while let Token::Ident(part) = self.current() {
+            name = Some(part.clone());
+            self.advance();
+            if self.current() != &Token::Dot {
+                break;
+            }
+            self.advance();
+        }
+
+        let is_word = |token: &Token, word: &str| {
+            matches!(token, Token::Ident(w) if w.eq_ignore_ascii_case(word))
+        };
+        if !is_word(self.current(), "AS") {
+            self.skip_statement();
+            return None;
+        }
+        self.advance();
+        if !is_word(self.current(), "ENUM") {
+            self.skip_statement();
+            return None;
+        }
+        self.advance();
+
+        let mut values = Vec::new();
+        if self.current() == &Token::LParen {
+            self.advance();
+            while !matches!(self.current(), Token::RParen | Token::Eof) {
+                if let Token::Str(value) = self.current() {
+                    values.push(value.clone());
+                }
+                self.advance();
+            }
+        }
+        self.skip_statement();
+
+        Some(Enum {
+            name: name?,
+            values,
+            doc: None,
+            span: Span::default(),
+        })
+    }
+
+    fn parse_column(&mut self, table_name: &str) -> Result<Option<Column>, SqlParseError> {
         let name = match self.current() {
             Token::Ident(n) => n.clone(),
             _ => return Ok(None),
@@ -261,9 +363,18 @@ impl Parser {
         // Type
         let mut type_parts = Vec::new();
         let mut paren_depth = 0;
+        // The values of MySQL's ENUM('a', 'b')
+        let mut values = Vec::new();
 
         loop {
             match self.current() {
+                // The lexer reads `[]` as an empty bracketed identifier.
+                Token::Ident(t) if t.is_empty() && paren_depth == 0 => {
+                    type_parts.push("[]".to_string());
+                    self.advance();
+                }
+                // MySQL's COMMENT 'text' may follow a bare type
+                Token::Ident(t) if t.eq_ignore_ascii_case("COMMENT") && paren_depth == 0 => break,
                 Token::Ident(t) => {
                     type_parts.push(t.clone());
                     self.advance();
@@ -286,6 +397,10 @@ impl Parser {
                     type_parts.push(n.clone());
                     self.advance();
                 }
+                Token::Str(value) if paren_depth > 0 => {
+                    values.push(value.clone());
+                    self.advance();
+                }
                 Token::Comma if paren_depth > 0 => {
                     type_parts.push(",".to_string());
                     self.advance();
@@ -298,12 +413,35 @@ impl Parser {
             return Ok(None);
         }
 
-        let raw_type = type_parts.join("");
-        let typ = map_type(&raw_type, self.dialect);
+        // Words of a multi-word type such as `double precision` keep their
+        // space; everything else is joined as written.
+        let mut raw_type = String::new();
+        for part in &type_parts {
+            let is_word = part.starts_with(|c: char| c.is_alphabetic());
+            if is_word && raw_type.ends_with(|c: char| c.is_alphanumeric()) {
+                raw_type.push(' ');
+            }
+            raw_type.push_str(part);
+        }
+        let mut typ = map_type(&raw_type, self.dialect);
+
+        // An inline enum becomes a declaration of its own, named after the
+        // column it belongs to.
+        if typ.name == "enum" && !values.is_empty() {
+            let enum_name = format!("{}_{}", table_name, name);
+            typ = ColumnType::new(enum_name.clone());
+            self.enums.push(Enum {
+                name: enum_name,
+                values,
+                doc: None,
+                span: Span::default(),
+            });
+        }
 
         // Parse modifiers
         let mut modifiers = Vec::new();
         let mut is_pk = false;
+        let mut doc = None;
 
         loop {
             match self.current() {
@@ -371,6 +509,14 @@ impl Parser {
                     // ON DELETE/UPDATE for inline FK
                     self.skip_on_actions();
                 }
+                Token::Ident(word) if word.eq_ignore_ascii_case("COMMENT") => {
+                    // MySQL: COMMENT 'text'
+                    self.advance();
+                    if let Token::Str(text) = self.current() {
+                        doc = Some(text.clone());
+                        self.advance();
+                    }
+                }
                 _ => {
                     self.advance();
                 }
@@ -385,9 +531,105 @@ impl Parser {
             name,
             typ,
             modifiers,
+            doc,
+            span: Span::default(),
         }))
     }
 
+    /// Skip the options after a table's closing parenthesis, up to and
+    /// including the `;`, keeping MySQL's `COMMENT='...'` and the first
+    /// table of PostgreSQL's `INHERITS (...)`.
+    fn parse_table_options(&mut self) -> TableOptions {
+        let mut options = TableOptions::default();
+        while !matches!(self.current(), Token::Semicolon | Token::Eof) {
+            match self.current() {
+                Token::Ident(word) if word.eq_ignore_ascii_case("COMMENT") => {
+                    self.advance();
+                    // The lexer drops the `=`
+                    if let Token::Str(text) = self.current() {
+                        options.comment = Some(text.clone());
+                    }
+                }
+                Token::Ident(word) if word.eq_ignore_ascii_case("INHERITS") => {
+                    self.advance();
+                    if self.current() != &Token::LParen {
+                        continue;
+                    }
+                    self.advance();
+                    // The last part of a schema-qualified name
+                    while let Token::Ident(part) = self.current() {
+                        options.inherits = Some(part.clone());
+                        self.advance();
+                        if self.current() != &Token::Dot {
+                            break;
+                        }
+                        self.advance();
+                    }
+                    self.skip_until(&[Token::RParen, Token::Semicolon]);
+                    continue;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+        if self.current() == &Token::Semicolon {
+            self.advance();
+        }
+        options
+    }
+
+    /// Parse `COMMENT ON TABLE t IS '...'` or `COMMENT ON COLUMN t.c IS
+    /// '...'`, either name optionally qualified by a schema. `IS NULL` drops
+    /// the comment. Comments on anything else are skipped.
+    fn parse_comment_on(&mut self) -> Option<CommentOn> {
+        self.advance(); // COMMENT
+        if self.current() != &Token::On {
+            self.skip_statement();
+            return None;
+        }
+        self.advance(); // ON
+
+        let on_column = match self.current() {
+            Token::Table => false,
+            Token::Ident(word) if word.eq_ignore_ascii_case("COLUMN") => true,
+            _ => {
+                self.skip_statement();
+                return None;
+            }
+        };
+        self.advance();
+
+        let mut path = Vec::new();
+        while let Token::Ident(part) = self.current() {
+            path.push(part.clone());
+            self.advance();
+            if self.current() != &Token::Dot {
+                break;
+            }
+            self.advance();
+        }
+
+        let is = matches!(self.current(), Token::Ident(word) if word.eq_ignore_ascii_case("IS"));
+        if !is {
+            self.skip_statement();
+            return None;
+        }
+        self.advance(); // IS
+        let text = match self.current() {
+            Token::Str(text) => Some(text.clone()),
+            _ => None,
+        };
+        self.skip_statement();
+
+        let (table, column) = if on_column {
+            let column = path.pop()?;
+            (path.pop()?, Some(column))
+        } else {
+            (path.pop()?, None)
+        };
+        Some(CommentOn { table, column, text })
+    }
+
     fn parse_default_value(&mut self) -> Result<String, SqlParseError> {
         match self.current() {
             Token::Str(s) => {
@@ -598,10 +840,10 @@ impl Parser {
                 }
                 Token::Ident(s) if s.to_uppercase() == "NO" => {
                     self.advance();
-                    if let Token::Ident(a) = self.current() {
-                        if a.to_uppercase() == "ACTION" {
-                            self.advance();
-                        }
+                    if let Token::Ident(a) = self.current()
+                        && a.to_uppercase() == "ACTION"
+                    {
+                        self.advance();
                     }
                 }
                 _ => {}
@@ -675,11 +917,19 @@ impl Parser {
 
             relationships.push(Relationship {
                 left: fk.target.clone(),
-                left_cardinality: Cardinality::One,
+                left_column: None,
+                left_cardinality: Cardinality::ONE,
                 right: source_table.clone(),
-                right_cardinality: Cardinality::Many,
+                right_column: None,
+                right_cardinality: Cardinality::MANY,
+                via: None,
                 label: None,
-                role: None,
+                left_role: None,
+                right_role: None,
+                identifying: None,
+                attributes: vec![],
+                doc: None,
+                span: Span::default(),
             });
         }
 
@@ -687,28 +937,73 @@ impl Parser {
         for entity in entities {
             for col in &entity.columns {
                 for modifier in &col.modifiers {
-                    if let ColumnModifier::Fk { target, .. } = modifier {
-                        if entity_names.contains(&target.as_str()) {
-                            // Avoid duplicates
-                            let exists = relationships.iter().any(|r| {
-                                r.left == *target && r.right == entity.name
+                    if let ColumnModifier::Fk { target, .. } = modifier
+                        && entity_names.contains(&target.as_str())
+                    {
+                        // Avoid duplicates
+                        let exists = relationships.iter().any(|r| {
+                            r.left == *target && r.right == entity.name
+                        });
+                        if !exists {
+                            relationships.push(Relationship {
+                                left: target.clone(),
+                                left_column: None,
+                                left_cardinality: Cardinality::ONE,
+                                right: entity.name.clone(),
+                                right_column: None,
+                                right_cardinality: Cardinality::MANY,
+                                via: None,
+                                label: None,
+                                left_role: None,
+                                right_role: None,
+                                identifying: None,
+                                attributes: vec![],
+                                doc: None,
+                                span: Span::default(),
                             });
-                            if !exists {
-                                relationships.push(Relationship {
-                                    left: target.clone(),
-                                    left_cardinality: Cardinality::One,
-                                    right: entity.name.clone(),
-                                    right_cardinality: Cardinality::Many,
-                                    label: None,
-                                    role: None,
-                                });
-                            }
                         }
                     }
                 }
             }
         }
 
+        // A pure join table, whose primary key is made of two foreign keys
+        // and which nothing references, becomes `A * -- * B via AB`.
+        for entity in entities {
+            let Some((a, b)) = join_targets(entity, fk_constraints) else {
+                continue;
+            };
+            if !entity_names.contains(&a.as_str())
+                || !entity_names.contains(&b.as_str())
+                || relationships.iter().any(|r| r.left == entity.name || r.via.as_ref() == Some(&entity.name))
+            {
+                continue;
+            }
+            let Some(at) = relationships.iter().position(|r| r.right == entity.name) else {
+                continue;
+            };
+            relationships.retain(|r| r.right != entity.name);
+            relationships.insert(
+                at,
+                Relationship {
+                    left: a,
+                    left_column: None,
+                    left_cardinality: Cardinality::MANY,
+                    right: b,
+                    right_column: None,
+                    right_cardinality: Cardinality::MANY,
+                    via: Some(entity.name.clone()),
+                    label: None,
+                    left_role: None,
+                    right_role: None,
+                    identifying: None,
+                    attributes: vec![],
+                    doc: None,
+                    span: Span::default(),
+                },
+            );
+        }
+
         relationships
     }
 
@@ -789,14 +1084,68 @@ impl Parser {
     }
 }
 
+/// What the options after a table's columns say about it.
+#[derive(Default)]
+struct TableOptions {
+    comment: Option<String>,
+    inherits: Option<String>,
+}
+
+/// `COMMENT ON` a table, or on one of its columns.
+struct CommentOn {
+    table: String,
+    column: Option<String>,
+    text: Option<String>,
+}
+
 struct FkInfo {
-    #[allow(dead_code)]
     columns: Vec<String>,
     target: String,
     #[allow(dead_code)]
     target_column: String,
 }
 
+/// The tables a join table links, when its primary key is exactly the
+/// columns of its two foreign keys.
+fn join_targets(entity: &Entity, fk_constraints: &[(String, FkInfo)]) -> Option<(String, String)> {
+    let mut keys: Vec<(Vec<&str>, &str)> = fk_constraints
+        .iter()
+        .filter(|(table, _)| *table == entity.name)
+        .map(|(_, fk)| (fk.columns.iter().map(String::as_str).collect(), fk.target.as_str()))
+        .collect();
+    for col in &entity.columns {
+        for modifier in &col.modifiers {
+            if let ColumnModifier::Fk { target, .. } = modifier {
+                let key = (vec![col.name.as_str()], target.as_str());
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+    }
+    // In the order of their columns, whichever way they were declared
+    keys.sort_by_key(|(columns, _)| {
+        columns
+            .first()
+            .and_then(|first| entity.columns.iter().position(|c| c.name == *first))
+    });
+    let [(a_columns, a), (b_columns, b)] = keys.as_slice() else {
+        return None;
+    };
+
+    let mut key_columns: Vec<&str> = a_columns.iter().chain(b_columns).copied().collect();
+    key_columns.sort_unstable();
+    key_columns.dedup();
+    let mut pk: Vec<&str> = entity
+        .columns
+        .iter()
+        .filter(|c| c.modifiers.iter().any(|m| matches!(m, ColumnModifier::Pk)))
+        .map(|c| c.name.as_str())
+        .collect();
+    pk.sort_unstable();
+    (key_columns == pk).then(|| (a.to_string(), b.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -865,7 +1214,113 @@ mod tests {
         let schema = parse_sql(sql, Dialect::PostgreSQL).unwrap();
         let user = &schema.entities[0];
 
-        assert_eq!(user.columns[0].typ, "int");
+        assert_eq!(user.columns[0].typ.to_string(), "int");
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let sql = r#"
+            CREATE TABLE users (
+                id INT PRIMARY KEY,
+                email VARCHAR(255) NOT NULL COMMENT 'Login name',
+                age INT COMMENT 'In years',
+                comment TEXT
+            ) ENGINE=InnoDB COMMENT='Registered customers';
+            CREATE TABLE orders (id INT PRIMARY KEY, total INT);
+            COMMENT ON TABLE public.orders IS 'Placed orders';
+            COMMENT ON COLUMN public.orders.total IS 'In cents';
+        "#;
+
+        let schema = parse_sql(sql, Dialect::Generic).unwrap();
+        let users = &schema.entities[0];
+        assert_eq!(users.doc.as_deref(), Some("Registered customers"));
+        assert_eq!(users.columns[1].doc.as_deref(), Some("Login name"));
+        assert_eq!(users.columns[2].typ.to_string(), "int");
+        assert_eq!(users.columns[2].doc.as_deref(), Some("In years"));
+        assert_eq!(users.columns[3].name, "comment");
+        assert_eq!(users.columns[3].doc, None);
+
+        let orders = &schema.entities[1];
+        assert_eq!(orders.doc.as_deref(), Some("Placed orders"));
+        assert_eq!(orders.columns[1].doc.as_deref(), Some("In cents"));
+    }
+
+    #[test]
+    fn test_parse_enums() {
+        let sql = r#"
+            CREATE TYPE public.Mood AS ENUM ('sad', 'ok', 'happy');
+            CREATE TYPE point AS (x INT, y INT);
+            CREATE TABLE people (
+                id INT PRIMARY KEY,
+                mood mood NOT NULL
+            );
+            CREATE TABLE orders (
+                id INT PRIMARY KEY,
+                status ENUM('pending', 'paid') NOT NULL DEFAULT 'pending',
+                total INT
+            );
+        "#;
+
+        let schema = parse_sql(sql, Dialect::PostgreSQL).unwrap();
+        let names: Vec<&str> = schema.enums.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Mood", "orders_status"]);
+        assert_eq!(schema.enums[0].values, vec!["sad", "ok", "happy"]);
+        assert_eq!(schema.enums[1].values, vec!["pending", "paid"]);
+
+        assert_eq!(schema.entities[0].columns[1].typ.to_string(), "Mood");
+        let orders = &schema.entities[1];
+        assert_eq!(orders.columns.len(), 3);
+        assert_eq!(orders.columns[1].typ.to_string(), "orders_status");
+        assert_eq!(orders.columns[2].name, "total");
+    }
+
+    #[test]
+    fn test_parse_inherits() {
+        let sql = r#"
+            CREATE TABLE users (id INT PRIMARY KEY, email TEXT);
+            CREATE TABLE admins (level INT) INHERITS (public.users, audited);
+        "#;
+
+        let schema = parse_sql(sql, Dialect::PostgreSQL).unwrap();
+        assert!(schema.entities[0].extends.is_none());
+        let extends = schema.entities[1].extends.as_ref().unwrap();
+        assert_eq!(extends.parent, "users");
+        assert_eq!(extends.disjointness, None);
+    }
+
+    #[test]
+    fn test_parse_join_tables() {
+        let sql = r#"
+            CREATE TABLE posts (id INT PRIMARY KEY);
+            CREATE TABLE tags (id INT PRIMARY KEY);
+            CREATE TABLE post_tags (
+                post_id INT REFERENCES posts(id),
+                tag_id INT,
+                PRIMARY KEY (post_id, tag_id),
+                FOREIGN KEY (tag_id) REFERENCES tags(id)
+            );
+            CREATE TABLE comments (
+                id INT PRIMARY KEY,
+                post_id INT REFERENCES posts(id),
+                tag_id INT REFERENCES tags(id)
+            );
+        "#;
+
+        let schema = parse_sql(sql, Dialect::PostgreSQL).unwrap();
+        let rels: Vec<String> = schema
+            .relationships
+            .iter()
+            .map(|r| format!("{} {} -- {} {} {:?}", r.left, r.left_cardinality, r.right_cardinality, r.right, r.via))
+            .collect();
+        // comments has its own key, so it is an entity in its own right.
+        assert_eq!(
+            rels,
+            vec![
+                "posts * -- * tags Some(\"post_tags\")",
+                "posts 1 -- * comments None",
+                "tags 1 -- * comments None",
+            ]
+        );
     }
 
     #[test]
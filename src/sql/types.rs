@@ -1,19 +1,46 @@
 //! SQL to ERD type mapping.
 
 use super::Dialect;
+use crate::ast::ColumnType;
 
-/// Map SQL type to ERD type.
-pub fn map_type(sql_type: &str, dialect: Dialect) -> String {
+/// Map SQL type to ERD type, keeping its parameters and array suffixes.
+pub fn map_type(sql_type: &str, dialect: Dialect) -> ColumnType {
     let lower = sql_type.to_lowercase();
-    let base = lower.split('(').next().unwrap_or(&lower).trim();
+    let base = lower.split(['(', '[']).next().unwrap_or(&lower).trim();
 
-    match dialect {
+    let name = match dialect {
         Dialect::PostgreSQL => map_postgres_type(base),
         Dialect::MySQL => map_mysql_type(base, &lower),
         _ => map_generic_type(base),
+    };
+
+    // An integer's parameter is a display width, not part of the data model.
+    let args = match name.as_str() {
+        "int" | "bigint" | "smallint" | "mediumint" | "tinyint" | "boolean" | "enum" => Vec::new(),
+        _ => type_args(&lower),
+    };
+
+    ColumnType {
+        // An unmapped multi-word type must still read back as one identifier.
+        name: name.replace(' ', "_"),
+        args,
+        array_dims: lower.matches('[').count(),
     }
 }
 
+/// The comma-separated parameters between the first pair of parentheses.
+fn type_args(sql_type: &str) -> Vec<String> {
+    let Some((_, rest)) = sql_type.split_once('(') else {
+        return Vec::new();
+    };
+    let inner = rest.split(')').next().unwrap_or(rest);
+    inner
+        .split(',')
+        .map(|arg| arg.trim().to_string())
+        .filter(|arg| !arg.is_empty())
+        .collect()
+}
+
 fn map_postgres_type(base: &str) -> String {
     match base {
         // Integer types
@@ -50,12 +77,6 @@ fn map_postgres_type(base: &str) -> String {
         // JSON
         "json" | "jsonb" => "json".to_string(),
 
-        // Arrays (strip array notation)
-        t if t.ends_with("[]") => {
-            let inner = &t[..t.len() - 2];
-            format!("{}[]", map_postgres_type(inner))
-        }
-
         // Default: keep original
         _ => base.to_string(),
     }
@@ -134,17 +155,31 @@ mod tests {
 
     #[test]
     fn test_postgres_types() {
-        assert_eq!(map_type("SERIAL", Dialect::PostgreSQL), "int");
-        assert_eq!(map_type("VARCHAR(255)", Dialect::PostgreSQL), "varchar");
-        assert_eq!(map_type("TIMESTAMPTZ", Dialect::PostgreSQL), "timestamp");
-        assert_eq!(map_type("JSONB", Dialect::PostgreSQL), "json");
+        assert_eq!(map_type("SERIAL", Dialect::PostgreSQL).to_string(), "int");
+        assert_eq!(map_type("VARCHAR(255)", Dialect::PostgreSQL).to_string(), "varchar(255)");
+        assert_eq!(map_type("TIMESTAMPTZ", Dialect::PostgreSQL).to_string(), "timestamp");
+        assert_eq!(map_type("JSONB", Dialect::PostgreSQL).to_string(), "json");
     }
 
     #[test]
     fn test_mysql_types() {
-        assert_eq!(map_type("INT", Dialect::MySQL), "int");
-        assert_eq!(map_type("TINYINT(1)", Dialect::MySQL), "boolean");
-        assert_eq!(map_type("TINYINT(4)", Dialect::MySQL), "tinyint");
-        assert_eq!(map_type("DATETIME", Dialect::MySQL), "timestamp");
+        assert_eq!(map_type("INT", Dialect::MySQL).to_string(), "int");
+        assert_eq!(map_type("TINYINT(1)", Dialect::MySQL).to_string(), "boolean");
+        assert_eq!(map_type("TINYINT(4)", Dialect::MySQL).to_string(), "tinyint");
+        assert_eq!(map_type("DATETIME", Dialect::MySQL).to_string(), "timestamp");
+        assert_eq!(map_type("INT(11)", Dialect::MySQL).to_string(), "int");
+    }
+
+    #[test]
+    fn test_type_parameters() {
+        let decimal = map_type("NUMERIC(10,2)", Dialect::PostgreSQL);
+        assert_eq!(decimal.name, "decimal");
+        assert_eq!(decimal.args, vec!["10", "2"]);
+
+        assert_eq!(map_type("text[]", Dialect::PostgreSQL).to_string(), "text[]");
+        assert_eq!(map_type("integer[][]", Dialect::PostgreSQL).to_string(), "int[][]");
+        assert_eq!(map_type("character varying(40)", Dialect::PostgreSQL).to_string(), "varchar(40)");
+        assert_eq!(map_type("VARBINARY(16)", Dialect::MySQL).to_string(), "binary(16)");
+        assert_eq!(map_type("bit varying(8)", Dialect::PostgreSQL).to_string(), "bit_varying(8)");
     }
 }
@@ -1,6 +1,6 @@
-use crate::ast::Cardinality;
-use crate::ir::{Edge, GraphIR};
-use crate::layout::{Layout, LayoutEdge, LayoutNode};
+use crate::ast::{Cardinality, Completeness};
+use crate::ir::{Edge, EdgeKind, GraphIR, NodeKind, Style};
+use crate::layout::{Layout, LayoutEdge, LayoutGroup, LayoutNode, LayoutNote};
 use crate::measure::TextMetrics;
 use std::collections::HashMap;
 use std::fmt::Write;
@@ -17,6 +17,7 @@ pub enum Notation {
 }
 
 impl Notation {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "crowsfoot" | "crows-foot" | "ie" => Some(Self::CrowsFoot),
@@ -26,10 +27,33 @@ impl Notation {
     }
 }
 
+/// How a supertype is linked to its subtypes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Subtypes {
+    /// A hollow triangle at the supertype, as in UML.
+    #[default]
+    Uml,
+    /// An IDEF1X category symbol: a circle near the supertype, underlined
+    /// twice when the subtypes are total and once otherwise.
+    Idef1x,
+}
+
+impl Subtypes {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "uml" => Some(Self::Uml),
+            "idef1x" => Some(Self::Idef1x),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct SvgRenderer {
     metrics: TextMetrics,
     notation: Notation,
+    subtypes: Subtypes,
 }
 
 impl SvgRenderer {
@@ -40,6 +64,10 @@ impl SvgRenderer {
         }
     }
 
+    pub fn with_subtypes(self, subtypes: Subtypes) -> Self {
+        Self { subtypes, ..self }
+    }
+
     pub fn render(&self, ir: &GraphIR, layout: &Layout) -> String {
         let mut svg = String::new();
 
@@ -63,13 +91,39 @@ impl SvgRenderer {
   .column-text {{ font-family: monospace; font-size: 12px; fill: #222; }}
   .pk {{ font-weight: bold; }}
   .fk {{ font-style: italic; }}
+  .column-extra {{ fill: #666; font-style: normal; font-weight: normal; }}
+  .index-text {{ font-family: monospace; font-size: 12px; fill: #555; }}
   .edge {{ stroke: #666; stroke-width: 1.5; fill: none; }}
+  .enum-header {{ fill: #d6e4f5; }}
+  .ghost .entity-bg, .ghost .entity-header {{ fill: #f6f6f6; }}
+  .ghost .entity-border {{ stroke: #aaa; stroke-width: 1; stroke-dasharray: 5 3; }}
+  .ghost .entity-name {{ fill: #999; font-weight: normal; }}
+  .ghost .edge, .ghost .edge-symbol, .ghost .edge-symbol-zero {{ stroke: #ccc; }}
+  .ghost .edge-label, .ghost .cardinality, .ghost .role {{ fill: #aaa; }}
+  .enum-link {{ stroke-width: 1; stroke-dasharray: 5 4; }}
+  .non-identifying {{ stroke-dasharray: 7 4; }}
+  .weak {{ stroke-width: 1; }}
+  .generalization {{ stroke: #666; stroke-width: 1.5; fill: #fff; }}
+  .inherited {{ fill-opacity: 0.55; }}
   .edge-symbol {{ stroke: #666; stroke-width: 1.5; fill: none; }}
   .edge-symbol-zero {{ stroke: #666; stroke-width: 1.5; fill: #fff; }}
   .edge-label-bg {{ fill: rgba(234,234,234,0.9); }}
   .edge-label {{ font-family: monospace; font-size: 14px; fill: #444; }}
+  .role-bg {{ fill: rgba(255,255,255,0.85); }}
+  .role {{ font-family: monospace; font-size: 12px; font-style: italic; fill: #555; }}
   .cardinality-bg {{ fill: rgba(224,224,224,0.95); }}
   .cardinality {{ font-family: monospace; font-size: 15px; font-weight: bold; fill: #222; }}
+  .group {{ fill-opacity: 0.12; stroke-opacity: 0.6; stroke-width: 1.5; }}
+  .group-label {{ font-family: monospace; font-size: 12px; font-weight: bold; fill: #555; }}
+  .group-0 {{ fill: #4a90d9; stroke: #4a90d9; }}
+  .group-1 {{ fill: #5cb85c; stroke: #5cb85c; }}
+  .group-2 {{ fill: #e8a33d; stroke: #e8a33d; }}
+  .group-3 {{ fill: #a569bd; stroke: #a569bd; }}
+  .group-4 {{ fill: #d9534f; stroke: #d9534f; }}
+  .group-5 {{ fill: #3dbdb0; stroke: #3dbdb0; }}
+  .note {{ fill: #fff8c5; stroke: #d4a72c; stroke-width: 1; }}
+  .note-link {{ stroke: #d4a72c; stroke-width: 1; stroke-dasharray: 4 3; }}
+  .note-text {{ font-family: monospace; font-size: 12px; fill: #3b2e00; }}
   @media (prefers-color-scheme: dark) {{
     .canvas {{ fill: #0d1117; }}
     .entity-bg {{ fill: #161b22; }}
@@ -78,18 +132,51 @@ impl SvgRenderer {
     .entity-separator {{ stroke: #6e7681; }}
     .entity-name {{ fill: #e6edf3; }}
     .column-text {{ fill: #e6edf3; }}
+    .column-extra {{ fill: #9da7b3; }}
+    .index-text {{ fill: #9da7b3; }}
     .edge {{ stroke: #8b949e; }}
+    .enum-header {{ fill: #1c2d44; }}
+    .ghost .entity-bg, .ghost .entity-header {{ fill: #0d1117; }}
+    .ghost .entity-border {{ stroke: #484f58; }}
+    .ghost .entity-name {{ fill: #6e7681; }}
+    .ghost .edge, .ghost .edge-symbol, .ghost .edge-symbol-zero {{ stroke: #30363d; }}
+    .ghost .edge-label, .ghost .cardinality, .ghost .role {{ fill: #6e7681; }}
+    .generalization {{ stroke: #8b949e; fill: #0d1117; }}
     .edge-symbol {{ stroke: #8b949e; }}
     .edge-symbol-zero {{ stroke: #8b949e; fill: #0d1117; }}
     .edge-label-bg {{ fill: rgba(48,54,61,0.92); }}
     .edge-label {{ fill: #c9d1d9; }}
+    .role-bg {{ fill: rgba(13,17,23,0.85); }}
+    .role {{ fill: #9da7b3; }}
     .cardinality-bg {{ fill: rgba(60,67,76,0.95); }}
     .cardinality {{ fill: #f0f6fc; }}
+    .group {{ fill-opacity: 0.16; stroke-opacity: 0.7; }}
+    .group-label {{ fill: #9da7b3; }}
+    .note {{ fill: #272115; stroke: #9e6a03; }}
+    .note-link {{ stroke: #9e6a03; }}
+    .note-text {{ fill: #e3b341; }}
   }}
 </style>"#
         )
         .unwrap();
 
+        // Colours and lines set in the file, one class for each distinct
+        // style, after the defaults they override
+        let mut styles: Vec<&Style> = Vec::new();
+        let node_styles = ir.nodes.iter().map(|n| &n.style);
+        for style in node_styles.chain(ir.edges.iter().map(|e| &e.style)) {
+            if !style.is_empty() && !styles.contains(&style) {
+                styles.push(style);
+            }
+        }
+        render_styles(&mut svg, &styles);
+        let style_class = |style: &Style| {
+            styles
+                .iter()
+                .position(|s| *s == style)
+                .map(|i| format!("style-{}", i))
+        };
+
         // The diagram paints its own surface, so the dark palette does not sit
         // on whatever colour the host page happens to use.
         writeln!(
@@ -101,21 +188,82 @@ impl SvgRenderer {
         // Build node lookup
         let node_map: HashMap<&str, &crate::ir::Node> =
             ir.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+        // Edges between two ghosts are greyed out with them.
+        let ghostly = |e: &Edge| {
+            [&e.from, &e.to]
+                .iter()
+                .all(|id| node_map.get(id.as_str()).is_some_and(|n| n.kind == NodeKind::Ghost))
+        };
+
+        // 0. Group regions, behind everything else. Colours set in the file
+        // get a class each, like the entity styles.
+        let mut group_colors: Vec<&str> = Vec::new();
+        for color in ir.groups.iter().filter_map(|g| g.color.as_deref()) {
+            if !group_colors.contains(&color) {
+                group_colors.push(color);
+            }
+        }
+        render_group_colors(&mut svg, &group_colors);
+        for (index, group) in layout.groups.iter().enumerate() {
+            let class = match ir
+                .groups
+                .iter()
+                .find(|g| g.name == group.name)
+                .and_then(|g| g.color.as_deref())
+            {
+                Some(color) => {
+                    format!("group-color-{}", group_colors.iter().position(|c| *c == color).unwrap())
+                }
+                None => format!("group-{}", index % GROUP_PALETTE_SIZE),
+            };
+            render_group(&mut svg, group, &class);
+        }
 
         // 1. Render edge lines (behind nodes)
         for edge in &layout.edges {
-            self.render_edge_line(&mut svg, edge, layout.corner_radius);
-            if self.notation == Notation::CrowsFoot {
-                if let Some(ir_edge) = ir.edges.get(edge.edge_index) {
-                    self.render_edge_ends(&mut svg, edge, ir_edge);
+            let ir_edge = ir.edges.get(edge.edge_index);
+            let doc = ir_edge.and_then(|e| e.doc.as_deref());
+            // Non-identifying relationships are dashed, as in IE and IDEF1X.
+            let class = match ir_edge {
+                Some(e) if e.kind == EdgeKind::EnumLink => "edge enum-link",
+                Some(e) if e.identifying == Some(false) => "edge non-identifying",
+                _ => "edge",
+            };
+            let custom = match ir_edge {
+                Some(e) if ghostly(e) => Some("ghost".to_string()),
+                _ => ir_edge.and_then(|e| style_class(&e.style)),
+            };
+            if let Some(custom) = &custom {
+                writeln!(&mut svg, r#"<g class="{}">"#, custom).unwrap();
+            }
+            self.render_edge_line(&mut svg, edge, layout.corner_radius, class, doc);
+            match ir_edge {
+                Some(e) if e.kind == EdgeKind::Relationship && self.notation == Notation::CrowsFoot => {
+                    self.render_edge_ends(&mut svg, edge, e);
                 }
+                Some(Edge {
+                    kind: EdgeKind::Generalization(completeness),
+                    ..
+                }) => self.render_generalization(&mut svg, edge, *completeness),
+                _ => {}
+            }
+            if custom.is_some() {
+                writeln!(&mut svg, "</g>").unwrap();
+            }
+        }
+
+        // Notes and the lines linking them to their entities, which they
+        // start from underneath
+        for note in &layout.notes {
+            if let Some(node) = layout.nodes.iter().find(|n| n.id == note.id) {
+                self.render_note(&mut svg, note, node);
             }
         }
 
         // 2. Render nodes (backgrounds, text, borders)
         for node in &layout.nodes {
             if let Some(ir_node) = node_map.get(node.id.as_str()) {
-                self.render_node(&mut svg, node, ir_node);
+                self.render_node(&mut svg, node, ir_node, style_class(&ir_node.style).as_deref());
             }
         }
 
@@ -123,24 +271,53 @@ impl SvgRenderer {
         //    after nudging apart any that would cover each other.
         let mut labels = Vec::new();
         for edge in &layout.edges {
-            if let Some(ir_edge) = ir.edges.get(edge.edge_index) {
+            if let Some(ir_edge) = ir.edges.get(edge.edge_index)
+                && ir_edge.kind != EdgeKind::EnumLink
+            {
                 self.plan_edge_labels(&mut labels, edge, ir_edge);
             }
         }
         resolve_label_overlaps(&mut labels, layout);
         for label in &labels {
+            let ghost = ir.edges.get(label.edge_index).is_some_and(ghostly);
+            if ghost {
+                writeln!(&mut svg, r#"<g class="ghost">"#).unwrap();
+            }
             render_label(&mut svg, label);
+            if ghost {
+                writeln!(&mut svg, "</g>").unwrap();
+            }
         }
 
         writeln!(&mut svg, "</svg>").unwrap();
         svg
     }
 
-    fn render_node(&self, svg: &mut String, layout: &LayoutNode, node: &crate::ir::Node) {
+    fn render_node(&self, svg: &mut String, layout: &LayoutNode, node: &crate::ir::Node, custom: Option<&str>) {
         let x = layout.x;
         let y = layout.y;
         let w = layout.width;
         let header_h = self.metrics.line_height + self.metrics.header_padding * 2.0;
+        let header_class = match node.kind {
+            NodeKind::Entity | NodeKind::Ghost => "entity-header",
+            NodeKind::Enum => "entity-header enum-header",
+        };
+        // A ghost is greyed out and dashed by its own class.
+        let custom = if node.kind == NodeKind::Ghost { Some("ghost") } else { custom };
+
+        // The entity's documentation, as a tooltip over the whole box, and
+        // the class of the colours set on it
+        let grouped = node.doc.is_some() || custom.is_some();
+        if grouped {
+            match custom {
+                Some(custom) => write!(svg, r#"<g class="{}">"#, custom).unwrap(),
+                None => write!(svg, "<g>").unwrap(),
+            }
+            match &node.doc {
+                Some(doc) => writeln!(svg, "<title>{}</title>", escape_xml(doc)).unwrap(),
+                None => writeln!(svg).unwrap(),
+            }
+        }
 
         // 1. Background (white)
         writeln!(
@@ -155,21 +332,22 @@ impl SvgRenderer {
             // No columns: header fills entire box
             writeln!(
                 svg,
-                r#"<rect class="entity-header" x="{}" y="{}" width="{}" height="{}" rx="4" />"#,
-                x, y, w, layout.height
+                r#"<rect class="{}" x="{}" y="{}" width="{}" height="{}" rx="4" />"#,
+                header_class, x, y, w, layout.height
             )
             .unwrap();
         } else {
             // With columns: header at top with square bottom corners
             writeln!(
                 svg,
-                r#"<rect class="entity-header" x="{}" y="{}" width="{}" height="{}" rx="4" />"#,
-                x, y, w, header_h
+                r#"<rect class="{}" x="{}" y="{}" width="{}" height="{}" rx="4" />"#,
+                header_class, x, y, w, header_h
             )
             .unwrap();
             writeln!(
                 svg,
-                r#"<rect class="entity-header" x="{}" y="{}" width="{}" height="{}" />"#,
+                r#"<rect class="{}" x="{}" y="{}" width="{}" height="{}" />"#,
+                header_class,
                 x,
                 y + header_h - 4.0,
                 w,
@@ -201,8 +379,17 @@ impl SvgRenderer {
             )
             .unwrap();
 
+            // At full detail, each row is a line of cells lined up with the
+            // rows above and below
+            let rows: Vec<Vec<String>> = if node.detailed {
+                node.columns.iter().map(|c| c.cells()).collect()
+            } else {
+                Vec::new()
+            };
+            let offsets = self.metrics.cell_offsets(&rows);
+
             let mut col_y = y + header_h + self.metrics.padding_y + self.metrics.line_height * 0.7;
-            for col in &node.columns {
+            for (i, col) in node.columns.iter().enumerate() {
                 let mut class = "column-text".to_string();
                 if col.is_pk {
                     class.push_str(" pk");
@@ -210,17 +397,46 @@ impl SvgRenderer {
                 if col.is_fk {
                     class.push_str(" fk");
                 }
+                if col.inherited {
+                    class.push_str(" inherited");
+                }
 
                 let prefix = if col.is_pk { "◆ " } else { "  " };
-                let text = format!("{}{}: {}", prefix, col.name, col.typ);
-
+                let text_x = x + self.metrics.padding_x;
+                // An enum's rows are its values, which have no type
+                let text = match (rows.get(i), node.kind) {
+                    (Some(cells), _) => {
+                        let mut text = escape_xml(&cells[0]);
+                        for (j, cell) in cells.iter().enumerate().skip(1).filter(|(_, c)| !c.is_empty()) {
+                            let class = if j == 1 { "" } else { r#" class="column-extra""# };
+                            write!(text, r#"<tspan{} x="{}">{}</tspan>"#, class, num(text_x + offsets[j]), escape_xml(cell))
+                                .unwrap();
+                        }
+                        text
+                    }
+                    (None, NodeKind::Entity | NodeKind::Ghost) => escape_xml(&format!("{}{}: {}", prefix, col.name, col.typ)),
+                    (None, NodeKind::Enum) => escape_xml(&format!("{}{}", prefix, col.name)),
+                };
+
+                let tooltip: Vec<String> = col
+                    .doc
+                    .iter()
+                    .cloned()
+                    .chain(col.check.iter().filter(|_| node.detailed).map(|c| format!("check {}", c)))
+                    .collect();
+                let title = if tooltip.is_empty() {
+                    String::new()
+                } else {
+                    format!("<title>{}</title>", escape_xml(&tooltip.join("\n")))
+                };
                 writeln!(
                     svg,
-                    r#"<text class="{}" x="{}" y="{}">{}</text>"#,
+                    r#"<text class="{}" x="{}" y="{}">{}{}</text>"#,
                     class,
-                    x + self.metrics.padding_x,
+                    text_x,
                     col_y,
-                    escape_xml(&text)
+                    title,
+                    text
                 )
                 .unwrap();
 
@@ -228,16 +444,114 @@ impl SvgRenderer {
             }
         }
 
-        // 5. Border (drawn last to be on top)
+        // 5. Indexes and foreign key actions, in a compartment of their own
+        if !node.indexes.is_empty() {
+            let top = y + header_h + self.metrics.body_height(node.columns.len());
+            writeln!(
+                svg,
+                r#"<line class="entity-separator" x1="{}" y1="{}" x2="{}" y2="{}" />"#,
+                x,
+                top,
+                x + w,
+                top
+            )
+            .unwrap();
+            let mut line_y = top + self.metrics.padding_y + self.metrics.line_height * 0.7;
+            for line in &node.indexes {
+                writeln!(
+                    svg,
+                    r#"<text class="index-text" x="{}" y="{}">{}</text>"#,
+                    x + self.metrics.padding_x,
+                    line_y,
+                    escape_xml(line)
+                )
+                .unwrap();
+                line_y += self.metrics.line_height;
+            }
+        }
+
+        // 6. Border (drawn last to be on top), doubled for a weak entity
         writeln!(
             svg,
             r#"<rect class="entity-border" x="{}" y="{}" width="{}" height="{}" rx="4" />"#,
             x, y, w, layout.height
         )
         .unwrap();
+        if node.weak {
+            writeln!(
+                svg,
+                r#"<rect class="entity-border weak" x="{}" y="{}" width="{}" height="{}" rx="2" />"#,
+                x + WEAK_INSET,
+                y + WEAK_INSET,
+                w - WEAK_INSET * 2.0,
+                layout.height - WEAK_INSET * 2.0
+            )
+            .unwrap();
+        }
+
+        if grouped {
+            writeln!(svg, "</g>").unwrap();
+        }
     }
 
-    fn render_edge_line(&self, svg: &mut String, layout: &LayoutEdge, corner_radius: f64) {
+    /// Draw a note box and a dashed line from the facing border of its
+    /// entity, midway along the stretch the two share.
+    fn render_note(&self, svg: &mut String, note: &LayoutNote, node: &LayoutNode) {
+        let overlap = |a: f64, a_len: f64, b: f64, b_len: f64| (a.max(b) + (a + a_len).min(b + b_len)) / 2.0;
+        let (x1, y1, x2, y2) = if note.x >= node.x + node.width {
+            let y = overlap(node.y, node.height, note.y, note.height);
+            (node.x + node.width, y, note.x, y)
+        } else if note.x + note.width <= node.x {
+            let y = overlap(node.y, node.height, note.y, note.height);
+            (node.x, y, note.x + note.width, y)
+        } else if note.y >= node.y + node.height {
+            let x = overlap(node.x, node.width, note.x, note.width);
+            (x, node.y + node.height, x, note.y)
+        } else {
+            let x = overlap(node.x, node.width, note.x, note.width);
+            (x, node.y, x, note.y + note.height)
+        };
+        writeln!(
+            svg,
+            r#"<line class="note-link" x1="{}" y1="{}" x2="{}" y2="{}" />"#,
+            num(x1),
+            num(y1),
+            num(x2),
+            num(y2)
+        )
+        .unwrap();
+
+        writeln!(
+            svg,
+            r#"<rect class="note" x="{}" y="{}" width="{}" height="{}" rx="2" />"#,
+            num(note.x),
+            num(note.y),
+            num(note.width),
+            num(note.height)
+        )
+        .unwrap();
+        let mut line_y = note.y + self.metrics.padding_y + self.metrics.line_height * 0.7;
+        for line in &note.lines {
+            writeln!(
+                svg,
+                r#"<text class="note-text" x="{}" y="{}">{}</text>"#,
+                num(note.x + self.metrics.padding_x),
+                num(line_y),
+                escape_xml(line)
+            )
+            .unwrap();
+            line_y += self.metrics.line_height;
+        }
+    }
+
+    fn render_edge_line(
+        &self,
+        svg: &mut String,
+        layout: &LayoutEdge,
+        corner_radius: f64,
+        class: &str,
+        doc: Option<&str>,
+    ) {
         if layout.waypoints.len() < 2 {
             return;
         }
@@ -295,7 +609,17 @@ impl SvgRenderer {
             }
         }
 
-        writeln!(svg, r#"<path class="edge" d="{}" />"#, path).unwrap();
+        match doc {
+            Some(doc) => writeln!(
+                svg,
+                r#"<path class="{}" d="{}"><title>{}</title></path>"#,
+                class,
+                path,
+                escape_xml(doc)
+            ),
+            None => writeln!(svg, r#"<path class="{}" d="{}" />"#, class, path),
+        }
+        .unwrap();
     }
 
     /// Draw the crow's foot symbol at both ends of an edge.
@@ -305,17 +629,6 @@ impl SvgRenderer {
             return;
         }
 
-        // Unit direction from an entity border along the line, away from it.
-        let outward = |from: (f64, f64), towards: (f64, f64)| {
-            let (dx, dy) = (towards.0 - from.0, towards.1 - from.1);
-            let length = (dx * dx + dy * dy).sqrt();
-            if length < f64::EPSILON {
-                (0.0, 0.0)
-            } else {
-                (dx / length, dy / length)
-            }
-        };
-
         let last = points.len() - 1;
         render_crows_foot(
             svg,
@@ -331,13 +644,84 @@ impl SvgRenderer {
         );
     }
 
-    /// Work out where an edge's cardinalities and label go, without drawing
-    /// them yet: they may still have to slide to avoid one another.
+    /// Draw the supertype end of a generalization, which is the edge's
+    /// `from` end.
+    fn render_generalization(&self, svg: &mut String, layout: &LayoutEdge, completeness: Option<Completeness>) {
+        let points = &layout.waypoints;
+        if points.len() < 2 {
+            return;
+        }
+        let at = points[0];
+        let out = outward(points[0], points[1]);
+        let along = |d: f64| (at.0 + out.0 * d, at.1 + out.1 * d);
+        let across = (-out.1, out.0);
+
+        match self.subtypes {
+            Subtypes::Uml => {
+                let (bx, by) = along(ARROW_LENGTH);
+                writeln!(
+                    svg,
+                    r#"<path class="generalization" d="M {} {} L {} {} L {} {} Z" />"#,
+                    num(at.0),
+                    num(at.1),
+                    num(bx + across.0 * ARROW_HALF_WIDTH),
+                    num(by + across.1 * ARROW_HALF_WIDTH),
+                    num(bx - across.0 * ARROW_HALF_WIDTH),
+                    num(by - across.1 * ARROW_HALF_WIDTH)
+                )
+                .unwrap();
+            }
+            Subtypes::Idef1x => {
+                let (cx, cy) = along(TICK_INSET + CATEGORY_RADIUS);
+                writeln!(
+                    svg,
+                    r#"<circle class="generalization" cx="{}" cy="{}" r="{}" />"#,
+                    num(cx),
+                    num(cy),
+                    num(CATEGORY_RADIUS)
+                )
+                .unwrap();
+                let bars = if completeness == Some(Completeness::Total) { 2 } else { 1 };
+                for bar in 0..bars {
+                    let (x, y) = along(TICK_INSET + CATEGORY_RADIUS * 2.0 + 3.0 + 4.0 * bar as f64);
+                    writeln!(
+                        svg,
+                        r#"<path class="edge-symbol" d="M {} {} L {} {}" />"#,
+                        num(x - across.0 * CATEGORY_RADIUS * 1.5),
+                        num(y - across.1 * CATEGORY_RADIUS * 1.5),
+                        num(x + across.0 * CATEGORY_RADIUS * 1.5),
+                        num(y + across.1 * CATEGORY_RADIUS * 1.5)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Work out where an edge's cardinalities, label and role names go,
+    /// without drawing them yet: they may still have to slide to avoid one
+    /// another.
     fn plan_edge_labels(&self, plans: &mut Vec<LabelPlan>, layout: &LayoutEdge, edge: &Edge) {
-        if layout.waypoints.len() < 2 {
+        let points = &layout.waypoints;
+        if points.len() < 2 {
             return;
         }
+        self.plan_cardinalities_and_label(plans, layout, edge);
+
+        // Roles come last, so they are the ones that make way.
+        let last = points.len() - 1;
+        let ends = [
+            (&edge.from_role, points[0], points[1]),
+            (&edge.to_role, points[last], points[last - 1]),
+        ];
+        for (role, at, next) in ends {
+            if let Some(role) = role {
+                plans.push(plan_role(at, next, role, layout.edge_index));
+            }
+        }
+    }
 
+    fn plan_cardinalities_and_label(&self, plans: &mut Vec<LabelPlan>, layout: &LayoutEdge, edge: &Edge) {
         let (x1, y1) = layout.waypoints[0];
         let (x2, y2) = layout.waypoints[layout.waypoints.len() - 1];
 
@@ -350,16 +734,35 @@ impl SvgRenderer {
         let index = layout.edge_index;
 
         // Crow's foot draws the cardinality on the line, so only the
-        // relationship label needs a pill.
-        let text_cardinalities = self.notation == Notation::Text;
+        // relationship label needs a pill. A generalization has none.
+        let text_cardinalities = self.notation == Notation::Text && edge.kind == EdgeKind::Relationship;
+
+        if layout.is_self_ref && layout.waypoints.len() >= 4 && layout.waypoints[1].0 == x1 {
+            // A self-reference below the entity, as sideways layouts draw
+            // them: cardinalities and label go under the loop.
+            let loop_y = layout.waypoints[1].1 + margin;
+
+            if text_cardinalities {
+                plans.push(plan_cardinality(x1, loop_y, &from_symbol, index, DOWN, 0.0));
+                plans.push(plan_cardinality(x2, loop_y, &to_symbol, index, DOWN, 0.0));
+            }
+
+            if let Some(label) = &edge.label {
+                let mid_x = (x1 + x2) / 2.0;
+                let label_y = loop_y + EDGE_LABEL_FONT_SIZE / 2.0 + margin;
+                let room = ((x2 - x1).abs() - monospace_width(label, EDGE_LABEL_FONT_SIZE)) / 2.0;
+                plans.push(plan_edge_label(mid_x, label_y, label, index, RIGHT, room));
+            }
+            return;
+        }
 
         if layout.is_self_ref && layout.waypoints.len() >= 4 {
             // Self-referential: place cardinalities on the right side of loop
             let loop_x = layout.waypoints[1].0 + margin;
 
             if text_cardinalities {
-                plans.push(plan_cardinality(loop_x, y1, from_symbol, index, RIGHT, 0.0));
-                plans.push(plan_cardinality(loop_x, y2, to_symbol, index, RIGHT, 0.0));
+                plans.push(plan_cardinality(loop_x, y1, &from_symbol, index, RIGHT, 0.0));
+                plans.push(plan_cardinality(loop_x, y2, &to_symbol, index, RIGHT, 0.0));
             }
 
             if let Some(label) = &edge.label {
@@ -390,7 +793,7 @@ impl SvgRenderer {
             plans.push(plan_cardinality(
                 from_pos.0,
                 from_pos.1,
-                from_symbol,
+                &from_symbol,
                 index,
                 from_dir,
                 from_room,
@@ -410,7 +813,7 @@ impl SvgRenderer {
             plans.push(plan_cardinality(
                 to_pos.0,
                 to_pos.1,
-                to_symbol,
+                &to_symbol,
                 index,
                 to_dir,
                 to_room,
@@ -432,6 +835,108 @@ impl SvgRenderer {
     }
 }
 
+/// Gap between the two borders of a weak entity.
+const WEAK_INSET: f64 = 3.0;
+
+/// Number of `.group-N` palette classes.
+const GROUP_PALETTE_SIZE: usize = 6;
+
+/// Rules for the styles set in the file, `style-0` onwards. In the dark
+/// palette, custom fills are mixed into the dark defaults so the text on them
+/// stays readable; borders and lines keep their colour.
+fn render_styles(svg: &mut String, styles: &[&Style]) {
+    if styles.is_empty() {
+        return;
+    }
+    let mut light = String::new();
+    let mut dark = String::new();
+    for (i, style) in styles.iter().enumerate() {
+        let class = format!(".style-{}", i);
+        if let Some(fill) = &style.fill {
+            writeln!(light, "  {class} .entity-bg {{ fill: {fill}; }}").unwrap();
+            writeln!(dark, "    {class} .entity-bg {{ fill: color-mix(in srgb, {fill} 25%, #161b22); }}").unwrap();
+        }
+        if let Some(header) = &style.header {
+            writeln!(light, "  {class} .entity-header {{ fill: {header}; }}").unwrap();
+            writeln!(dark, "    {class} .entity-header {{ fill: color-mix(in srgb, {header} 40%, #262c36); }}").unwrap();
+        }
+        if let Some(stroke) = &style.stroke {
+            writeln!(
+                light,
+                "  {class} .entity-border, {class} .edge, {class} .edge-symbol, {class} .edge-symbol-zero, \
+                 {class} .generalization {{ stroke: {stroke}; }}"
+            )
+            .unwrap();
+        }
+        if let Some(width) = style.width {
+            writeln!(
+                light,
+                "  {class} .edge, {class} .edge-symbol, {class} .edge-symbol-zero {{ stroke-width: {}; }}",
+                num(width)
+            )
+            .unwrap();
+        }
+        if style.dashed {
+            // Shorter dashes than a non-identifying line, which means something else.
+            writeln!(light, "  {class} .edge {{ stroke-dasharray: 3 3; }}").unwrap();
+        }
+    }
+
+    writeln!(svg, "<style>").unwrap();
+    svg.push_str(&escape_xml(&light));
+    if !dark.is_empty() {
+        writeln!(svg, "  @media (prefers-color-scheme: dark) {{").unwrap();
+        svg.push_str(&escape_xml(&dark));
+        writeln!(svg, "  }}").unwrap();
+    }
+    writeln!(svg, "</style>").unwrap();
+}
+
+/// Rules for the group colours set in the file, `group-color-0` onwards. The
+/// `.group` class keeps the opacity, so they still read as a tint; in the
+/// dark palette the fill is also mixed into the canvas colour.
+fn render_group_colors(svg: &mut String, colors: &[&str]) {
+    if colors.is_empty() {
+        return;
+    }
+    let mut light = String::new();
+    let mut dark = String::new();
+    for (i, color) in colors.iter().enumerate() {
+        writeln!(light, "  .group-color-{i} {{ fill: {color}; stroke: {color}; }}").unwrap();
+        writeln!(dark, "    .group-color-{i} {{ fill: color-mix(in srgb, {color} 70%, #0d1117); }}").unwrap();
+    }
+
+    writeln!(svg, "<style>").unwrap();
+    svg.push_str(&escape_xml(&light));
+    writeln!(svg, "  @media (prefers-color-scheme: dark) {{").unwrap();
+    svg.push_str(&escape_xml(&dark));
+    writeln!(svg, "  }}").unwrap();
+    writeln!(svg, "</style>").unwrap();
+}
+
+/// A rounded, tinted region with the group name in its top-left corner,
+/// painted by `class`: a palette colour or one set in the file.
+fn render_group(svg: &mut String, group: &LayoutGroup, class: &str) {
+    writeln!(
+        svg,
+        r#"<rect class="group {}" x="{}" y="{}" width="{}" height="{}" rx="10" />"#,
+        class,
+        num(group.x),
+        num(group.y),
+        num(group.width),
+        num(group.height)
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<text class="group-label" x="{}" y="{}">{}</text>"#,
+        num(group.x + 10.0),
+        num(group.y + 16.0),
+        escape_xml(&group.name)
+    )
+    .unwrap();
+}
+
 /// Length of the crow's foot prongs, and of the gap before an outer tick.
 const FOOT_LENGTH: f64 = 15.0;
 
@@ -444,6 +949,24 @@ const TICK_INSET: f64 = 10.0;
 /// Radius of the "zero" circle.
 const ZERO_RADIUS: f64 = 4.0;
 
+/// Length and half the base of a generalization's triangle.
+const ARROW_LENGTH: f64 = 14.0;
+const ARROW_HALF_WIDTH: f64 = 8.0;
+
+/// Radius of the IDEF1X category circle.
+const CATEGORY_RADIUS: f64 = 6.0;
+
+/// Unit direction from an entity border along the line, away from it.
+fn outward(from: (f64, f64), towards: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (towards.0 - from.0, towards.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length < f64::EPSILON {
+        (0.0, 0.0)
+    } else {
+        (dx / length, dy / length)
+    }
+}
+
 /// Draw the crow's foot symbol for one end of an edge.
 ///
 /// `at` is the point on the entity border and `out` the unit direction along
@@ -494,14 +1017,16 @@ fn render_crows_foot(svg: &mut String, at: (f64, f64), out: (f64, f64), cardinal
         .unwrap();
     };
 
-    match cardinality {
-        Cardinality::One => tick(svg, TICK_INSET),
-        Cardinality::ZeroOrOne => {
+    // Only 0..1, 1, * and 1..* have a glyph; any other range is drawn as the
+    // nearest of them, by whether it may be empty and whether it may hold many.
+    match (cardinality.is_optional(), cardinality.is_many()) {
+        (false, false) => tick(svg, TICK_INSET),
+        (true, false) => {
             tick(svg, TICK_INSET);
             zero(svg, TICK_INSET + 2.0);
         }
-        Cardinality::Many => foot(svg),
-        Cardinality::OneOrMore => {
+        (true, true) => foot(svg),
+        (false, true) => {
             foot(svg);
             tick(svg, FOOT_LENGTH);
         }
@@ -512,13 +1037,8 @@ fn render_crows_foot(svg: &mut String, at: (f64, f64), out: (f64, f64), cardinal
 /// so it reads as a footnote mark next to the digits; this one sits centered.
 const MANY: &str = "\u{2731}";
 
-fn cardinality_symbol(c: Cardinality) -> &'static str {
-    match c {
-        Cardinality::One => "1",
-        Cardinality::ZeroOrOne => "0..1",
-        Cardinality::Many => MANY,
-        Cardinality::OneOrMore => concat!("1..", "\u{2731}"),
-    }
+fn cardinality_symbol(c: Cardinality) -> String {
+    c.to_string().replace('*', MANY)
 }
 
 /// Format a coordinate with at most one decimal place.
@@ -535,11 +1055,14 @@ fn num(v: f64) -> String {
 /// the label itself.
 const MIN_LABEL_ROOM: f64 = 12.0;
 
+/// A label position, the direction it may slide, and how far.
+type LabelAnchor = ((f64, f64), (f64, f64), f64);
+
 /// Where a relationship label sits: centered on the longest segment that can
 /// hold it, preferring horizontal runs so the text reads along the line.
 ///
 /// Returns the position, the direction it may slide, and how far.
-fn label_anchor(waypoints: &[(f64, f64)], width: f64) -> Option<((f64, f64), (f64, f64), f64)> {
+fn label_anchor(waypoints: &[(f64, f64)], width: f64) -> Option<LabelAnchor> {
     let segments = waypoints.windows(2).map(|seg| {
         let horizontal = (seg[0].1 - seg[1].1).abs() < 0.5;
         let length = if horizontal {
@@ -687,6 +1210,49 @@ fn plan_cardinality(
     }
 }
 
+/// A role name beside the line near the end at `at`, whose first segment
+/// runs to `next`: right of a vertical line, above a horizontal one, and
+/// clear of the symbol drawn at the end.
+fn plan_role(at: (f64, f64), next: (f64, f64), role: &str, edge_index: usize) -> LabelPlan {
+    let padding = 2.0;
+    let width = monospace_width(role, ROLE_FONT_SIZE) + padding * 2.0;
+    let height = ROLE_FONT_SIZE + padding * 2.0;
+    let dir = outward(at, next);
+    let stub = (next.0 - at.0).abs().max((next.1 - at.1).abs());
+
+    let (x, y, along) = if dir.1.abs() > dir.0.abs() {
+        let along = height / 2.0 + ROLE_MARGIN;
+        (at.0 + SYMBOL_HALF_WIDTH + ROLE_MARGIN + width / 2.0, at.1 + dir.1 * along, along)
+    } else {
+        let along = width / 2.0 + ROLE_MARGIN;
+        (at.0 + dir.0 * along, at.1 - SYMBOL_HALF_WIDTH - ROLE_MARGIN - height / 2.0, along)
+    };
+    let room = stub - along * 2.0;
+
+    LabelPlan {
+        x,
+        y,
+        width,
+        height,
+        text: role.to_string(),
+        bg_class: "role-bg",
+        text_class: "role",
+        edge_index,
+        slide: (room > 0.0).then_some(Slide {
+            dir,
+            room,
+            both_ways: false,
+            sideways: true,
+        }),
+    }
+}
+
+/// Font size of role names, matching the `.role` class.
+const ROLE_FONT_SIZE: f64 = 12.0;
+
+/// Gap between a role name and the entity border or end symbol beside it.
+const ROLE_MARGIN: f64 = 4.0;
+
 /// Clearance kept between a label and whatever it must not cover.
 const LABEL_GAP: f64 = 3.0;
 
@@ -811,7 +1377,7 @@ fn escape_xml(s: &str) -> String {
 mod tests {
     use super::*;
     use crate::ir::DetailLevel;
-    use crate::layout::LayoutEngine;
+    use crate::layout::{LayoutEngine, Notes};
     use crate::parser::Parser;
 
     #[test]
@@ -863,4 +1429,319 @@ mod tests {
         assert!(svg.contains("places"));
         assert!(svg.contains(r#"class="edge""#));
     }
+
+    #[test]
+    fn test_render_cardinality_ranges() {
+        let input = r#"
+            entity Order { id int pk }
+            entity Shipment { id int pk }
+            rel { Order 1 -- 1..5 Shipment }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        let layout = LayoutEngine::default().layout(&ir);
+
+        let text = SvgRenderer::with_notation(Notation::Text).render(&ir, &layout);
+        assert!(text.contains(">1..5<"));
+
+        // 1..5 is drawn like 1..*: a foot and a tick, on top of the 1's tick.
+        let crows_foot = SvgRenderer::default().render(&ir, &layout);
+        assert_eq!(crows_foot.matches(r#"class="edge-symbol""#).count(), 4);
+    }
+
+    #[test]
+    fn test_render_groups() {
+        let input = r##"
+            @hint.group.billing.color = "#e8a33d"
+            entity User {
+                @hint.group = "core"
+                id int pk
+            }
+            entity Invoice {
+                @hint.group = billing
+                id int pk
+            }
+        "##;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+
+        assert!(svg.contains(r#"<rect class="group group-0""#));
+        assert!(svg.contains(r#"<rect class="group group-color-0""#));
+        assert!(svg.contains(".group-color-0 { fill: #e8a33d; stroke: #e8a33d; }"));
+        assert!(svg.contains(".group-color-0 { fill: color-mix(in srgb, #e8a33d 70%, #0d1117); }"));
+        assert!(svg.contains(r#"class="group-label" x="50" y="56">core</text>"#));
+        // Regions go behind the entities.
+        assert!(svg.find(r#"class="group-label""#).unwrap() < svg.find(r#"class="entity-bg""#).unwrap());
+    }
+
+    #[test]
+    fn test_render_docs() {
+        let input = r#"
+            entity User "Registered <customer>" {
+                /// Login name
+                email string
+            }
+            entity Order { id int pk }
+            rel {
+                /// Every order has a buyer
+                User 1 -- * Order
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+        assert!(svg.contains("<g><title>Registered &lt;customer&gt;</title>"));
+        assert!(svg.contains("><title>Login name</title>  email: string</text>"));
+        assert!(svg.contains("><title>Every order has a buyer</title></path>"));
+        assert!(!svg.contains(r#"class="note""#));
+
+        let layout = LayoutEngine::default().with_notes(Notes::Show).layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+        assert!(svg.contains(r#"<line class="note-link""#));
+        assert!(svg.contains(">Registered &lt;customer&gt;</text>"));
+    }
+
+    #[test]
+    fn test_render_enums() {
+        let input = r#"
+            enum Status { pending, paid }
+            entity Order {
+                id int pk
+                status Status
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        ir.add_enums(&schema);
+
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+        assert!(svg.contains(r#"<rect class="entity-header enum-header""#));
+        assert!(svg.contains(">«enum» Status</text>"));
+        assert!(svg.contains(">  pending</text>"));
+        assert!(svg.contains(r#"<path class="edge enum-link""#));
+        // No crow's foot on the link
+        assert!(!svg.contains(r#"class="edge-symbol"#));
+
+        let svg = SvgRenderer::with_notation(Notation::Text).render(&ir, &layout);
+        assert!(!svg.contains(r#"class="cardinality""#));
+    }
+
+    #[test]
+    fn test_render_generalization() {
+        let input = r#"
+            entity Party { id int pk }
+            entity Person extends Party disjoint total { name string }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let mut ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        ir.inherit_columns(&schema, DetailLevel::All);
+        let layout = LayoutEngine::default().layout(&ir);
+
+        let svg = SvgRenderer::default().render(&ir, &layout);
+        assert!(svg.contains(r#"<path class="generalization" d="M "#));
+        assert!(svg.contains(">{disjoint, total}</text>"));
+        assert!(svg.contains(r#"<text class="column-text pk inherited""#));
+        assert!(!svg.contains(r#"class="edge-symbol""#));
+
+        let svg = SvgRenderer::default().with_subtypes(Subtypes::Idef1x).render(&ir, &layout);
+        assert!(svg.contains(r#"<circle class="generalization""#));
+        // Total: two bars under the circle
+        assert_eq!(svg.matches(r#"class="edge-symbol""#).count(), 2);
+
+        let svg = SvgRenderer::with_notation(Notation::Text).render(&ir, &layout);
+        assert!(!svg.contains(r#"class="cardinality""#));
+    }
+
+    #[test]
+    fn test_render_identifying() {
+        let input = r#"
+            entity Order { id int pk }
+            entity Customer { id int pk }
+            entity OrderItem {
+                order_id int fk -> Order.id
+                customer_id int fk -> Customer.id
+                line int
+                primary_key(order_id, line)
+            }
+            rel {
+                Order 1 -- * OrderItem
+                Customer 1 -- * OrderItem
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+
+        assert_eq!(svg.matches(r#"<path class="edge" "#).count(), 1);
+        assert_eq!(svg.matches(r#"<path class="edge non-identifying" "#).count(), 1);
+        assert_eq!(svg.matches(r#"<rect class="entity-border weak""#).count(), 1);
+    }
+
+    #[test]
+    fn test_render_full_detail() {
+        let input = r#"
+            entity User {
+                id int pk
+                email string unique not null
+                age int check "age >= 18"
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::Full);
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+
+        // Types and badges line up in columns, whatever the name's length.
+        let x = layout.nodes[0].x + 12.0;
+        assert!(svg.contains(&format!(r#">  email<tspan x="{}">string</tspan>"#, num(x + 72.0))));
+        assert!(svg.contains(&format!(r#"<tspan class="column-extra" x="{}">NN UQ</tspan>"#, num(x + 136.0))));
+        assert!(svg.contains("<title>check age &gt;= 18</title>"));
+        // The box is wide enough for the widest row.
+        assert!(layout.nodes[0].width >= (136.0 + 5.0 * 8.0) + 24.0);
+    }
+
+    #[test]
+    fn test_render_roles() {
+        let input = r#"
+            entity Dept { id int pk }
+            entity Employee { id int pk }
+            rel { Dept 1 -- * Employee as employer, staff }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+
+        let text = |role: &str| {
+            let end = svg.find(&format!(">{}</text>", role)).unwrap();
+            let start = svg[..end].rfind("<text").unwrap();
+            let attr = |name: &str| -> f64 {
+                let at = svg[start..end].find(&format!(" {}=\"", name)).unwrap() + start + name.len() + 3;
+                svg[at..at + svg[at..].find('"').unwrap()].parse().unwrap()
+            };
+            assert!(svg[start..end].contains(r#"class="role""#));
+            (attr("x"), attr("y"))
+        };
+        let (dept, employee) = (&layout.nodes[0], &layout.nodes[1]);
+        let line_x = layout.edges[0].waypoints[0].0;
+
+        // Each role sits beside the line, just outside the entity it names.
+        let (x, y) = text("employer");
+        assert!(x > line_x + 7.5);
+        assert!(y > dept.y + dept.height && y < dept.y + dept.height + 20.0);
+        let (x, y) = text("staff");
+        assert!(x > layout.edges[0].waypoints.last().unwrap().0 + 7.5);
+        assert!(y < employee.y && y > employee.y - 20.0);
+    }
+
+    #[test]
+    fn test_render_ghosts() {
+        let input = r#"
+            /// Someone who orders
+            entity User { id int pk email string }
+            entity Order { id int pk }
+            rel { User 1 -- * Order }
+            view orders { include Order }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let view = crate::view::resolve_named(&schema, "orders");
+        let mut ir = GraphIR::from_schema(&schema, view.as_ref(), DetailLevel::All);
+        ir.add_ghosts(&schema, crate::ir::Ghosts::Show, crate::ir::EdgeSource::Rel);
+        let layout = LayoutEngine::default().with_notes(Notes::Show).layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+
+        // The ghost is its name alone, in its own class, and keeps its
+        // description as a tooltip only.
+        assert!(svg.contains("<g class=\"ghost\"><title>Someone who orders</title>\n"));
+        assert!(!svg.contains("email"));
+        assert!(layout.notes.is_empty());
+        let user = layout.nodes.iter().find(|n| n.id == "User").unwrap();
+        assert_eq!(user.height, 28.0);
+        assert_eq!(layout.edges.len(), 1);
+        assert!(svg.contains(".ghost .entity-border { stroke: #aaa; stroke-width: 1; stroke-dasharray: 5 3; }"));
+        assert!(!svg.contains("<g class=\"ghost\">\n<path"));
+
+        // With the whole rest of the schema, edges between ghosts are greyed.
+        let input = format!("{input}\nentity Address {{ id int pk }}\nrel {{ User 1 -- * Address }}\n");
+        let schema = Parser::new(&input).unwrap().parse().unwrap();
+        let view = crate::view::resolve_named(&schema, "orders");
+        let mut ir = GraphIR::from_schema(&schema, view.as_ref(), DetailLevel::All);
+        ir.add_ghosts(&schema, crate::ir::Ghosts::All, crate::ir::EdgeSource::Rel);
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+        assert_eq!(svg.matches("<g class=\"ghost\">\n<path").count(), 1);
+    }
+
+    #[test]
+    fn test_render_physical_detail() {
+        let input = r#"
+            entity User { id int pk }
+            entity Order {
+                id int pk
+                user_id int fk -> User.id
+                foreign_key(user_id) references User(id) on delete set null
+                index(user_id) [name = ix_order_user]
+            }
+        "#;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::Physical);
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+
+        // The compartment starts below the two column rows.
+        let order = &layout.nodes[1];
+        let top = order.y + 28.0 + 2.0 * 20.0 + 16.0;
+        assert!(svg.contains(&format!(r#"y1="{}" x2="{}" y2="{}" />"#, top, order.x + order.width, top)));
+        assert!(svg.contains(&format!(
+            r#"<text class="index-text" x="{}" y="{}">INDEX ix_order_user (user_id)</text>"#,
+            order.x + 12.0,
+            top + 8.0 + 14.0
+        )));
+        assert!(svg.contains("FK (user_id) → User(id) ON DELETE SET NULL</text>"));
+        assert_eq!(order.height, top + 2.0 * 20.0 + 16.0 - order.y);
+    }
+
+    #[test]
+    fn test_render_styles() {
+        let input = r##"
+            entity Legacy {
+                @hint.color = "#ffd"
+                @hint.header = orange
+                id int pk
+            }
+            entity Plain { id int pk }
+            entity Order { id int pk }
+            rel {
+                Legacy 1 -- * Order [dashed, color = red, width = 2]
+                Plain 1 -- * Order
+            }
+        "##;
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        let layout = LayoutEngine::default().layout(&ir);
+        let svg = SvgRenderer::default().render(&ir, &layout);
+
+        assert!(svg.contains("  .style-0 .entity-bg { fill: #ffd; }\n"));
+        assert!(svg.contains("    .style-0 .entity-header { fill: color-mix(in srgb, orange 40%, #262c36); }\n"));
+        assert!(svg.contains(".style-1 .edge { stroke-dasharray: 3 3; }"));
+        assert!(svg.contains(".non-identifying { stroke-dasharray: 7 4; }"));
+        assert!(svg.contains(".style-1 .edge, .style-1 .edge-symbol, .style-1 .edge-symbol-zero { stroke-width: 2; }"));
+        assert!(svg.contains(".style-1 .edge-symbol, .style-1 .edge-symbol-zero, .style-1 .generalization { stroke: red; }"));
+        assert_eq!(svg.matches(r#"<g class="style-0">"#).count(), 1);
+        assert_eq!(svg.matches(r#"<g class="style-1">"#).count(), 1);
+        assert!(!svg.contains("style-2"));
+
+        // Without styles, the output has no rules for them.
+        let schema = Parser::new("entity A { id int pk }").unwrap().parse().unwrap();
+        let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
+        let svg = SvgRenderer::default().render(&ir, &LayoutEngine::default().layout(&ir));
+        assert!(!svg.contains("style-"));
+    }
 }
+
@@ -0,0 +1,530 @@
+//! Checks that run on a parsed schema, for mistakes the grammar cannot see:
+//! references to entities that do not exist, and names defined twice.
+//!
+//! Without these, `GraphIR::from_schema` would quietly drop whatever it
+//! cannot resolve.
+
+use crate::ast::*;
+use crate::diagnostic::Diagnostic;
+use crate::ir::{Anchoring, DetailLevel, EdgeSource, EnumBoxes, Ghosts, InheritedColumns, JoinEntities, color_value, width_value};
+use crate::layout::{Direction, Notes};
+use crate::svg::{Notation, Subtypes};
+use std::collections::{HashMap, HashSet};
+
+/// Check `schema` and return every problem found, in source order (file by
+/// file, for a schema put together by the loader).
+pub fn validate(schema: &Schema) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut entities: HashMap<&str, &Entity> = HashMap::new();
+    for entity in &schema.entities {
+        if let Some(first) = entities.get(entity.name.as_str()) {
+            diagnostics.push(
+                Diagnostic::error(format!("Entity `{}` is defined more than once", entity.name), entity.span)
+                    .with_note("first defined here", first.span),
+            );
+        } else {
+            entities.insert(&entity.name, entity);
+        }
+        check_columns(entity, &mut diagnostics);
+    }
+
+    let mut enums: HashMap<&str, &Enum> = HashMap::new();
+    for e in &schema.enums {
+        if let Some(first) = enums.get(e.name.as_str()) {
+            diagnostics.push(
+                Diagnostic::error(format!("Enum `{}` is defined more than once", e.name), e.span)
+                    .with_note("first defined here", first.span),
+            );
+        } else if let Some(entity) = entities.get(e.name.as_str()) {
+            diagnostics.push(
+                Diagnostic::error(format!("Enum `{}` has the name of an entity", e.name), e.span)
+                    .with_note("entity defined here", entity.span),
+            );
+        } else {
+            enums.insert(&e.name, e);
+        }
+        let mut seen = HashSet::new();
+        for value in &e.values {
+            if !seen.insert(value.as_str()) {
+                diagnostics.push(Diagnostic::error(
+                    format!("Enum `{}` lists `{}` more than once", e.name, value),
+                    e.span,
+                ));
+            }
+        }
+    }
+
+    for entity in &schema.entities {
+        check_foreign_keys(entity, &entities, &mut diagnostics);
+        check_enum_defaults(entity, &enums, &mut diagnostics);
+        check_entity_style(entity, &mut diagnostics);
+    }
+    check_supertypes(schema, &entities, &mut diagnostics);
+
+    for rel in &schema.relationships {
+        for (name, column) in [(&rel.left, &rel.left_column), (&rel.right, &rel.right_column)] {
+            match entities.get(name.as_str()) {
+                None => diagnostics.push(Diagnostic::error(
+                    format!("Relationship refers to undefined entity `{}`", name),
+                    rel.span,
+                )),
+                // The edge still renders, attached to the border instead.
+                Some(entity) => {
+                    if let Some(column) = column
+                        && !entity.columns.iter().any(|c| c.name == *column)
+                    {
+                        diagnostics.push(Diagnostic::warning(
+                            format!("Relationship refers to `{}.{}`, which is not a column", name, column),
+                            rel.span,
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(join) = &rel.via
+            && !entities.contains_key(join.as_str())
+        {
+            diagnostics.push(Diagnostic::error(
+                format!("Relationship goes through undefined entity `{}`", join),
+                rel.span,
+            ));
+        }
+        check_relationship_style(rel, &mut diagnostics);
+    }
+
+    let mut views: HashMap<&str, &View> = HashMap::new();
+    for view in &schema.views {
+        if let Some(first) = views.get(view.name.as_str()) {
+            diagnostics.push(
+                Diagnostic::error(format!("View `{}` is defined more than once", view.name), view.span)
+                    .with_note("first defined here", first.span),
+            );
+        } else {
+            views.insert(&view.name, view);
+        }
+        diagnostics.extend(crate::view::resolve(schema, view).diagnostics);
+    }
+
+    let arrangements = schema.arrangement.iter().chain(schema.views.iter().filter_map(|v| v.arrangement.as_ref()));
+    for arrangement in arrangements {
+        let mut seen = HashSet::new();
+        for name in arrangement.iter().flatten() {
+            if !entities.contains_key(name.as_str()) {
+                diagnostics.push(Diagnostic::warning(
+                    format!("Arrangement lists undefined entity `{}`", name.name),
+                    name.span,
+                ));
+            } else if !seen.insert(name.as_str()) {
+                diagnostics.push(Diagnostic::warning(
+                    format!("Entity `{}` is listed more than once in the arrangement", name.name),
+                    name.span,
+                ));
+            }
+        }
+    }
+
+    for hint in schema.hints.iter().chain(schema.views.iter().flat_map(|v| &v.hints)) {
+        let word = match &hint.value {
+            HintValue::Ident(s) | HintValue::Str(s) => Some(s.as_str()),
+            HintValue::Int(_) => None,
+        };
+        if is_group_color(&hint.key) {
+            if color_value(&hint.value).is_none() {
+                diagnostics.push(Diagnostic::warning(
+                    format!("`@{}` is not a colour; expected a name or a quoted \"#rrggbb\"", hint.key),
+                    hint.span,
+                ));
+            }
+            continue;
+        }
+        let (valid, message) = match hint.key.as_str() {
+            "hint.direction" => (
+                word.and_then(Direction::from_str).is_some(),
+                "Unknown layout direction; expected TB, LR, BT or RL",
+            ),
+            "hint.anchors" => (
+                word.and_then(Anchoring::from_str).is_some(),
+                "Unknown edge anchoring; expected border or columns",
+            ),
+            "hint.edges" => (
+                word.and_then(EdgeSource::from_str).is_some(),
+                "Unknown edge source; expected rel or fk",
+            ),
+            "hint.notes" => (
+                word.and_then(Notes::from_str).is_some(),
+                "Unknown notes setting; expected show or hide",
+            ),
+            "hint.enums" => (
+                word.and_then(EnumBoxes::from_str).is_some(),
+                "Unknown enums setting; expected show or hide",
+            ),
+            "hint.subtypes" => (
+                word.and_then(Subtypes::from_str).is_some(),
+                "Unknown subtypes setting; expected uml or idef1x",
+            ),
+            "hint.inherited" => (
+                word.and_then(InheritedColumns::from_str).is_some(),
+                "Unknown inherited setting; expected show or hide",
+            ),
+            "hint.joins" => (
+                word.and_then(JoinEntities::from_str).is_some(),
+                "Unknown joins setting; expected show or collapse",
+            ),
+            "hint.ghosts" => (
+                word.and_then(Ghosts::from_str).is_some(),
+                "Unknown ghosts setting; expected show, all or hide",
+            ),
+            "hint.detail" => (
+                word.and_then(DetailLevel::from_str).is_some(),
+                "Unknown detail level; expected tables, pk, pk_fk, all, full or physical",
+            ),
+            "hint.notation" => (
+                word.and_then(Notation::from_str).is_some(),
+                "Unknown notation; expected crowsfoot or text",
+            ),
+            _ => continue,
+        };
+        if !valid {
+            diagnostics.push(Diagnostic::warning(message.to_string(), hint.span));
+        }
+    }
+
+    diagnostics.sort_by_key(|d| (d.span.file, d.span.start));
+    diagnostics
+}
+
+fn check_columns(entity: &Entity, diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen = HashSet::new();
+    for column in &entity.columns {
+        if !seen.insert(column.name.as_str()) {
+            diagnostics.push(Diagnostic::error(
+                format!("Column `{}` is defined more than once in `{}`", column.name, entity.name),
+                column.span,
+            ));
+        }
+    }
+}
+
+/// A dangling `fk ->` still renders, so it is only a warning.
+fn check_foreign_keys(entity: &Entity, entities: &HashMap<&str, &Entity>, diagnostics: &mut Vec<Diagnostic>) {
+    for column in &entity.columns {
+        for modifier in &column.modifiers {
+            let ColumnModifier::Fk { target, column: target_column } = modifier else {
+                continue;
+            };
+            match entities.get(target.as_str()) {
+                None => diagnostics.push(Diagnostic::warning(
+                    format!("Foreign key `{}` refers to undefined entity `{}`", column.name, target),
+                    column.span,
+                )),
+                Some(t) if !t.columns.iter().any(|c| c.name == *target_column) => {
+                    diagnostics.push(Diagnostic::warning(
+                        format!(
+                            "Foreign key `{}` refers to `{}.{}`, which is not a column",
+                            column.name, target, target_column
+                        ),
+                        column.span,
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+/// Every `extends` must name an entity, and following them must not lead back
+/// to where it started. Subtypes of one supertype that give it different
+/// markers are only a warning; the first marker is drawn.
+fn check_supertypes(schema: &Schema, entities: &HashMap<&str, &Entity>, diagnostics: &mut Vec<Diagnostic>) {
+    let mut markers: HashMap<&str, (Option<Disjointness>, Option<Completeness>)> = HashMap::new();
+    for entity in &schema.entities {
+        let Some(extends) = &entity.extends else {
+            continue;
+        };
+        if !entities.contains_key(extends.parent.as_str()) {
+            diagnostics.push(Diagnostic::error(
+                format!("Entity `{}` extends undefined entity `{}`", entity.name, extends.parent.name),
+                extends.parent.span,
+            ));
+            continue;
+        }
+
+        let mut seen = HashSet::from([entity.name.as_str()]);
+        let mut parent = Some(extends.parent.as_str());
+        while let Some(name) = parent {
+            if name == entity.name {
+                diagnostics.push(Diagnostic::error(
+                    format!("Entity `{}` extends itself", entity.name),
+                    extends.parent.span,
+                ));
+                break;
+            }
+            if !seen.insert(name) {
+                break;
+            }
+            parent = entities
+                .get(name)
+                .and_then(|e| e.extends.as_ref())
+                .map(|x| x.parent.as_str());
+        }
+
+        let (disjointness, completeness) = markers.entry(extends.parent.as_str()).or_default();
+        let disagrees = matches!((*disjointness, extends.disjointness), (Some(a), Some(b)) if a != b)
+            || matches!((*completeness, extends.completeness), (Some(a), Some(b)) if a != b);
+        if disagrees {
+            diagnostics.push(Diagnostic::warning(
+                format!(
+                    "Subtypes of `{}` disagree on whether they are disjoint or total",
+                    extends.parent.name
+                ),
+                extends.parent.span,
+            ));
+        }
+        *disjointness = disjointness.or(extends.disjointness);
+        *completeness = completeness.or(extends.completeness);
+    }
+}
+
+/// A default an enum-typed column can never hold is only a warning too.
+fn check_enum_defaults(entity: &Entity, enums: &HashMap<&str, &Enum>, diagnostics: &mut Vec<Diagnostic>) {
+    for column in &entity.columns {
+        let Some(e) = enums.get(column.typ.name.as_str()) else {
+            continue;
+        };
+        for modifier in &column.modifiers {
+            if let ColumnModifier::Default(value) = modifier
+                && column.typ.array_dims == 0
+                && !e.values.contains(value)
+            {
+                diagnostics.push(Diagnostic::warning(
+                    format!("Default `{}` of `{}` is not a value of enum `{}`", value, column.name, e.name),
+                    column.span,
+                ));
+            }
+        }
+    }
+}
+
+/// Style hints the renderer cannot use are drawn with the defaults, so they
+/// are warnings.
+fn check_entity_style(entity: &Entity, diagnostics: &mut Vec<Diagnostic>) {
+    for hint in &entity.hints {
+        if matches!(hint.key.as_str(), "hint.color" | "hint.header" | "hint.stroke")
+            && color_value(&hint.value).is_none()
+        {
+            diagnostics.push(Diagnostic::warning(
+                format!("`@{}` is not a colour; expected a name or a quoted \"#rrggbb\"", hint.key),
+                hint.span,
+            ));
+        }
+    }
+}
+
+/// `hint.group.NAME.color`, which colours the region of group `NAME`.
+fn is_group_color(key: &str) -> bool {
+    key.strip_prefix("hint.group.")
+        .and_then(|rest| rest.strip_suffix(".color"))
+        .is_some_and(|name| !name.is_empty())
+}
+
+fn check_relationship_style(rel: &Relationship, diagnostics: &mut Vec<Diagnostic>) {
+    for attribute in &rel.attributes {
+        let message = match (attribute.name.as_str(), &attribute.value) {
+            ("dashed", None) => continue,
+            ("dashed", Some(_)) => "`dashed` takes no value".to_string(),
+            ("color", value) if value.as_ref().and_then(color_value).is_some() => continue,
+            ("color", _) => "`color` is not a colour; expected a name or a quoted \"#rrggbb\"".to_string(),
+            ("width", value) if value.as_ref().and_then(width_value).is_some() => continue,
+            ("width", _) => "`width` is not a positive number".to_string(),
+            (name, _) => format!("Unknown relationship attribute `{}`; expected dashed, color or width", name),
+        };
+        diagnostics.push(Diagnostic::warning(message, attribute.span));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+    use crate::parser::Parser;
+
+    fn check(input: &str) -> Vec<Diagnostic> {
+        validate(&Parser::new(input).unwrap().parse().unwrap())
+    }
+
+    #[test]
+    fn test_valid_schema() {
+        let input = r#"
+            entity User { id int pk }
+            entity Order {
+                id int pk
+                user_id int fk -> User.id
+            }
+            rel { User 1 -- * Order }
+            view core { include User, Order }
+            @hint.arrangement = { User; Order }
+            @hint.direction = LR
+            @hint.anchors = columns
+            @hint.edges = fk
+            @hint.notes = show
+            @hint.enums = show
+            @hint.subtypes = idef1x
+            @hint.inherited = show
+            @hint.joins = collapse
+            @hint.ghosts = show
+            @hint.detail = pk_fk
+            @hint.notation = text
+        "#;
+        assert!(check(input).is_empty());
+    }
+
+    #[test]
+    fn test_supertypes() {
+        let input = "entity Party { id int pk }\n\
+                     entity Person extends Party disjoint {}\n\
+                     entity Company extends Party overlapping {}\n\
+                     entity Robot extends Machine {}\n\
+                     entity A extends B {}\n\
+                     entity B extends A {}\n";
+        let diagnostics = check(input);
+
+        let found: Vec<(Severity, usize)> = diagnostics.iter().map(|d| (d.severity, d.span.line)).collect();
+        assert_eq!(
+            found,
+            vec![(Severity::Warning, 3), (Severity::Error, 4), (Severity::Error, 5), (Severity::Error, 6)]
+        );
+        assert!(diagnostics[1].message.contains("`Machine`"));
+        assert!(diagnostics[2].message.contains("`A` extends itself"));
+    }
+
+    #[test]
+    fn test_enums() {
+        let input = "enum Status { pending, paid }
+                     enum Status { open }
+                     enum User { a, b, a }
+                     entity User {
+    status Status default pending
+    next Status default gone
+}
+";
+        let diagnostics = check(input);
+
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.span.line).collect();
+        assert_eq!(lines, vec![2, 3, 3, 6]);
+        assert_eq!(diagnostics[0].notes[0].1.line, 1);
+        assert!(diagnostics[1].message.contains("name of an entity"));
+        assert!(diagnostics[2].message.contains("`a` more than once"));
+        assert!(!diagnostics[3].is_error());
+        assert!(diagnostics[3].message.contains("`gone`"));
+    }
+
+    #[test]
+    fn test_styles() {
+        let input = r##"entity User {
+    @hint.color = "#ffd"
+    @hint.header = "#36c"
+    @hint.stroke = "red; }"
+}
+rel {
+    User 1 -- * User [dashed, color = red, width = 2]
+    User 1 -- * User [dashed = yes, color, width = "0", bold]
+}
+@hint.group.billing.color = "#e8a33d"
+@hint.group.core.color = "red</style>"
+"##;
+        let diagnostics = check(input);
+
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.span.line).collect();
+        assert_eq!(lines, vec![4, 8, 8, 8, 8, 11]);
+        assert!(diagnostics.iter().all(|d| !d.is_error()));
+        assert!(diagnostics[0].message.contains("`@hint.stroke` is not a colour"));
+        assert!(diagnostics[4].message.contains("`bold`"));
+        assert!(diagnostics[5].message.contains("`@hint.group.core.color` is not a colour"));
+    }
+
+    #[test]
+    fn test_undefined_entities() {
+        let input = "entity User { id int pk }\n\
+                     rel { User 1 -- * Ordr }\n\
+                     view core { include User, Ordr }\n\
+                     @hint.arrangement = { User Ordr }\n\
+                     rel { User * -- * User via Follows }\n";
+        let diagnostics = check(input);
+
+        let found: Vec<(Severity, usize)> = diagnostics.iter().map(|d| (d.severity, d.span.line)).collect();
+        assert_eq!(
+            found,
+            vec![(Severity::Error, 2), (Severity::Error, 3), (Severity::Warning, 4), (Severity::Error, 5)]
+        );
+        assert_eq!(&input[diagnostics[1].span.start..diagnostics[1].span.end], "Ordr");
+        assert!(diagnostics[0].message.contains("`Ordr`"));
+        assert!(diagnostics[3].message.contains("through undefined entity `Follows`"));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let input = "entity User {\n    id int pk\n    id int\n}\n\
+                     entity User { id int pk }\n\
+                     @hint.arrangement = { User; User }\n\
+                     view v { include User }\n\
+                     view v { include User }\n";
+        let diagnostics = check(input);
+
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.span.line).collect();
+        assert_eq!(lines, vec![3, 5, 6, 8]);
+        assert_eq!(diagnostics[1].notes[0].1.line, 1);
+        assert!(!diagnostics[2].is_error());
+        assert_eq!(diagnostics[3].notes[0].1.line, 7);
+    }
+
+    #[test]
+    fn test_foreign_key_targets() {
+        let input = "entity User { id int pk }\n\
+                     entity Order {\n    user_id int fk -> User.uid\n    shop_id int fk -> Shop.id\n}\n";
+        let diagnostics = check(input);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+        assert!(diagnostics[0].message.contains("`User.uid`"));
+        assert!(diagnostics[1].message.contains("`Shop`"));
+    }
+
+    #[test]
+    fn test_unknown_direction() {
+        let diagnostics = check("@hint.direction = sideways\nentity User { id int pk }\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(!diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].span.line, 1);
+    }
+
+    #[test]
+    fn test_view_hints() {
+        let input = "entity User { id int pk }\n\
+                     view v {\n\
+                         include User\n\
+                         @hint.arrangement = { User Ghost }\n\
+                         @hint.detail = everything\n\
+                         @hint.notation = text\n\
+                     }\n";
+        let diagnostics = check(input);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].message.contains("`Ghost`"));
+        assert!(diagnostics[1].message.starts_with("Unknown detail level"));
+        assert_eq!(diagnostics[1].span.line, 5);
+    }
+
+    #[test]
+    fn test_relationship_columns() {
+        let input = "entity User { id int pk }\n\
+                     entity Order { user_id int }\n\
+                     rel { User.id 1 -- * Order.usr_id }\n\
+                     @hint.anchors = rows\n";
+        let diagnostics = check(input);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+        assert!(diagnostics[0].message.contains("`Order.usr_id`"));
+        assert_eq!(diagnostics[1].span.line, 4);
+    }
+}
@@ -0,0 +1,560 @@
+//! Which entities a view shows.
+//!
+//! A view starts from the entities of the view it extends, or from nothing,
+//! and applies its `include` and `exclude` lines in order. A view whose first
+//! line is an `exclude` and that extends nothing starts from every entity
+//! instead, so `exclude *_audit` alone hides the audit tables. Resolution
+//! happens before the graph is built; [`crate::ir::GraphIR::from_schema`]
+//! only keeps the entities it is given.
+//!
+//! A view may also carry hints and an arrangement of its own. They apply over
+//! the file's, and a view that extends another inherits those of its base.
+//!
+//! A [`Focus`] is a view put together when rendering, from `--focus`,
+//! `--include` and `--exclude`, rather than written in the file.
+
+use crate::ast::{ColumnModifier, Constraint, Hint, HintValue, Name, Schema, Selector, View, ViewRule};
+use crate::diagnostic::{Diagnostic, Span};
+use std::collections::{HashMap, HashSet};
+
+/// The entities one view shows, and what is wrong with the view.
+#[derive(Debug, Clone)]
+pub struct ResolvedView {
+    pub name: String,
+    /// In the order the schema defines them
+    pub entities: Vec<String>,
+    /// The view's own arrangement, or else the nearest one among the views
+    /// it extends
+    pub arrangement: Option<Vec<Vec<Name>>>,
+    /// The hints of the views it extends, base first, then its own
+    pub hints: Vec<Hint>,
+    /// Undefined names and views are errors; patterns, neighbourhoods and
+    /// groups that match nothing are warnings.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ResolvedView {
+    pub fn contains(&self, entity: &str) -> bool {
+        self.entities.iter().any(|e| e == entity)
+    }
+}
+
+/// Entities picked out when rendering: the neighbourhood of `focus`, then
+/// `include`, less `exclude`. Each entry is a name or a `*` pattern.
+#[derive(Debug, Clone)]
+pub struct Focus {
+    pub focus: Vec<String>,
+    /// How many links out from `focus` to go
+    pub depth: usize,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self {
+            focus: Vec::new(),
+            depth: 1,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl Focus {
+    /// Name of the view a focus stands for. No view in a file can be called
+    /// this; its diagnostics name the flags instead.
+    pub const VIEW_NAME: &'static str = "(focus)";
+
+    pub fn is_empty(&self) -> bool {
+        self.focus.is_empty() && self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// The view these selections make, as if written in the file. With only
+    /// `exclude`, it starts from every entity.
+    pub fn to_view(&self) -> View {
+        let names = |patterns: &[String]| -> Vec<Name> {
+            patterns
+                .iter()
+                .map(|p| Name {
+                    name: p.clone(),
+                    span: Span::default(),
+                })
+                .collect()
+        };
+        let neighbours = names(&self.focus)
+            .into_iter()
+            .map(|pattern| Selector::Neighbours {
+                pattern,
+                depth: self.depth,
+            });
+        let included: Vec<Selector> = neighbours
+            .chain(names(&self.include).into_iter().map(Selector::Pattern))
+            .collect();
+        let excluded: Vec<Selector> = names(&self.exclude).into_iter().map(Selector::Pattern).collect();
+
+        let rules = [(false, included), (true, excluded)]
+            .into_iter()
+            .filter(|(_, selectors)| !selectors.is_empty())
+            .map(|(exclude, selectors)| ViewRule { exclude, selectors })
+            .collect();
+        View {
+            name: Self::VIEW_NAME.to_string(),
+            extends: None,
+            rules,
+            arrangement: None,
+            hints: Vec::new(),
+            span: Span::default(),
+        }
+    }
+}
+
+/// Resolve `focus`, narrowing `within` to the entities it picks out when a
+/// view is given too. The view keeps its arrangement and hints. Leaving
+/// nothing to draw is a warning.
+pub fn resolve_focus(schema: &Schema, focus: &Focus, within: Option<ResolvedView>) -> ResolvedView {
+    let picked = resolve(schema, &focus.to_view());
+    match within {
+        Some(mut view) => {
+            let shown = !view.entities.is_empty();
+            view.entities.retain(|e| picked.contains(e));
+            view.diagnostics.extend(picked.diagnostics);
+            if shown && view.entities.is_empty() {
+                view.diagnostics.push(Diagnostic::warning(
+                    format!("`--focus`, `--include` and `--exclude` leave no entity of view `{}`", view.name),
+                    Span::default(),
+                ));
+            }
+            view
+        }
+        None => {
+            let mut picked = picked;
+            if picked.entities.is_empty() && !schema.entities.is_empty() {
+                picked.diagnostics.push(Diagnostic::warning(
+                    "`--focus`, `--include` and `--exclude` leave no entity",
+                    Span::default(),
+                ));
+            }
+            picked
+        }
+    }
+}
+
+/// The value of a word or string hint, taken from `view` when it sets one
+/// and from the file otherwise.
+pub fn hint_str<'a>(schema: &'a Schema, view: Option<&'a ResolvedView>, key: &str) -> Option<&'a str> {
+    let own = view.and_then(|v| {
+        v.hints.iter().rev().find(|h| h.key == key).and_then(|h| match &h.value {
+            HintValue::Ident(s) | HintValue::Str(s) => Some(s.as_str()),
+            HintValue::Int(_) => None,
+        })
+    });
+    own.or_else(|| schema.hint_str(key))
+}
+
+/// Resolve the view called `name`, if the schema has one.
+pub fn resolve_named(schema: &Schema, name: &str) -> Option<ResolvedView> {
+    schema.find_view(name).map(|view| resolve(schema, view))
+}
+
+pub fn resolve(schema: &Schema, view: &View) -> ResolvedView {
+    let mut diagnostics = Vec::new();
+    let selected = select(schema, view, &mut vec![view.name.as_str()], true, &mut diagnostics);
+    let lineage = lineage(schema, view);
+    ResolvedView {
+        name: view.name.clone(),
+        entities: schema
+            .entities
+            .iter()
+            .filter(|e| selected.contains(e.name.as_str()))
+            .map(|e| e.name.clone())
+            .collect(),
+        arrangement: lineage.iter().find_map(|v| v.arrangement.clone()),
+        hints: lineage.iter().rev().flat_map(|v| v.hints.iter().cloned()).collect(),
+        diagnostics,
+    }
+}
+
+/// `view` and the views it extends, nearest first, up to an undefined view
+/// or back to one already listed.
+fn lineage<'a>(schema: &'a Schema, view: &'a View) -> Vec<&'a View> {
+    let mut lineage = vec![view];
+    let mut next = view.extends.as_ref();
+    while let Some(base) = next.and_then(|name| schema.find_view(name.as_str())) {
+        if lineage.iter().any(|v| v.name == base.name) {
+            break;
+        }
+        lineage.push(base);
+        next = base.extends.as_ref();
+    }
+    lineage
+}
+
+/// The entities `view` selects. `stack` holds the views being resolved, the
+/// one asked for first. Problems are reported only for that one, apart from
+/// a cycle through it, which may be found while resolving a view it extends.
+fn select<'a>(
+    schema: &'a Schema,
+    view: &'a View,
+    stack: &mut Vec<&'a str>,
+    report: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> HashSet<&'a str> {
+    let mut selected = HashSet::new();
+    if let Some(base) = &view.extends {
+        match schema.find_view(base.as_str()) {
+            None if report => diagnostics.push(Diagnostic::error(
+                format!("View `{}` extends undefined view `{}`", view.name, base.name),
+                base.span,
+            )),
+            None => {}
+            Some(_) if stack[0] == base.as_str() => diagnostics.push(Diagnostic::error(
+                format!("View `{}` extends itself: {} -> {}", stack[0], stack.join(" -> "), base.name),
+                base.span,
+            )),
+            // A cycle the view only leads into is reported by the views in it.
+            Some(_) if stack.contains(&base.as_str()) => {}
+            Some(parent) => {
+                stack.push(&parent.name);
+                selected = select(schema, parent, stack, false, diagnostics);
+                stack.pop();
+            }
+        }
+    } else if view.rules.first().is_some_and(|rule| rule.exclude) {
+        selected = schema.entities.iter().map(|e| e.name.as_str()).collect();
+    }
+
+    let links = links(schema);
+    for rule in &view.rules {
+        for selector in &rule.selectors {
+            let mut problems = Vec::new();
+            let matched = matching(schema, &links, view, rule.exclude, selector, &mut problems);
+            if report {
+                diagnostics.extend(problems);
+            }
+            if rule.exclude {
+                selected.retain(|e| !matched.contains(e));
+            } else {
+                selected.extend(matched);
+            }
+        }
+    }
+    selected
+}
+
+/// The entities one selector picks out.
+fn matching<'a>(
+    schema: &'a Schema,
+    links: &HashMap<&'a str, Vec<&'a str>>,
+    view: &View,
+    exclude: bool,
+    selector: &Selector,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> HashSet<&'a str> {
+    // A focus is no view the user wrote, so its problems name the flag.
+    let flag = (view.name == Focus::VIEW_NAME).then_some(match selector {
+        Selector::Neighbours { .. } => "--focus",
+        _ if exclude => "--exclude",
+        _ => "--include",
+    });
+    match selector {
+        Selector::Pattern(pattern) => matching_pattern(schema, view, flag, exclude, pattern, diagnostics),
+        Selector::Neighbours { pattern, depth } => {
+            let mut reached = matching_pattern(schema, view, flag, exclude, pattern, diagnostics);
+            let mut frontier: Vec<&str> = reached.iter().copied().collect();
+            for _ in 0..*depth {
+                frontier = frontier
+                    .iter()
+                    .flat_map(|e| links.get(e).into_iter().flatten().copied())
+                    .filter(|e| reached.insert(e))
+                    .collect();
+            }
+            reached
+        }
+        Selector::Group(group) => {
+            let members: HashSet<&str> = schema
+                .entities
+                .iter()
+                .filter(|e| {
+                    e.hints.iter().any(|h| {
+                        h.key == "hint.group"
+                            && matches!(&h.value, HintValue::Str(s) | HintValue::Ident(s) if *s == group.name)
+                    })
+                })
+                .map(|e| e.name.as_str())
+                .collect();
+            if members.is_empty() {
+                diagnostics.push(Diagnostic::warning(
+                    format!("View `{}`: no entity is in group `{}`", view.name, group.name),
+                    group.span,
+                ));
+            }
+            members
+        }
+    }
+}
+
+/// The entities a name or wildcard pattern matches. A plain name that is not
+/// an entity is an error, as a misspelling would be; a pattern that matches
+/// nothing is only a warning. `flag` is the command-line flag a focus's
+/// pattern came from.
+fn matching_pattern<'a>(
+    schema: &'a Schema,
+    view: &View,
+    flag: Option<&str>,
+    exclude: bool,
+    pattern: &Name,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> HashSet<&'a str> {
+    let matched: HashSet<&str> = schema
+        .entities
+        .iter()
+        .map(|e| e.name.as_str())
+        .filter(|name| glob(&pattern.name, name))
+        .collect();
+    if matched.is_empty() {
+        let verb = if exclude { "excludes" } else { "includes" };
+        diagnostics.push(match (pattern.name.contains('*'), flag) {
+            (true, Some(flag)) => Diagnostic::warning(
+                format!("`{}` pattern `{}` matches no entity", flag, pattern.name),
+                pattern.span,
+            ),
+            (true, None) => Diagnostic::warning(
+                format!("Pattern `{}` in view `{}` matches no entity", pattern.name, view.name),
+                pattern.span,
+            ),
+            (false, Some(flag)) => Diagnostic::error(
+                format!("`{}` names undefined entity `{}`", flag, pattern.name),
+                pattern.span,
+            ),
+            (false, None) => Diagnostic::error(
+                format!("View `{}` {} undefined entity `{}`", view.name, verb, pattern.name),
+                pattern.span,
+            ),
+        });
+    }
+    matched
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any run of
+/// characters, none included.
+fn glob(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let [first, middle @ .., last] = parts.as_slice() else {
+        return pattern == name;
+    };
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// The entities each entity is linked to, in either direction, by a
+/// relationship, a foreign key or `extends`. A relationship through a join
+/// entity links all three.
+fn links(schema: &Schema) -> HashMap<&str, Vec<&str>> {
+    let mut links: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut link = |a, b| {
+        links.entry(a).or_default().push(b);
+        links.entry(b).or_default().push(a);
+    };
+    for rel in &schema.relationships {
+        link(&rel.left, &rel.right);
+        if let Some(join) = &rel.via {
+            link(&rel.left, join);
+            link(&rel.right, join);
+        }
+    }
+    for entity in &schema.entities {
+        for column in &entity.columns {
+            for modifier in &column.modifiers {
+                if let ColumnModifier::Fk { target, .. } = modifier {
+                    link(&entity.name, target);
+                }
+            }
+        }
+        for constraint in &entity.constraints {
+            if let Constraint::ForeignKey { target, .. } = constraint {
+                link(&entity.name, target);
+            }
+        }
+        if let Some(extends) = &entity.extends {
+            link(&entity.name, extends.parent.as_str());
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn resolved(input: &str, view: &str) -> ResolvedView {
+        let schema = Parser::new(input).unwrap().parse().unwrap();
+        resolve_named(&schema, view).unwrap()
+    }
+
+    const SCHEMA: &str = r#"
+        entity User { id int pk }
+        entity Order { id int pk
+            user_id int fk -> User.id }
+        entity OrderItem { order_id int fk -> Order.id
+            product_id int fk -> Product.id }
+        entity Product { id int pk }
+        entity Order_audit { order_id int }
+        entity Invoice {
+            @hint.group = billing
+            order_id int fk -> Order.id
+        }
+        entity Payment {
+            @hint.group = billing
+            invoice_id int fk -> Invoice.id
+        }
+    "#;
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("Order*", "Order"));
+        assert!(glob("Order*", "OrderItem"));
+        assert!(glob("*_audit", "Order_audit"));
+        assert!(glob("O*r*m", "OrderItem"));
+        assert!(glob("*", "User"));
+        assert!(!glob("Order*", "User"));
+        assert!(!glob("*_audit", "Order"));
+        assert!(!glob("Or*der", "Order_audit"));
+        assert!(!glob("Order", "OrderItem"));
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        let input = format!("{SCHEMA}\nview orders {{ include Order*\n exclude *_audit }}\n");
+        assert_eq!(resolved(&input, "orders").entities, vec!["Order", "OrderItem"]);
+
+        // Starting with `exclude` starts from every entity.
+        let input = format!("{SCHEMA}\nview live {{ exclude *_audit, group billing }}\n");
+        assert_eq!(resolved(&input, "live").entities, vec!["User", "Order", "OrderItem", "Product"]);
+    }
+
+    #[test]
+    fn test_neighbours_and_groups() {
+        let input = format!(
+            "{SCHEMA}\nview near {{ include neighbours(Product) }}\nview far {{ include neighbours(Product, 2) }}\n\
+             view billing {{ include group \"billing\" }}\n"
+        );
+        assert_eq!(resolved(&input, "near").entities, vec!["OrderItem", "Product"]);
+        assert_eq!(resolved(&input, "far").entities, vec!["Order", "OrderItem", "Product"]);
+        assert_eq!(resolved(&input, "billing").entities, vec!["Invoice", "Payment"]);
+    }
+
+    #[test]
+    fn test_extends() {
+        let input = format!(
+            "{SCHEMA}\nview core {{ include User, Order }}\nview sales extends core {{ include OrderItem\n exclude User }}\n"
+        );
+        assert_eq!(resolved(&input, "sales").entities, vec!["Order", "OrderItem"]);
+    }
+
+    #[test]
+    fn test_view_hints() {
+        let input = format!(
+            "{SCHEMA}\n@hint.direction = LR\n@hint.notation = text\n\
+             view core {{\n  @hint.detail = pk\n  @hint.arrangement = {{ User; Order }}\n  include User, Order\n}}\n\
+             view sales extends core {{\n  @hint.direction = TB\n  include OrderItem\n}}\n"
+        );
+        let schema = Parser::new(&input).unwrap().parse().unwrap();
+        let sales = resolve_named(&schema, "sales").unwrap();
+
+        assert_eq!(hint_str(&schema, Some(&sales), "hint.direction"), Some("TB"));
+        assert_eq!(hint_str(&schema, Some(&sales), "hint.detail"), Some("pk"));
+        assert_eq!(hint_str(&schema, Some(&sales), "hint.notation"), Some("text"));
+        assert_eq!(hint_str(&schema, None, "hint.direction"), Some("LR"));
+        let rows: Vec<Vec<&str>> = sales
+            .arrangement
+            .iter()
+            .flatten()
+            .map(|row| row.iter().map(|n| n.as_str()).collect())
+            .collect();
+        assert_eq!(rows, vec![vec!["User"], vec!["Order"]]);
+        assert_eq!(schema.arrangement, None);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let input = format!(
+            "{SCHEMA}\nview a extends b {{ include Ordr, Customer*, group hr }}\nview b extends a {{ exclude Invoice }}\n\
+             view c extends a {{ include User }}\nview d extends nowhere {{ include User }}\n"
+        );
+        let messages = |view: &str| -> Vec<String> {
+            resolved(&input, view)
+                .diagnostics
+                .iter()
+                .map(|d| format!("{}: {}", d.severity, d.message))
+                .collect()
+        };
+        assert_eq!(
+            messages("a"),
+            vec![
+                "error: View `a` extends itself: a -> b -> a",
+                "error: View `a` includes undefined entity `Ordr`",
+                "warning: Pattern `Customer*` in view `a` matches no entity",
+                "warning: View `a`: no entity is in group `hr`",
+            ]
+        );
+        // Only the views in the cycle report it, and only their own problems.
+        assert_eq!(messages("c"), Vec::<String>::new());
+        assert_eq!(messages("d"), vec!["error: View `d` extends undefined view `nowhere`"]);
+    }
+
+    #[test]
+    fn test_focus() {
+        let input = format!("{SCHEMA}
+view sales {{ include User, Order, Invoice
+ @hint.detail = pk }}
+");
+        let schema = Parser::new(&input).unwrap().parse().unwrap();
+        let focus = |focus: &[&str], depth: usize, include: &[&str], exclude: &[&str]| Focus {
+            focus: focus.iter().map(|s| s.to_string()).collect(),
+            depth,
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+        };
+
+        let near = resolve_focus(&schema, &focus(&["Invoice"], 1, &[], &[]), None);
+        assert_eq!(near.entities, vec!["Order", "Invoice", "Payment"]);
+        let far = resolve_focus(&schema, &focus(&["Invoice"], 2, &["Product"], &["Order*"]), None);
+        assert_eq!(far.entities, vec!["User", "Product", "Invoice", "Payment"]);
+        // Excluding alone starts from every entity.
+        let rest = resolve_focus(&schema, &focus(&[], 1, &[], &["Order*", "Invoice", "Payment"]), None);
+        assert_eq!(rest.entities, vec!["User", "Product"]);
+
+        // Within a view, only what both show is kept, with the view's hints.
+        let within = resolve_focus(&schema, &focus(&["Invoice"], 1, &[], &[]), resolve_named(&schema, "sales"));
+        assert_eq!(within.entities, vec!["Order", "Invoice"]);
+        assert_eq!(hint_str(&schema, Some(&within), "hint.detail"), Some("pk"));
+
+        let typo = resolve_focus(&schema, &focus(&["Invoce"], 1, &[], &[]), None);
+        assert_eq!(typo.diagnostics[0].message, "`--focus` names undefined entity `Invoce`");
+        let nothing = resolve_focus(&schema, &focus(&[], 1, &["Zz*"], &[]), None);
+        let messages: Vec<&str> = nothing.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`--include` pattern `Zz*` matches no entity",
+                "`--focus`, `--include` and `--exclude` leave no entity",
+            ]
+        );
+        let apart = resolve_focus(&schema, &focus(&["Product"], 0, &[], &[]), resolve_named(&schema, "sales"));
+        assert!(apart.entities.is_empty());
+        assert_eq!(
+            apart.diagnostics[0].message,
+            "`--focus`, `--include` and `--exclude` leave no entity of view `sales`"
+        );
+    }
+}
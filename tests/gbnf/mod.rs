@@ -335,7 +335,10 @@ fn read_literal(chars: &[char], mut i: usize) -> Result<(String, usize), String>
     Err("unterminated literal".to_string())
 }
 
-fn read_class(chars: &[char], mut i: usize) -> Result<(bool, Vec<(char, char)>, usize), String> {
+/// A character class: whether it is negated, its ranges, and the index after it.
+type Class = (bool, Vec<(char, char)>, usize);
+
+fn read_class(chars: &[char], mut i: usize) -> Result<Class, String> {
     let negated = chars.get(i) == Some(&'^');
     if negated {
         i += 1;
@@ -1,14 +1,15 @@
 //! Routing quality checks over the bundled examples.
 
-use rusterd::ir::{DetailLevel, GraphIR};
-use rusterd::layout::{Layout, LayoutEngine, LayoutNode};
+use rusterd::layout::{Direction, Layout, LayoutNode};
 use rusterd::parser::Parser;
+use rusterd::render::{self, RenderOptions};
 use std::fs;
 use std::path::PathBuf;
 
 /// Detours shorter than this read as an accidental wiggle rather than a turn.
 const MIN_JOG: f64 = 20.0;
 
+/// Every example, laid out in every direction.
 fn examples() -> Vec<(String, Layout)> {
     let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples");
     let mut paths: Vec<PathBuf> = fs::read_dir(dir)
@@ -19,17 +20,29 @@ fn examples() -> Vec<(String, Layout)> {
     paths.sort();
     assert!(!paths.is_empty(), "no examples found");
 
+    let directions = [
+        Direction::TopDown,
+        Direction::LeftRight,
+        Direction::BottomUp,
+        Direction::RightLeft,
+    ];
     paths
         .into_iter()
-        .map(|path| {
+        .flat_map(|path| {
             let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
             let input = fs::read_to_string(&path).expect(&name);
             let schema = Parser::new(&input)
                 .expect(&name)
                 .parse()
                 .unwrap_or_else(|e| panic!("{name}: {e}"));
-            let ir = GraphIR::from_schema(&schema, None, DetailLevel::All);
-            (name, LayoutEngine::default().layout(&ir))
+            directions.map(|d| {
+                let options = RenderOptions {
+                    direction: Some(d),
+                    ..RenderOptions::default()
+                };
+                let diagram = render::layout(&schema, &options).unwrap_or_else(|e| panic!("{name}: {e:?}"));
+                (format!("{name} ({d:?})"), diagram.layout)
+            })
         })
         .collect()
 }
@@ -91,8 +104,20 @@ fn paths_have_no_redundant_waypoints() {
 #[test]
 fn paths_do_not_cross_entities() {
     for (name, layout) in examples() {
+        // Notes are in the way just as much, their own entity's edges included.
+        let notes: Vec<LayoutNode> = layout
+            .notes
+            .iter()
+            .map(|n| LayoutNode {
+                id: format!("the note on {}", n.id),
+                x: n.x,
+                y: n.y,
+                width: n.width,
+                height: n.height,
+            })
+            .collect();
         for edge in &layout.edges {
-            for node in &layout.nodes {
+            for node in layout.nodes.iter().chain(&notes) {
                 // The endpoints touch their own entities by design.
                 if node.id == edge.from || node.id == edge.to {
                     continue;
@@ -111,3 +136,35 @@ fn paths_do_not_cross_entities() {
         }
     }
 }
+
+/// A schema of `count` unhinted entities, each hanging from one or two
+/// earlier ones, picked by a fixed pseudo-random sequence.
+fn generated_schema(count: usize, seed: u64) -> String {
+    let mut state = seed;
+    let mut next = |bound: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) as usize) % bound
+    };
+    let mut source = String::new();
+    for i in 0..count {
+        source.push_str(&format!("entity T{i} {{ id int pk }}\n"));
+    }
+    source.push_str("rel {\n");
+    for i in 1..count {
+        for _ in 0..1 + next(2) {
+            source.push_str(&format!("    T{} 1 -- * T{i}\n", next(i)));
+        }
+    }
+    source.push_str("}\n");
+    source
+}
+
+#[test]
+fn large_unhinted_schemas_lay_out() {
+    for seed in 0..8 {
+        let input = generated_schema(100, seed);
+        let schema = Parser::new(&input).unwrap().parse().unwrap();
+        let diagram = render::layout(&schema, &RenderOptions::default()).unwrap();
+        assert_eq!(diagram.layout.nodes.len(), 100, "seed {seed}");
+    }
+}